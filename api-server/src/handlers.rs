@@ -1,23 +1,25 @@
 use std::collections::HashMap;
 
-use cosmscan_models::storage::StorageReader;
+use cosmscan_models::{models::transaction::Transaction, storage::Storage};
 use hyper::{Body, Request, Response};
 use url::Url;
 
 use crate::{responses, AppState, GenericError};
 
-pub async fn all_chains(_: Request<Body>, state: AppState) -> Result<Response<Body>, GenericError> {
-    let storage = state.storage;
-    let chains = storage.all_chains()?;
+pub async fn all_chains<S: Storage + Send + Sync + 'static>(
+    _: Request<Body>,
+    state: AppState<S>,
+) -> Result<Response<Body>, GenericError> {
+    let chains = state.storage.run(|storage| storage.all_chains()).await?;
     let json = serde_json::to_string(&chains)?;
 
     state.resp_builder.ok_json(json)
 }
 
 /// Returns a block by height.
-pub async fn get_block(
+pub async fn get_block<S: Storage + Send + Sync + 'static>(
     _req: Request<Body>,
-    state: AppState,
+    state: AppState<S>,
 ) -> Result<Response<Body>, GenericError> {
     let chain_id = match state.params.find("chain_id") {
         Some(chain_id) => chain_id.parse::<i32>()?,
@@ -31,25 +33,29 @@ pub async fn get_block(
         }
     };
 
-    let storage = state.storage;
-    let block = storage.find_block_by_height(chain_id, block_height)?;
+    let block = state
+        .storage
+        .run(move |storage| storage.find_block_by_height(chain_id, block_height))
+        .await?;
     let json = serde_json::to_string(&block)?;
 
     state.resp_builder.ok_json(json)
 }
 
 /// Returns a latestblock
-pub async fn latest_block(
+pub async fn latest_block<S: Storage + Send + Sync + 'static>(
     _: Request<Body>,
-    state: AppState,
+    state: AppState<S>,
 ) -> Result<Response<Body>, GenericError> {
     let chain_id = match state.params.find("chain_id") {
         Some(chain_id) => chain_id.parse::<i32>()?,
         None => return state.resp_builder.invalid_form("chain_id is required"),
     };
 
-    let storage = state.storage;
-    let block = storage.find_latest_block(chain_id)?;
+    let block = state
+        .storage
+        .run(move |storage| storage.find_latest_block(chain_id))
+        .await?;
     let json = serde_json::to_string(&block)?;
 
     state.resp_builder.ok_json(json)
@@ -57,9 +63,9 @@ pub async fn latest_block(
 
 /// Returns list of blocks by given chain_id
 /// it's sorted by height in descending order
-pub async fn block_list(
+pub async fn block_list<S: Storage + Send + Sync + 'static>(
     req: Request<Body>,
-    state: AppState,
+    state: AppState<S>,
 ) -> Result<Response<Body>, GenericError> {
     // parse limit and offset from query string
     let uri = req.uri().to_string().parse::<hyper::Uri>()?;
@@ -89,17 +95,19 @@ pub async fn block_list(
         }
     };
 
-    let storage = state.storage;
-    let blocks = storage.list_blocks(chain_id, limit, offset)?;
+    let blocks = state
+        .storage
+        .run(move |storage| storage.list_blocks(chain_id, limit, offset))
+        .await?;
     let json = serde_json::to_string(&blocks)?;
 
     state.resp_builder.ok_json(json)
 }
 
-/// Returns the transaction by hash
-pub async fn transaction_by_hash(
+/// Returns the transactions with the given hash in every chain
+pub async fn transactions_by_hash<S: Storage + Send + Sync + 'static>(
     _: Request<Body>,
-    state: AppState,
+    state: AppState<S>,
 ) -> Result<Response<Body>, GenericError> {
     let tx_hash = match state.params.find("tx_hash") {
         Some(tx_hash) => tx_hash.to_string(),
@@ -108,20 +116,71 @@ pub async fn transaction_by_hash(
         }
     };
 
-    let storage = state.storage;
-    let tx = storage.find_transaction_by_hash(tx_hash)?;
-    let messages = storage.list_messages_by_tx(tx.id)?;
-    let events = storage.list_events_by_tx(tx.transaction_hash.clone())?;
-    let result = responses::Transaction::new(tx, events, messages);
+    let result = state
+        .storage
+        .run(move |storage| {
+            storage
+                .list_transactions_by_hash(tx_hash)?
+                .into_iter()
+                .map(|tx| transaction_detail(storage, tx))
+                .collect::<Result<Vec<_>, GenericError>>()
+        })
+        .await?;
     let json = serde_json::to_string(&result)?;
 
     state.resp_builder.ok_json(json)
 }
 
+/// Returns the transaction by chain_id and hash
+pub async fn transaction_by_hash<S: Storage + Send + Sync + 'static>(
+    _: Request<Body>,
+    state: AppState<S>,
+) -> Result<Response<Body>, GenericError> {
+    let chain_id = match state.params.find("chain_id") {
+        Some(chain_id) => chain_id.parse::<i32>()?,
+        None => {
+            return state.resp_builder.invalid_form("chain_id is missing");
+        }
+    };
+
+    let tx_hash = match state.params.find("tx_hash") {
+        Some(tx_hash) => tx_hash.to_string(),
+        None => {
+            return state.resp_builder.invalid_form("tx_hash is missing");
+        }
+    };
+
+    let result = state
+        .storage
+        .run(move |storage| {
+            let tx = storage.find_transaction_by_hash(chain_id, tx_hash)?;
+            transaction_detail(storage, tx)
+        })
+        .await?;
+    let json = serde_json::to_string(&result)?;
+
+    state.resp_builder.ok_json(json)
+}
+
+/// loads the messages, events, signers and fee coins of the transaction.
+fn transaction_detail<S: Storage>(
+    storage: &S,
+    tx: Transaction,
+) -> Result<responses::Transaction, GenericError> {
+    let messages = storage.list_messages_by_tx(tx.id)?;
+    let events = storage.list_events_by_tx(tx.chain_id, tx.transaction_hash.clone())?;
+    let signers = storage.list_signers_by_tx(tx.id)?;
+    let fees = storage.list_fees_by_tx(tx.id)?;
+
+    Ok(responses::Transaction::new(
+        tx, events, messages, signers, fees,
+    ))
+}
+
 /// Returns the transaction list by block height and chain_id
-pub async fn transaction_list_in_block(
+pub async fn transaction_list_in_block<S: Storage + Send + Sync + 'static>(
     _: Request<Body>,
-    state: AppState,
+    state: AppState<S>,
 ) -> Result<Response<Body>, GenericError> {
     let chain_id = match state.params.find("chain_id") {
         Some(chain_id) => chain_id.parse::<i32>()?,
@@ -137,8 +196,450 @@ pub async fn transaction_list_in_block(
         }
     };
 
-    let storage = state.storage;
-    let txes = storage.list_transactions(chain_id, block_height)?;
+    let txes = state
+        .storage
+        .run(move |storage| storage.list_transactions(chain_id, block_height))
+        .await?;
     let json = serde_json::to_string(&txes)?;
     state.resp_builder.ok_json(json)
 }
+
+/// Returns the account and its balances by chain_id and address
+pub async fn account_by_address<S: Storage + Send + Sync + 'static>(
+    _: Request<Body>,
+    state: AppState<S>,
+) -> Result<Response<Body>, GenericError> {
+    let chain_id = match state.params.find("chain_id") {
+        Some(chain_id) => chain_id.parse::<i32>()?,
+        None => {
+            return state.resp_builder.invalid_form("chain_id is missing");
+        }
+    };
+
+    let address = match state.params.find("address") {
+        Some(address) => address.to_string(),
+        None => {
+            return state.resp_builder.invalid_form("address is missing");
+        }
+    };
+
+    let result = state
+        .storage
+        .run(move |storage| {
+            let account = storage.find_account_by_address(chain_id, address)?;
+            let balances = storage.list_account_balances(account.id)?;
+            Ok::<_, GenericError>(responses::Account::new(account, balances))
+        })
+        .await?;
+    let json = serde_json::to_string(&result)?;
+
+    state.resp_builder.ok_json(json)
+}
+
+/// Returns the validator set which is active at the given block height
+pub async fn validator_set_at_height<S: Storage + Send + Sync + 'static>(
+    _: Request<Body>,
+    state: AppState<S>,
+) -> Result<Response<Body>, GenericError> {
+    let chain_id = match state.params.find("chain_id") {
+        Some(chain_id) => chain_id.parse::<i32>()?,
+        None => {
+            return state.resp_builder.invalid_form("chain_id is missing");
+        }
+    };
+
+    let block_height = match state.params.find("block_height") {
+        Some(block_height) => block_height.parse::<i64>()?,
+        None => {
+            return state.resp_builder.invalid_form("block_height is missing");
+        }
+    };
+
+    let result = state
+        .storage
+        .run(move |storage| validator_set_detail(storage, chain_id, block_height))
+        .await?;
+    let json = serde_json::to_string(&result)?;
+
+    state.resp_builder.ok_json(json)
+}
+
+/// Returns the validator set which is active at the latest block
+pub async fn latest_validator_set<S: Storage + Send + Sync + 'static>(
+    _: Request<Body>,
+    state: AppState<S>,
+) -> Result<Response<Body>, GenericError> {
+    let chain_id = match state.params.find("chain_id") {
+        Some(chain_id) => chain_id.parse::<i32>()?,
+        None => {
+            return state.resp_builder.invalid_form("chain_id is missing");
+        }
+    };
+
+    let result = state
+        .storage
+        .run(move |storage| validator_set_detail(storage, chain_id, i64::MAX))
+        .await?;
+    let json = serde_json::to_string(&result)?;
+
+    state.resp_builder.ok_json(json)
+}
+
+/// Returns the ranges of heights which cannot be indexed, e.g. pruned by the node
+pub async fn unindexable_ranges<S: Storage + Send + Sync + 'static>(
+    _: Request<Body>,
+    state: AppState<S>,
+) -> Result<Response<Body>, GenericError> {
+    let chain_id = match state.params.find("chain_id") {
+        Some(chain_id) => chain_id.parse::<i32>()?,
+        None => {
+            return state.resp_builder.invalid_form("chain_id is missing");
+        }
+    };
+
+    let ranges = state
+        .storage
+        .run(move |storage| storage.list_unindexable_ranges(chain_id))
+        .await?;
+    let json = serde_json::to_string(&ranges)?;
+
+    state.resp_builder.ok_json(json)
+}
+
+/// loads the validator set which is active at the given height, along with its members.
+fn validator_set_detail<S: Storage>(
+    storage: &S,
+    chain_id: i32,
+    block_height: i64,
+) -> Result<responses::ValidatorSet, GenericError> {
+    let validator_set = storage.find_validator_set_at_height(chain_id, block_height)?;
+    let members = storage.list_validator_set_members(validator_set.id)?;
+
+    Ok(responses::ValidatorSet::new(validator_set, members))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::NaiveDateTime;
+    use cosmscan_models::{
+        config::DBConfig,
+        memory_storage::MemoryStorage,
+        models::{
+            account::NewAccount,
+            account_balance::NewAccountBalance,
+            block::NewBlock,
+            chain::NewChain,
+            message::NewMessage,
+            transaction::NewTransaction,
+            transaction_fee::NewTransactionFee,
+            unindexable_range::NewUnindexableRange,
+            validator_set::{NewValidatorSet, NewValidatorSetMember},
+        },
+        storage::StorageWriter,
+    };
+    use hyper::StatusCode;
+
+    use crate::{
+        resputil::ResponseBuilder,
+        router::{self, RouterRegister},
+        server::ApiServer,
+        Config, ServerConfig,
+    };
+
+    use super::*;
+
+    fn timestamp() -> NaiveDateTime {
+        NaiveDateTime::from_timestamp(1_600_000_000, 0)
+    }
+
+    fn new_block(height: i64) -> NewBlock {
+        NewBlock {
+            chain_id: 1,
+            height,
+            block_hash: format!("HASH{}", height),
+            prev_hash: format!("HASH{}", height - 1),
+            proposer_address: "PROPOSER".to_string(),
+            last_commit_hash: String::new(),
+            data_hash: String::new(),
+            validators_hash: String::new(),
+            next_validators_hash: String::new(),
+            consensus_hash: String::new(),
+            app_hash: String::new(),
+            last_result_hash: String::new(),
+            evidence_hash: String::new(),
+            block_time: timestamp(),
+            inserted_at: timestamp(),
+        }
+    }
+
+    fn server() -> ApiServer<MemoryStorage> {
+        let storage = MemoryStorage::new();
+        storage
+            .insert_chain(&NewChain {
+                chain_id: "cosmoshub-4".to_string(),
+                chain_name: "cosmoshub".to_string(),
+                inserted_at: timestamp(),
+            })
+            .unwrap();
+        storage
+            .insert_blocks(&[new_block(1), new_block(2), new_block(3)])
+            .unwrap();
+        let tx = storage
+            .insert_transaction(&NewTransaction {
+                chain_id: 1,
+                transaction_hash: "TX".to_string(),
+                height: 2,
+                code: 0,
+                code_space: String::new(),
+                tx_data: String::new(),
+                raw_log: String::new(),
+                info: String::new(),
+                memo: None,
+                gas_wanted: 200000,
+                gas_used: 100000,
+                tx_timestamp: String::new(),
+                gas_limit: 200000,
+                fee_payer: None,
+                fee_granter: None,
+                inserted_at: timestamp(),
+            })
+            .unwrap();
+        storage
+            .insert_message(&NewMessage {
+                transaction_id: tx.id,
+                seq: 0,
+                rawdata: serde_json::json!({"@type": "/cosmos.bank.v1beta1.MsgSend"}),
+                inserted_at: timestamp(),
+            })
+            .unwrap();
+        storage
+            .insert_transaction_fees(&[NewTransactionFee {
+                transaction_id: tx.id,
+                seq: 0,
+                denom: "uatom".to_string(),
+                amount: "500".to_string(),
+                inserted_at: timestamp(),
+            }])
+            .unwrap();
+
+        let account = storage
+            .upsert_account(&NewAccount {
+                chain_id: 1,
+                address: "cosmos1account".to_string(),
+                inserted_at: timestamp(),
+            })
+            .unwrap();
+        storage
+            .replace_account_balances(
+                account.id,
+                &[NewAccountBalance {
+                    account_id: account.id,
+                    amount: "1000".to_string(),
+                    denom: "uatom".to_string(),
+                    inserted_at: timestamp(),
+                }],
+            )
+            .unwrap();
+
+        for (start_height, voting_powers) in [(1, vec![10]), (3, vec![10, 20])] {
+            let members = voting_powers
+                .into_iter()
+                .enumerate()
+                .map(|(i, voting_power)| NewValidatorSetMember {
+                    validator_set_id: 0,
+                    address: format!("VALIDATOR{}", i),
+                    pub_key_type: "tendermint/PubKeyEd25519".to_string(),
+                    pub_key: String::new(),
+                    voting_power,
+                    proposer_priority: 0,
+                    inserted_at: timestamp(),
+                })
+                .collect::<Vec<_>>();
+            storage
+                .insert_validator_set(
+                    &NewValidatorSet {
+                        chain_id: 1,
+                        start_height,
+                        validators_hash: format!("VALIDATORS{}", start_height),
+                        inserted_at: timestamp(),
+                    },
+                    &members,
+                )
+                .unwrap();
+        }
+
+        storage
+            .upsert_unindexable_range(&NewUnindexableRange {
+                chain_id: 1,
+                start_height: 4,
+                end_height: 9,
+                reason: "pruned".to_string(),
+                inserted_at: timestamp(),
+            })
+            .unwrap();
+
+        let config = Config {
+            db: DBConfig {
+                driver: None,
+                host: String::new(),
+                port: 0,
+                user: String::new(),
+                password: String::new(),
+                database: String::new(),
+                max_connections: Some(2),
+            },
+            server: ServerConfig {
+                host: "127.0.0.1".to_string(),
+                port: 0,
+                allowed_host: "*".to_string(),
+            },
+        };
+        ApiServer::with_storage(config, storage)
+    }
+
+    async fn get(
+        server: &ApiServer<MemoryStorage>,
+        uri: &str,
+    ) -> Result<(StatusCode, serde_json::Value), GenericError> {
+        let req = Request::get(uri).body(Body::empty())?;
+        let resp = router::route(
+            req,
+            Arc::new(server.router()),
+            server.storage.clone(),
+            Arc::new(ResponseBuilder::new("*".to_string())),
+        )
+        .await?;
+
+        let status = resp.status();
+        let body = hyper::body::to_bytes(resp.into_body()).await?;
+        Ok((status, serde_json::from_slice(&body)?))
+    }
+
+    #[tokio::test]
+    async fn lists_chains() {
+        let server = server();
+        let (status, body) = get(&server, "/api/chains/all").await.unwrap();
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["chain_id"], "cosmoshub-4");
+    }
+
+    #[tokio::test]
+    async fn returns_blocks() {
+        let server = server();
+
+        let (status, body) = get(&server, "/api/block/1/2").await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["block_hash"], "HASH2");
+
+        let (_, body) = get(&server, "/api/block/latest_block/1").await.unwrap();
+        assert_eq!(body["height"], 3);
+
+        let (_, body) = get(&server, "/api/block/list/1?limit=2&offset=1")
+            .await
+            .unwrap();
+        let heights = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| b["height"].as_i64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(heights, vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn returns_transactions_with_their_messages() {
+        let server = server();
+
+        let (status, body) = get(&server, "/api/tx/1/TX").await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["height"], 2);
+        assert_eq!(body["messages"][0]["@type"], "/cosmos.bank.v1beta1.MsgSend");
+        assert_eq!(body["fee"][0]["amount"], "500");
+
+        let (_, body) = get(&server, "/api/tx/TX").await.unwrap();
+        assert_eq!(body.as_array().unwrap().len(), 1);
+
+        let (_, body) = get(&server, "/api/tx/list/1/at/2").await.unwrap();
+        assert_eq!(body[0]["transaction_hash"], "TX");
+
+        assert!(get(&server, "/api/tx/1/UNKNOWN").await.is_err());
+        assert!(get(&server, "/api/tx/not-a-number/TX").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn returns_accounts_with_their_balances() {
+        let server = server();
+
+        let (status, body) = get(&server, "/api/account/1/cosmos1account").await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["address"], "cosmos1account");
+        assert_eq!(body["balances"][0]["denom"], "uatom");
+        assert_eq!(body["balances"][0]["amount"], "1000");
+
+        assert!(get(&server, "/api/account/1/cosmos1unknown").await.is_err());
+        assert!(get(&server, "/api/account/2/cosmos1account").await.is_err());
+        assert!(get(&server, "/api/account/not-a-number/cosmos1account")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn returns_validator_sets() {
+        let server = server();
+
+        let (status, body) = get(&server, "/api/validators/latest/1").await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["start_height"], 3);
+        assert_eq!(body["total_voting_power"], 30);
+        assert_eq!(body["validators"].as_array().unwrap().len(), 2);
+
+        // the set which started below the height is still active at it.
+        let (status, body) = get(&server, "/api/validators/1/at/2").await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["start_height"], 1);
+        assert_eq!(body["validators_hash"], "VALIDATORS1");
+        assert_eq!(body["validators"][0]["voting_power"], 10);
+
+        assert!(get(&server, "/api/validators/1/at/0").await.is_err());
+        assert!(get(&server, "/api/validators/latest/2").await.is_err());
+        assert!(get(&server, "/api/validators/1/at/not-a-number")
+            .await
+            .is_err());
+        assert!(get(&server, "/api/validators/latest/not-a-number")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn lists_unindexable_ranges() {
+        let server = server();
+
+        let (status, body) = get(&server, "/api/chains/unindexable/1").await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["start_height"], 4);
+        assert_eq!(body[0]["end_height"], 9);
+        assert_eq!(body[0]["reason"], "pruned");
+
+        let (status, body) = get(&server, "/api/chains/unindexable/2").await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.as_array().unwrap().is_empty());
+
+        assert!(get(&server, "/api/chains/unindexable/not-a-number")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn missing_records_and_routes_fail() {
+        let server = server();
+
+        let (status, _) = get(&server, "/api/unknown").await.unwrap();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        assert!(get(&server, "/api/block/1/100").await.is_err());
+        assert!(get(&server, "/api/block/1/not-a-number").await.is_err());
+    }
+}
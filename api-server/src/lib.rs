@@ -1,6 +1,6 @@
 use std::{fs, sync::Arc};
 
-use cosmscan_models::{config::DBConfig, db::BackendDB, storage::PersistenceStorage};
+use cosmscan_models::{async_storage::AsyncStorage, config::DBConfig};
 
 use resputil::ResponseBuilder;
 use route_recognizer::Params;
@@ -16,8 +16,9 @@ mod server_route;
 
 type GenericError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
-pub struct AppState {
-    pub storage: Arc<PersistenceStorage<BackendDB>>,
+pub struct AppState<S> {
+    /// queries run on the blocking thread pool, see [`AsyncStorage`].
+    pub storage: AsyncStorage<S>,
     pub params: Params,
     pub resp_builder: Arc<ResponseBuilder>,
 }
@@ -35,12 +36,8 @@ pub struct ServerConfig {
     pub allowed_host: String,
 }
 
-impl AppState {
-    fn new(
-        storage: Arc<PersistenceStorage<BackendDB>>,
-        params: Params,
-        resp_builder: Arc<ResponseBuilder>,
-    ) -> Self {
+impl<S> AppState<S> {
+    fn new(storage: AsyncStorage<S>, params: Params, resp_builder: Arc<ResponseBuilder>) -> Self {
         Self {
             storage,
             params,
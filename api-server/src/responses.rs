@@ -15,10 +15,29 @@ pub struct Transaction {
     pub gas_wanted: i64,
     pub gas_used: i64,
     pub tx_timestamp: String,
+    pub fee: Vec<Coin>,
+    pub gas_limit: i64,
+    pub fee_payer: Option<String>,
+    pub fee_granter: Option<String>,
+    pub signers: Vec<Signer>,
     pub messages: Vec<serde_json::Value>,
     pub events: Vec<Event>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Coin {
+    pub denom: String,
+    pub amount: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Signer {
+    pub pub_key_type: Option<String>,
+    pub pub_key: Option<String>,
+    pub sequence: i64,
+    pub sign_mode: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Event {
     pub tx_type: i16,
@@ -31,11 +50,91 @@ pub struct Event {
     pub indexed: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub chain_id: i32,
+    pub address: String,
+    pub balances: Vec<Balance>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Balance {
+    pub denom: String,
+    pub amount: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidatorSet {
+    pub chain_id: i32,
+    pub start_height: i64,
+    pub validators_hash: String,
+    pub total_voting_power: i64,
+    pub validators: Vec<Validator>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Validator {
+    pub address: String,
+    pub pub_key_type: String,
+    pub pub_key: String,
+    pub voting_power: i64,
+    pub proposer_priority: i64,
+}
+
+impl Account {
+    pub fn new(
+        account: models::account::Account,
+        balances: Vec<models::account_balance::AccountBalance>,
+    ) -> Self {
+        let balances = balances
+            .into_iter()
+            .map(|b| Balance {
+                denom: b.denom,
+                amount: b.amount,
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            chain_id: account.chain_id,
+            address: account.address,
+            balances,
+        }
+    }
+}
+
+impl ValidatorSet {
+    pub fn new(
+        validator_set: models::validator_set::ValidatorSet,
+        members: Vec<models::validator_set::ValidatorSetMember>,
+    ) -> Self {
+        let validators = members
+            .into_iter()
+            .map(|m| Validator {
+                address: m.address,
+                pub_key_type: m.pub_key_type,
+                pub_key: m.pub_key,
+                voting_power: m.voting_power,
+                proposer_priority: m.proposer_priority,
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            chain_id: validator_set.chain_id,
+            start_height: validator_set.start_height,
+            validators_hash: validator_set.validators_hash,
+            total_voting_power: validators.iter().map(|v| v.voting_power).sum(),
+            validators,
+        }
+    }
+}
+
 impl Transaction {
     pub fn new(
         tx: models::transaction::Transaction,
         events: Vec<models::event::Event>,
         messages: Vec<models::message::Message>,
+        signers: Vec<models::transaction_signer::TransactionSigner>,
+        fees: Vec<models::transaction_fee::TransactionFee>,
     ) -> Self {
         let tx_events = events
             .into_iter()
@@ -53,6 +152,24 @@ impl Transaction {
 
         let tx_messages = messages.into_iter().map(|m| m.rawdata).collect::<Vec<_>>();
 
+        let tx_signers = signers
+            .into_iter()
+            .map(|s| Signer {
+                pub_key_type: s.pub_key_type,
+                pub_key: s.pub_key,
+                sequence: s.sequence,
+                sign_mode: s.sign_mode,
+            })
+            .collect::<Vec<_>>();
+
+        let tx_fee = fees
+            .into_iter()
+            .map(|f| Coin {
+                denom: f.denom,
+                amount: f.amount,
+            })
+            .collect::<Vec<_>>();
+
         Self {
             chain_id: tx.chain_id,
             transaction_hash: tx.transaction_hash,
@@ -66,6 +183,11 @@ impl Transaction {
             gas_wanted: tx.gas_wanted,
             gas_used: tx.gas_used,
             tx_timestamp: tx.tx_timestamp,
+            fee: tx_fee,
+            gas_limit: tx.gas_limit,
+            fee_payer: tx.fee_payer,
+            fee_granter: tx.fee_granter,
+            signers: tx_signers,
             messages: tx_messages,
             events: tx_events,
         }
@@ -1,7 +1,7 @@
 use std::{collections::HashMap, sync::Arc};
 
 use async_trait::async_trait;
-use cosmscan_models::{db::BackendDB, storage::PersistenceStorage};
+use cosmscan_models::{async_storage::AsyncStorage, storage::Storage};
 use futures::Future;
 use hyper::{Body, Method, Request, Response};
 
@@ -9,69 +9,69 @@ use crate::{
     errors::Error, handlers, resputil::ResponseBuilder, server::ApiServer, AppState, GenericError,
 };
 
-type InternalRotuer = route_recognizer::Router<Box<dyn Handler>>;
+type InternalRotuer<S> = route_recognizer::Router<Box<dyn Handler<S>>>;
 
 #[async_trait]
-pub trait Handler: Send + Sync + 'static {
+pub trait Handler<S>: Send + Sync + 'static {
     async fn handle(
         &self,
         req: Request<Body>,
-        state: AppState,
+        state: AppState<S>,
     ) -> Result<Response<Body>, GenericError>;
 }
 
 #[async_trait]
-impl<F: Send + Sync + 'static, Fut> Handler for F
+impl<S: Send + Sync + 'static, F: Send + Sync + 'static, Fut> Handler<S> for F
 where
-    F: Fn(Request<Body>, AppState) -> Fut,
+    F: Fn(Request<Body>, AppState<S>) -> Fut,
     Fut: Future<Output = Result<Response<Body>, GenericError>> + Send + Sync + 'static,
 {
     async fn handle(
         &self,
         req: Request<Body>,
-        state: AppState,
+        state: AppState<S>,
     ) -> Result<Response<Body>, GenericError> {
         self(req, state).await
     }
 }
 
-pub trait RouterRegister {
-    fn router(&self) -> Router;
+pub trait RouterRegister<S> {
+    fn router(&self) -> Router<S>;
 }
 
-pub struct Router {
-    pub router_map: HashMap<Method, InternalRotuer>,
+pub struct Router<S> {
+    pub router_map: HashMap<Method, InternalRotuer<S>>,
 }
 
-impl Router {
+impl<S> Router<S> {
     pub fn new() -> Self {
         Self {
             router_map: HashMap::new(),
         }
     }
 
-    pub fn get(&mut self, path: &str, handler: impl Handler) {
+    pub fn get(&mut self, path: &str, handler: impl Handler<S>) {
         self.router_map
             .entry(Method::GET)
             .or_insert_with(InternalRotuer::new)
             .add(path, Box::new(handler));
     }
 
-    pub fn post(&mut self, path: &str, handler: impl Handler) {
+    pub fn post(&mut self, path: &str, handler: impl Handler<S>) {
         self.router_map
             .entry(Method::POST)
             .or_insert_with(InternalRotuer::new)
             .add(path, Box::new(handler));
     }
 
-    pub fn put(&mut self, path: &str, handler: impl Handler) {
+    pub fn put(&mut self, path: &str, handler: impl Handler<S>) {
         self.router_map
             .entry(Method::PUT)
             .or_insert_with(InternalRotuer::new)
             .add(path, Box::new(handler));
     }
 
-    pub fn delete(&mut self, path: &str, handler: impl Handler) {
+    pub fn delete(&mut self, path: &str, handler: impl Handler<S>) {
         self.router_map
             .entry(Method::DELETE)
             .or_insert_with(InternalRotuer::new)
@@ -80,10 +80,10 @@ impl Router {
 }
 
 /// route the request to the correct handler.
-pub async fn route(
+pub async fn route<S: Storage + Send + Sync + 'static>(
     req: Request<Body>,
-    router: Arc<Router>,
-    storage: Arc<PersistenceStorage<BackendDB>>,
+    router: Arc<Router<S>>,
+    storage: AsyncStorage<S>,
     resp_builder: Arc<ResponseBuilder>,
 ) -> Result<Response<Body>, GenericError> {
     let method = req.method().clone();
@@ -1,9 +1,12 @@
 use std::sync::Arc;
 
 use cosmscan_models::{
+    async_storage::AsyncStorage,
     db::{BackendDB, Database},
-    storage::PersistenceStorage,
+    storage::{PersistenceStorage, Storage},
 };
+#[cfg(feature = "sqlite")]
+use cosmscan_models::{db::SqliteDB, sqlite_storage::SqliteStorage};
 use hyper::{
     service::{make_service_fn, service_fn},
     Body, Response, Server,
@@ -17,22 +20,49 @@ use crate::{
     Config, GenericError,
 };
 
-pub struct ApiServer {
+pub struct ApiServer<S> {
     pub config: Config,
+    /// queries run on the blocking thread pool, see [`AsyncStorage`].
+    pub storage: AsyncStorage<S>,
 }
 
-impl ApiServer {
+impl ApiServer<PersistenceStorage<BackendDB>> {
+    /// creates a server which reads the configured database.
     pub fn new(config: Config) -> Self {
-        Self { config }
+        // connect to the database
+        let mut db = BackendDB::new(config.db.clone());
+        db.connect();
+
+        Self::with_storage(config, PersistenceStorage::new(db))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl ApiServer<SqliteStorage<SqliteDB>> {
+    /// creates a server which reads the configured sqlite database file.
+    pub fn new_sqlite(config: Config) -> Self {
+        let mut db = SqliteDB::new(config.db.clone());
+        db.connect();
+
+        Self::with_storage(config, SqliteStorage::new(db))
+    }
+}
+
+impl<S: Storage + Send + Sync + 'static> ApiServer<S> {
+    /// creates a server on top of the given storage, e.g. a [`MemoryStorage`] in tests.
+    ///
+    /// [`MemoryStorage`]: cosmscan_models::memory_storage::MemoryStorage
+    pub fn with_storage(config: Config, storage: S) -> Self {
+        // queries can't run on more threads than the connections in the pool.
+        let max_connections = config.db.max_connections() as usize;
+        let storage = AsyncStorage::new(storage, max_connections);
+
+        Self { config, storage }
     }
 
     /// run the server on the given address & port
     pub async fn run(&self) -> Result<(), GenericError> {
-        // connect to the database
-        let mut db = BackendDB::new(self.config.db.clone());
-        db.connect();
-        let storage = PersistenceStorage::new(db);
-        let shared_storage = Arc::new(storage);
+        let shared_storage = self.storage.clone();
 
         // construct response builder
         let resp_builder = ResponseBuilder::new(self.config.server.allowed_host.clone());
@@ -1,21 +1,49 @@
+use cosmscan_models::storage::Storage;
+
 use crate::{
     handlers,
     router::{Router, RouterRegister},
     server::ApiServer,
 };
 
-impl RouterRegister for ApiServer {
-    fn router(&self) -> Router {
+impl<S: Storage + Send + Sync + 'static> RouterRegister<S> for ApiServer<S> {
+    fn router(&self) -> Router<S> {
         let mut router = Router::new();
 
-        router.get("/api/chains/all", handlers::all_chains);
-        router.get("/api/block/latest_block/:chain_id", handlers::latest_block);
-        router.get("/api/block/list/:chain_id", handlers::block_list);
-        router.get("/api/block/:chain_id/:block_height", handlers::get_block);
-        router.get("/api/tx/:tx_hash", handlers::transaction_by_hash);
+        router.get("/api/chains/all", handlers::all_chains::<S>);
+        router.get(
+            "/api/chains/unindexable/:chain_id",
+            handlers::unindexable_ranges::<S>,
+        );
+        router.get(
+            "/api/block/latest_block/:chain_id",
+            handlers::latest_block::<S>,
+        );
+        router.get("/api/block/list/:chain_id", handlers::block_list::<S>);
+        router.get(
+            "/api/block/:chain_id/:block_height",
+            handlers::get_block::<S>,
+        );
+        router.get("/api/tx/:tx_hash", handlers::transactions_by_hash::<S>);
+        router.get(
+            "/api/tx/:chain_id/:tx_hash",
+            handlers::transaction_by_hash::<S>,
+        );
         router.get(
             "/api/tx/list/:chain_id/at/:block_height",
-            handlers::transaction_list_in_block,
+            handlers::transaction_list_in_block::<S>,
+        );
+        router.get(
+            "/api/account/:chain_id/:address",
+            handlers::account_by_address::<S>,
+        );
+        router.get(
+            "/api/validators/latest/:chain_id",
+            handlers::latest_validator_set::<S>,
+        );
+        router.get(
+            "/api/validators/:chain_id/at/:block_height",
+            handlers::validator_set_at_height::<S>,
         );
 
         router
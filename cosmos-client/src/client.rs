@@ -1,52 +1,283 @@
-use cosmos_sdk_proto::cosmos::{self, tx::v1beta1::service_client};
+use std::sync::Arc;
+use std::time::Duration;
 
+use cosmos_sdk_proto::cosmos::{
+    bank::v1beta1::{query_client, QueryAllBalancesRequest},
+    base::query::v1beta1::PageRequest,
+    base::tendermint::v1beta1::{
+        service_client as tendermint_service_client, GetNodeInfoRequest, GetSyncingRequest,
+    },
+    tx::signing::v1beta1::SignMode,
+    tx::v1beta1::{mode_info, SignerInfo, Tx},
+};
+use cosmos_sdk_proto::prost::Message;
+
+use log::{info, warn};
 use tendermint::block;
-use tendermint_rpc::Client as tm_client;
+use tendermint_rpc::{Client as tm_client, HttpClient, Paging};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use tonic::transport::Channel;
 
 use crate::{
-    bytes_to_tx_hash, convert_block_events,
+    bytes_to_tx_hash,
+    decoder::MessageDecoder,
+    dialect::{AbciDialect, BlockResultsRequest, TxResult},
+    endpoint::{EndpointPool, EndpointSelection, EndpointStatus},
     errors::Error,
-    response::{self, EventType},
+    response,
+    status::StatusRequest,
+    subscription::NewBlockSubscription,
 };
 
-type TxClient = service_client::ServiceClient<tonic::transport::Channel>;
+type BankClient = query_client::QueryClient<Channel>;
+type TendermintServiceClient = tendermint_service_client::ServiceClient<Channel>;
 
+#[derive(Debug, Clone)]
 pub struct ClientConfig {
-    pub tendermint_rpc_endpoint: String,
-    pub grpc_endpoint: String,
-    pub rest_api_endpoint: String,
+    /// every endpoint must serve the same chain, requests fail over between them.
+    pub tendermint_rpc_endpoints: Vec<String>,
+    pub grpc_endpoints: Vec<String>,
+    pub tendermint_ws_endpoint: Option<String>,
+    pub endpoint_selection: EndpointSelection,
+    /// unhealthy endpoints are only retried by requests if it's not set.
+    pub health_check_interval: Option<Duration>,
+    /// maximum number of rpc and gRPC requests in flight, shared by every clone of the client.
+    /// requests aren't limited if it's not set.
+    pub max_in_flight_requests: Option<usize>,
 }
+
+/// Client is cheap to clone, clones share the endpoints, the connections and the in-flight limit.
+/// It can be used concurrently without any lock.
+#[derive(Clone)]
 pub struct Client {
-    tm_client: tendermint_rpc::HttpClient,
-    tx_client: TxClient,
-    config: ClientConfig,
+    tm_pool: Arc<EndpointPool<HttpClient>>,
+    grpc_pool: Arc<EndpointPool<Channel>>,
+    /// only held, the task is aborted when it's dropped.
+    _health_check: Option<Arc<HealthCheck>>,
+    decoder: Arc<MessageDecoder>,
+    dialect: AbciDialect,
+    config: Arc<ClientConfig>,
+}
+
+/// HealthCheck aborts the health check task once the last clone of the client is dropped.
+struct HealthCheck(JoinHandle<()>);
+
+impl Drop for HealthCheck {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
 }
 
 impl Client {
     /// Create a new client.
     /// It abstracts the tendermint and grpc client both.
     pub async fn new(config: ClientConfig) -> Result<Self, Error> {
-        let tm_client =
-            tendermint_rpc::HttpClient::new(config.tendermint_rpc_endpoint.clone().as_str())
-                .map_err(|e| Error::from(e))?;
-        let tx_client = TxClient::connect(config.grpc_endpoint.clone())
-            .await
-            .map_err(|e| Error::from(e))?;
+        if config.tendermint_rpc_endpoints.is_empty() || config.grpc_endpoints.is_empty() {
+            return Err(Error::NoEndpointConfigured);
+        }
+
+        let mut tm_clients = vec![];
+        for endpoint in config.tendermint_rpc_endpoints.iter() {
+            let client = HttpClient::new(endpoint.as_str()).map_err(Error::from)?;
+            tm_clients.push((endpoint.clone(), client));
+        }
+        // channels connect on the first request, so a node which is down doesn't block startup.
+        let mut channels = vec![];
+        for endpoint in config.grpc_endpoints.iter() {
+            let channel = tonic::transport::Endpoint::from_shared(endpoint.clone())?.connect_lazy();
+            channels.push((endpoint.clone(), channel));
+        }
+        let limiter = config
+            .max_in_flight_requests
+            .filter(|limit| *limit > 0)
+            .map(|limit| Arc::new(Semaphore::new(limit)));
+        let tm_pool = Arc::new(EndpointPool::new(
+            tm_clients,
+            config.endpoint_selection,
+            limiter.clone(),
+        ));
+        let grpc_pool = Arc::new(EndpointPool::new(
+            channels,
+            config.endpoint_selection,
+            limiter,
+        ));
+
+        // the shape of block results depends on the node version.
+        let (status, dialect) = Self::detect_dialect(&tm_pool).await?;
+        info!(
+            "connected to {} | version: {}, dialect: {:?}, earliest_block_height: {}",
+            status.network, status.version, dialect, status.earliest_block_height
+        );
+
+        let health_check = config.health_check_interval.map(|interval| {
+            let tm_pool = tm_pool.clone();
+            let grpc_pool = grpc_pool.clone();
+            let task = tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(interval).await;
+                    tm_pool
+                        .check_health(|client| async move {
+                            client.status().await.map(|_| ()).map_err(Error::from)
+                        })
+                        .await;
+                    grpc_pool
+                        .check_health(|channel| async move {
+                            TendermintServiceClient::new(channel)
+                                .get_syncing(GetSyncingRequest {})
+                                .await
+                                .map(|_| ())
+                                .map_err(Error::from)
+                        })
+                        .await;
+                }
+            });
+            Arc::new(HealthCheck(task))
+        });
 
         Ok(Client {
-            tm_client,
-            tx_client,
-            config,
+            tm_pool,
+            grpc_pool,
+            _health_check: health_check,
+            decoder: Arc::new(MessageDecoder::default()),
+            dialect,
+            config: Arc::new(config),
         })
     }
 
+    /// Returns the ABCI dialect of the node, detected on creation.
+    pub fn abci_dialect(&self) -> AbciDialect {
+        self.dialect
+    }
+
+    /// Returns the health and the request/error counters of every endpoint,
+    /// tendermint rpc endpoints first, followed by gRPC endpoints.
+    pub fn endpoint_status(&self) -> Vec<EndpointStatus> {
+        let mut status = self.tm_pool.status();
+        status.extend(self.grpc_pool.status());
+        status
+    }
+
+    /// Returns the chain id, the version and the available heights of the node.
+    pub async fn node_status(&self) -> Result<response::NodeStatus, Error> {
+        Self::fetch_node_status(&self.tm_pool).await
+    }
+
+    /// detects the dialect from `/status` of every rpc endpoint, the requests fail over between them
+    /// so that they must agree. unreachable endpoints are skipped, they're expected to run the same version.
+    async fn detect_dialect(
+        tm_pool: &EndpointPool<HttpClient>,
+    ) -> Result<(response::NodeStatus, AbciDialect), Error> {
+        let statuses = tm_pool
+            .each(|client| async move { client.perform(StatusRequest).await.map_err(Error::from) })
+            .await;
+
+        let mut detected: Option<(String, response::NodeStatus, AbciDialect)> = None;
+        let mut last_error = None;
+        for (url, result) in statuses {
+            let status = match result {
+                Ok(status) => response::NodeStatus::from(status),
+                Err(e) => {
+                    warn!(
+                        "failed to get the node status | endpoint: {}, error: {}",
+                        url, e
+                    );
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            let dialect = AbciDialect::from_version(&status.version);
+            match &detected {
+                Some((first_url, first, first_dialect)) if *first_dialect != dialect => {
+                    return Err(Error::AbciDialectMismatch(
+                        first_url.clone(),
+                        first.version.clone(),
+                        url,
+                        status.version,
+                    ))
+                }
+                Some(_) => {}
+                None => detected = Some((url, status, dialect)),
+            }
+        }
+
+        match detected {
+            Some((_, status, dialect)) => Ok((status, dialect)),
+            None => Err(last_error.unwrap_or(Error::NoEndpointConfigured)),
+        }
+    }
+
+    async fn fetch_node_status(
+        tm_pool: &EndpointPool<HttpClient>,
+    ) -> Result<response::NodeStatus, Error> {
+        let status = tm_pool
+            .call(|client| async move { client.perform(StatusRequest).await.map_err(Error::from) })
+            .await?;
+
+        Ok(response::NodeStatus::from(status))
+    }
+
+    /// Returns the chain id served by each endpoint, rpc endpoints first, followed by gRPC endpoints.
+    /// It's `network` of `/status` for rpc endpoints, and of `GetNodeInfo` for gRPC endpoints.
+    pub async fn endpoint_chain_ids(&self) -> Vec<(String, Result<String, Error>)> {
+        let mut chain_ids = self
+            .tm_pool
+            .each(|client| async move {
+                client
+                    .perform(StatusRequest)
+                    .await
+                    .map(|status| status.node_info.network)
+                    .map_err(Error::from)
+            })
+            .await;
+        chain_ids.extend(
+            self.grpc_pool
+                .each(|channel| async move {
+                    let response = TendermintServiceClient::new(channel)
+                        .get_node_info(GetNodeInfoRequest {})
+                        .await?
+                        .into_inner();
+                    Ok(response
+                        .default_node_info
+                        .map(|info| info.network)
+                        .unwrap_or_default())
+                })
+                .await,
+        );
+
+        chain_ids
+    }
+
+    /// Returns the latest block height the node has committed.
+    pub async fn latest_block_height(&self) -> Result<i64, Error> {
+        let status = self
+            .tm_pool
+            .call(|client| async move { client.status().await.map_err(Error::from) })
+            .await?;
+
+        Ok(status.sync_info.latest_block_height.into())
+    }
+
+    /// Subscribes to `NewBlock` events over the tendermint websocket endpoint.
+    pub async fn subscribe_new_blocks(&self) -> Result<NewBlockSubscription, Error> {
+        match &self.config.tendermint_ws_endpoint {
+            Some(endpoint) => NewBlockSubscription::connect(endpoint).await,
+            None => Err(Error::WebSocketNotConfigured),
+        }
+    }
+
     /// Returns a block info by height.
     pub async fn get_block(&self, height: i64) -> Result<(response::Block, Vec<String>), Error> {
         let block = self
-            .tm_client
-            .block(block::Height::from(height as u32))
-            .await
-            .map_err(|e| Error::from(e))?;
+            .tm_pool
+            .call(|client| async move {
+                client
+                    .block(block::Height::from(height as u32))
+                    .await
+                    .map_err(Error::from)
+            })
+            .await?;
 
         let tx_hashes = block
             .block
@@ -60,91 +291,230 @@ impl Client {
         Ok((resp, tx_hashes))
     }
 
+    /// Returns the validator set which signs the block at the given height.
+    pub async fn get_validators(&self, height: i64) -> Result<Vec<response::Validator>, Error> {
+        let response = self
+            .tm_pool
+            .call(|client| async move {
+                client
+                    .validators(block::Height::from(height as u32), Paging::All)
+                    .await
+                    .map_err(Error::from)
+            })
+            .await?;
+
+        Ok(response
+            .validators
+            .into_iter()
+            .map(response::Validator::from)
+            .collect())
+    }
+
     /// Returns a block result by height.
+    /// Every ABCI dialect is normalized into begin/end block events.
     pub async fn get_block_result(&self, height: i64) -> Result<response::BlockResult, Error> {
-        let block_result = self
-            .tm_client
-            .block_results(block::Height::from(height as u32))
-            .await
-            .map_err(|e| Error::from(e))?;
-
-        let mut resp = response::BlockResult {
-            height,
-            begin_block_events: vec![],
-            end_block_events: vec![],
-        };
+        let (block_result, _) = self.block_results(height).await?;
 
-        if let Some(events) = block_result.begin_block_events {
-            let converted = convert_block_events(events, height, EventType::BeginBlock);
-            resp.begin_block_events = converted;
+        Ok(block_result)
+    }
+
+    async fn block_results(
+        &self,
+        height: i64,
+    ) -> Result<(response::BlockResult, Vec<TxResult>), Error> {
+        let raw = self
+            .tm_pool
+            .call(|client| async move {
+                client
+                    .perform(BlockResultsRequest {
+                        height: block::Height::from(height as u32),
+                    })
+                    .await
+                    .map_err(Error::from)
+            })
+            .await?;
+
+        Ok(raw.normalize(height, self.dialect))
+    }
+
+    /// Returns a block with its transactions and events.
+    /// They're built from `/block` and `/block_results` only, without querying each transaction.
+    pub async fn get_block_with_txs(
+        &self,
+        height: i64,
+    ) -> Result<
+        (
+            response::Block,
+            Vec<response::Transaction>,
+            Vec<response::Event>,
+        ),
+        Error,
+    > {
+        let (block, (block_result, tx_results)) = futures::try_join!(
+            self.tm_pool.call(|client| async move {
+                client
+                    .block(block::Height::from(height as u32))
+                    .await
+                    .map_err(Error::from)
+            }),
+            self.block_results(height),
+        )?;
+
+        let raw_txs = block.block.data.iter().cloned().collect::<Vec<_>>();
+        let resp_block = response::Block::from(block.block);
+        // same format as the timestamp of `GetTxResponse`.
+        let tx_timestamp = resp_block
+            .block_time
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string();
+        if raw_txs.len() != tx_results.len() {
+            return Err(Error::TxResultsMismatch(
+                height,
+                raw_txs.len(),
+                tx_results.len(),
+            ));
         }
 
-        if let Some(events) = block_result.end_block_events {
-            let converted = convert_block_events(events, height, EventType::EndBlock);
-            resp.end_block_events = converted;
+        let mut events: Vec<response::Event> = vec![];
+        events.extend(block_result.begin_block_events);
+        events.extend(block_result.end_block_events);
+
+        let mut transactions: Vec<response::Transaction> = vec![];
+        for (raw_tx, result) in raw_txs.into_iter().zip(tx_results) {
+            let transaction_hash = bytes_to_tx_hash(raw_tx.as_bytes());
+            let mut resp = response::Transaction {
+                transaction_hash: transaction_hash.clone(),
+                height,
+                code: result.code,
+                code_space: result.codespace,
+                tx_data: result.data,
+                raw_log: result.log,
+                info: result.info,
+                memo: None,
+                gas_wanted: result.gas_wanted,
+                gas_used: result.gas_used,
+                tx_timestamp: tx_timestamp.clone(),
+                messages: vec![],
+                fee: vec![],
+                gas_limit: 0,
+                fee_payer: None,
+                fee_granter: None,
+                signers: vec![],
+            };
+
+            // a transaction without its messages, fee and signers must not be stored.
+            let tx = Tx::decode(raw_tx.as_bytes())
+                .map_err(|e| Error::TxDecode(transaction_hash.clone(), e))?;
+            self.apply_tx(&mut resp, &tx);
+
+            events.extend(result.events.into_iter().map(|evt| response::Event {
+                tx_hash: Some(transaction_hash.clone()),
+                ..evt
+            }));
+            transactions.push(resp);
         }
 
-        Ok(resp)
+        Ok((resp_block, transactions, events))
     }
 
-    /// Returns a transaction by hash.
-    pub async fn get_transaction(
-        &mut self,
-        hash: String,
-    ) -> Result<(response::Transaction, Vec<response::Event>), Error> {
-        let request = cosmos_sdk_proto::cosmos::tx::v1beta1::GetTxRequest { hash };
-
-        let response = self
-            .tx_client
-            .get_tx(request)
-            .await
-            .map_err(|e| Error::from(e))?;
+    /// Fills the fields which are decoded from the transaction itself:
+    /// memo, messages, fee and signers.
+    fn apply_tx(&self, resp: &mut response::Transaction, tx: &Tx) {
+        if let Some(body) = tx.body.as_ref() {
+            resp.memo = Some(body.memo.clone());
+            resp.messages = body
+                .messages
+                .iter()
+                .map(|m| self.decoder.decode(m).to_string())
+                .collect();
+        }
 
-        let resp = response::Transaction::from(&response.get_ref().clone());
+        if let Some(auth_info) = tx.auth_info.as_ref() {
+            resp.signers = auth_info
+                .signer_infos
+                .iter()
+                .map(|signer| self.signer(signer))
+                .collect();
 
-        let mut events: Vec<response::Event> = vec![];
-        if let Some(tx_resp) = &response.get_ref().tx_response {
-            for log in tx_resp.logs.iter() {
-                for (seq, evt) in log.events.iter().enumerate() {
-                    for attr in evt.attributes.iter() {
-                        let raw_event = response::Event {
-                            tx_type: EventType::Transaction,
-                            tx_hash: Some(tx_resp.txhash.clone()),
-                            block_height: tx_resp.height,
-                            event_seq: seq as i32,
-                            event_type: evt.r#type.clone(),
-                            event_key: attr.key.clone(),
-                            event_value: attr.value.clone(),
-                            indexed: false,
-                        };
-                        events.push(raw_event);
-                    }
-                }
+            if let Some(fee) = auth_info.fee.as_ref() {
+                let non_empty = |s: &String| Some(s.clone()).filter(|s| !s.is_empty());
+                resp.fee = fee
+                    .amount
+                    .iter()
+                    .map(|c| response::Coin {
+                        denom: c.denom.clone(),
+                        amount: c.amount.clone(),
+                    })
+                    .collect();
+                resp.gas_limit = fee.gas_limit as i64;
+                resp.fee_payer = non_empty(&fee.payer);
+                resp.fee_granter = non_empty(&fee.granter);
             }
         }
+    }
+
+    /// Converts the signer info of a transaction.
+    /// Multisig keys aren't decoded, they're kept as the decoder's JSON.
+    fn signer(&self, signer: &SignerInfo) -> response::Signer {
+        let pub_key = signer.public_key.as_ref().map(|any| {
+            let decoded = self.decoder.decode(any);
+            match decoded["key"].as_str() {
+                Some(key) => key.to_string(),
+                None => decoded.to_string(),
+            }
+        });
 
-        Ok((resp, events))
+        let sign_mode = match signer.mode_info.as_ref().and_then(|m| m.sum.as_ref()) {
+            Some(mode_info::Sum::Single(single)) => SignMode::from_i32(single.mode)
+                .map(|mode| mode.as_str_name().to_string())
+                .unwrap_or_else(|| single.mode.to_string()),
+            Some(mode_info::Sum::Multi(_)) => "MULTI".to_string(),
+            None => SignMode::Unspecified.as_str_name().to_string(),
+        };
+
+        response::Signer {
+            pub_key_type: signer.public_key.as_ref().map(|any| any.type_url.clone()),
+            pub_key,
+            sequence: signer.sequence as i64,
+            sign_mode,
+        }
     }
 
-    /// Returns a transaction messages by tx hash
-    /// It uses REST API, because prost cannot automatically convert it into json string
-    pub async fn get_tx_messages(&self, hash: String) -> Result<Vec<String>, Error> {
-        let url = format!(
-            "{}/cosmos/tx/v1beta1/txs/{}",
-            self.config.rest_api_endpoint.clone(),
-            hash
-        );
-        let response = reqwest::get(url).await?.text().await?;
+    /// Returns every balance of the given address at the latest height.
+    pub async fn get_all_balances(&self, address: String) -> Result<Vec<response::Coin>, Error> {
+        let mut balances: Vec<response::Coin> = vec![];
+        let mut next_key: Vec<u8> = vec![];
 
-        let res: serde_json::Value = serde_json::from_str(&response)?;
+        loop {
+            let request = QueryAllBalancesRequest {
+                address: address.clone(),
+                pagination: Some(PageRequest {
+                    key: next_key,
+                    ..Default::default()
+                }),
+            };
 
-        let messages = res["tx"]["body"]["messages"]
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|m| m.to_string())
-            .collect();
+            let response = self
+                .grpc_pool
+                .call(|channel| {
+                    let request = request.clone();
+                    async move {
+                        BankClient::new(channel)
+                            .all_balances(request)
+                            .await
+                            .map_err(Error::from)
+                    }
+                })
+                .await?
+                .into_inner();
+            balances.extend(response.balances.into_iter().map(response::Coin::from));
+
+            match response.pagination {
+                Some(page) if !page.next_key.is_empty() => next_key = page.next_key,
+                _ => break,
+            }
+        }
 
-        Ok(messages)
+        Ok(balances)
     }
 }
@@ -0,0 +1,1599 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use cosmos_sdk_proto::{
+    cosmos::{authz, bank, base::v1beta1::Coin, crypto, distribution, feegrant, gov, staking},
+    cosmwasm::wasm,
+    ibc::{applications::transfer, core::client::v1::Height},
+    prost::{DecodeError, Message},
+    Any,
+};
+use log::warn;
+use serde_json::{json, Value};
+
+use crate::proto::gov::v1 as gov_v1;
+
+/// DecodeFn turns the encoded bytes of a registered type into JSON.
+/// It receives the decoder so that nested `Any` fields can be decoded as well.
+pub type DecodeFn = fn(&MessageDecoder, &[u8]) -> Result<Value, DecodeError>;
+
+/// MessageDecoder is a registry of decoders for protobuf `Any` messages, keyed by type url.
+/// The JSON follows the format of the REST API: snake_case fields, 64-bit integers as strings
+/// and bytes as base64, with the type url under `@type`.
+pub struct MessageDecoder {
+    decoders: HashMap<String, DecodeFn>,
+}
+
+impl MessageDecoder {
+    /// creates an empty registry, see [`MessageDecoder::default`] for the one with the known types.
+    pub fn new() -> Self {
+        MessageDecoder {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// registers a decoder for the type url, replacing the existing one.
+    pub fn register(&mut self, type_url: &str, decode: DecodeFn) {
+        self.decoders.insert(type_url.to_string(), decode);
+    }
+
+    /// decodes the message into JSON.
+    /// unknown types, and messages which cannot be decoded, fall back to base64 with their type url.
+    pub fn decode(&self, any: &Any) -> Value {
+        let decoded = match self.decoders.get(any.type_url.as_str()) {
+            Some(decode) => decode(self, &any.value),
+            None => return Self::fallback(any),
+        };
+
+        match decoded {
+            Ok(Value::Object(mut fields)) => {
+                fields.insert("@type".to_string(), Value::from(any.type_url.clone()));
+                Value::Object(fields)
+            }
+            Ok(value) => json!({ "@type": any.type_url, "value": value }),
+            Err(e) => {
+                warn!("failed to decode {}, stored as base64: {}", any.type_url, e);
+                Self::fallback(any)
+            }
+        }
+    }
+
+    fn decode_optional(&self, any: &Option<Any>) -> Value {
+        any.as_ref().map_or(Value::Null, |any| self.decode(any))
+    }
+
+    fn fallback(any: &Any) -> Value {
+        json!({ "@type": any.type_url, "value": base64::encode(&any.value) })
+    }
+}
+
+impl Default for MessageDecoder {
+    /// creates a registry with the bank, staking, distribution, gov, authz, feegrant,
+    /// ibc transfer and wasm types.
+    fn default() -> Self {
+        let mut decoder = MessageDecoder::new();
+
+        // bank
+        decoder.register(
+            "/cosmos.bank.v1beta1.MsgSend",
+            decode::<bank::v1beta1::MsgSend>,
+        );
+        decoder.register(
+            "/cosmos.bank.v1beta1.MsgMultiSend",
+            decode::<bank::v1beta1::MsgMultiSend>,
+        );
+        decoder.register(
+            "/cosmos.bank.v1beta1.SendAuthorization",
+            decode::<bank::v1beta1::SendAuthorization>,
+        );
+
+        // staking
+        decoder.register(
+            "/cosmos.staking.v1beta1.MsgCreateValidator",
+            decode::<staking::v1beta1::MsgCreateValidator>,
+        );
+        decoder.register(
+            "/cosmos.staking.v1beta1.MsgEditValidator",
+            decode::<staking::v1beta1::MsgEditValidator>,
+        );
+        decoder.register(
+            "/cosmos.staking.v1beta1.MsgDelegate",
+            decode::<staking::v1beta1::MsgDelegate>,
+        );
+        decoder.register(
+            "/cosmos.staking.v1beta1.MsgBeginRedelegate",
+            decode::<staking::v1beta1::MsgBeginRedelegate>,
+        );
+        decoder.register(
+            "/cosmos.staking.v1beta1.MsgUndelegate",
+            decode::<staking::v1beta1::MsgUndelegate>,
+        );
+        decoder.register(
+            "/cosmos.crypto.ed25519.PubKey",
+            decode::<crypto::ed25519::PubKey>,
+        );
+        decoder.register(
+            "/cosmos.crypto.secp256k1.PubKey",
+            decode::<crypto::secp256k1::PubKey>,
+        );
+
+        // distribution
+        decoder.register(
+            "/cosmos.distribution.v1beta1.MsgSetWithdrawAddress",
+            decode::<distribution::v1beta1::MsgSetWithdrawAddress>,
+        );
+        decoder.register(
+            "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward",
+            decode::<distribution::v1beta1::MsgWithdrawDelegatorReward>,
+        );
+        decoder.register(
+            "/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission",
+            decode::<distribution::v1beta1::MsgWithdrawValidatorCommission>,
+        );
+        decoder.register(
+            "/cosmos.distribution.v1beta1.MsgFundCommunityPool",
+            decode::<distribution::v1beta1::MsgFundCommunityPool>,
+        );
+
+        // gov
+        decoder.register(
+            "/cosmos.gov.v1beta1.MsgSubmitProposal",
+            decode::<gov::v1beta1::MsgSubmitProposal>,
+        );
+        decoder.register(
+            "/cosmos.gov.v1beta1.MsgVote",
+            decode::<gov::v1beta1::MsgVote>,
+        );
+        decoder.register(
+            "/cosmos.gov.v1beta1.MsgVoteWeighted",
+            decode::<gov::v1beta1::MsgVoteWeighted>,
+        );
+        decoder.register(
+            "/cosmos.gov.v1beta1.MsgDeposit",
+            decode::<gov::v1beta1::MsgDeposit>,
+        );
+        decoder.register(
+            "/cosmos.gov.v1beta1.TextProposal",
+            decode::<gov::v1beta1::TextProposal>,
+        );
+        decoder.register(
+            "/cosmos.gov.v1.MsgSubmitProposal",
+            decode::<gov_v1::MsgSubmitProposal>,
+        );
+        decoder.register("/cosmos.gov.v1.MsgVote", decode::<gov_v1::MsgVote>);
+        decoder.register(
+            "/cosmos.gov.v1.MsgVoteWeighted",
+            decode::<gov_v1::MsgVoteWeighted>,
+        );
+        decoder.register("/cosmos.gov.v1.MsgDeposit", decode::<gov_v1::MsgDeposit>);
+
+        // authz
+        decoder.register(
+            "/cosmos.authz.v1beta1.MsgGrant",
+            decode::<authz::v1beta1::MsgGrant>,
+        );
+        decoder.register(
+            "/cosmos.authz.v1beta1.MsgExec",
+            decode::<authz::v1beta1::MsgExec>,
+        );
+        decoder.register(
+            "/cosmos.authz.v1beta1.MsgRevoke",
+            decode::<authz::v1beta1::MsgRevoke>,
+        );
+        decoder.register(
+            "/cosmos.authz.v1beta1.GenericAuthorization",
+            decode::<authz::v1beta1::GenericAuthorization>,
+        );
+
+        // feegrant
+        decoder.register(
+            "/cosmos.feegrant.v1beta1.MsgGrantAllowance",
+            decode::<feegrant::v1beta1::MsgGrantAllowance>,
+        );
+        decoder.register(
+            "/cosmos.feegrant.v1beta1.MsgRevokeAllowance",
+            decode::<feegrant::v1beta1::MsgRevokeAllowance>,
+        );
+        decoder.register(
+            "/cosmos.feegrant.v1beta1.BasicAllowance",
+            decode::<feegrant::v1beta1::BasicAllowance>,
+        );
+        decoder.register(
+            "/cosmos.feegrant.v1beta1.PeriodicAllowance",
+            decode::<feegrant::v1beta1::PeriodicAllowance>,
+        );
+        decoder.register(
+            "/cosmos.feegrant.v1beta1.AllowedMsgAllowance",
+            decode::<feegrant::v1beta1::AllowedMsgAllowance>,
+        );
+
+        // ibc transfer
+        decoder.register(
+            "/ibc.applications.transfer.v1.MsgTransfer",
+            decode::<transfer::v1::MsgTransfer>,
+        );
+
+        // wasm
+        decoder.register(
+            "/cosmwasm.wasm.v1.MsgStoreCode",
+            decode::<wasm::v1::MsgStoreCode>,
+        );
+        decoder.register(
+            "/cosmwasm.wasm.v1.MsgInstantiateContract",
+            decode::<wasm::v1::MsgInstantiateContract>,
+        );
+        decoder.register(
+            "/cosmwasm.wasm.v1.MsgExecuteContract",
+            decode::<wasm::v1::MsgExecuteContract>,
+        );
+        decoder.register(
+            "/cosmwasm.wasm.v1.MsgMigrateContract",
+            decode::<wasm::v1::MsgMigrateContract>,
+        );
+        decoder.register(
+            "/cosmwasm.wasm.v1.MsgUpdateAdmin",
+            decode::<wasm::v1::MsgUpdateAdmin>,
+        );
+        decoder.register(
+            "/cosmwasm.wasm.v1.MsgClearAdmin",
+            decode::<wasm::v1::MsgClearAdmin>,
+        );
+
+        decoder
+    }
+}
+
+/// ToJson converts a decoded protobuf message into JSON.
+pub trait ToJson {
+    fn to_json(&self, decoder: &MessageDecoder) -> Value;
+}
+
+/// decodes the bytes as `T`, it can be registered as a [`DecodeFn`].
+pub fn decode<T: Message + Default + ToJson>(
+    decoder: &MessageDecoder,
+    bytes: &[u8],
+) -> Result<Value, DecodeError> {
+    Ok(T::decode(bytes)?.to_json(decoder))
+}
+
+fn coins(coins: &[Coin]) -> Value {
+    coins.iter().map(coin).collect()
+}
+
+fn coin(coin: &Coin) -> Value {
+    json!({ "denom": coin.denom, "amount": coin.amount })
+}
+
+fn optional_coin(value: &Option<Coin>) -> Value {
+    value.as_ref().map_or(Value::Null, coin)
+}
+
+fn timestamp(value: &Option<prost_types::Timestamp>) -> Value {
+    value
+        .as_ref()
+        .and_then(|ts| NaiveDateTime::from_timestamp_opt(ts.seconds, ts.nanos as u32))
+        .map_or(Value::Null, |time| {
+            Value::from(
+                DateTime::<Utc>::from_utc(time, Utc).to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            )
+        })
+}
+
+fn duration(value: &Option<prost_types::Duration>) -> Value {
+    value.as_ref().map_or(Value::Null, |d| {
+        if d.nanos == 0 {
+            Value::from(format!("{}s", d.seconds))
+        } else {
+            // seconds and nanos have the same sign, a duration shorter than a second has it on nanos only.
+            let sign = if d.seconds < 0 || d.nanos < 0 {
+                "-"
+            } else {
+                ""
+            };
+            Value::from(format!(
+                "{}{}.{:09}s",
+                sign,
+                d.seconds.unsigned_abs(),
+                d.nanos.unsigned_abs()
+            ))
+        }
+    })
+}
+
+fn height(value: &Option<Height>) -> Value {
+    value.as_ref().map_or(Value::Null, |h| {
+        json!({
+            "revision_number": h.revision_number.to_string(),
+            "revision_height": h.revision_height.to_string(),
+        })
+    })
+}
+
+/// contract messages are JSON documents, other payloads are kept as base64.
+fn contract_msg(bytes: &[u8]) -> Value {
+    serde_json::from_slice(bytes).unwrap_or_else(|_| Value::from(base64::encode(bytes)))
+}
+
+impl ToJson for bank::v1beta1::MsgSend {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "from_address": self.from_address,
+            "to_address": self.to_address,
+            "amount": coins(&self.amount),
+        })
+    }
+}
+
+impl ToJson for bank::v1beta1::MsgMultiSend {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|i| json!({ "address": i.address, "coins": coins(&i.coins) }))
+            .collect::<Vec<_>>();
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|o| json!({ "address": o.address, "coins": coins(&o.coins) }))
+            .collect::<Vec<_>>();
+
+        json!({ "inputs": inputs, "outputs": outputs })
+    }
+}
+
+impl ToJson for bank::v1beta1::SendAuthorization {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({ "spend_limit": coins(&self.spend_limit) })
+    }
+}
+
+fn description(value: &Option<staking::v1beta1::Description>) -> Value {
+    value.as_ref().map_or(Value::Null, |d| {
+        json!({
+            "moniker": d.moniker,
+            "identity": d.identity,
+            "website": d.website,
+            "security_contact": d.security_contact,
+            "details": d.details,
+        })
+    })
+}
+
+impl ToJson for staking::v1beta1::MsgCreateValidator {
+    fn to_json(&self, decoder: &MessageDecoder) -> Value {
+        let commission = self.commission.as_ref().map_or(Value::Null, |c| {
+            json!({
+                "rate": c.rate,
+                "max_rate": c.max_rate,
+                "max_change_rate": c.max_change_rate,
+            })
+        });
+
+        json!({
+            "description": description(&self.description),
+            "commission": commission,
+            "min_self_delegation": self.min_self_delegation,
+            "delegator_address": self.delegator_address,
+            "validator_address": self.validator_address,
+            "pubkey": decoder.decode_optional(&self.pubkey),
+            "value": optional_coin(&self.value),
+        })
+    }
+}
+
+impl ToJson for staking::v1beta1::MsgEditValidator {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "description": description(&self.description),
+            "validator_address": self.validator_address,
+            "commission_rate": self.commission_rate,
+            "min_self_delegation": self.min_self_delegation,
+        })
+    }
+}
+
+impl ToJson for staking::v1beta1::MsgDelegate {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "delegator_address": self.delegator_address,
+            "validator_address": self.validator_address,
+            "amount": optional_coin(&self.amount),
+        })
+    }
+}
+
+impl ToJson for staking::v1beta1::MsgBeginRedelegate {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "delegator_address": self.delegator_address,
+            "validator_src_address": self.validator_src_address,
+            "validator_dst_address": self.validator_dst_address,
+            "amount": optional_coin(&self.amount),
+        })
+    }
+}
+
+impl ToJson for staking::v1beta1::MsgUndelegate {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "delegator_address": self.delegator_address,
+            "validator_address": self.validator_address,
+            "amount": optional_coin(&self.amount),
+        })
+    }
+}
+
+impl ToJson for crypto::ed25519::PubKey {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({ "key": base64::encode(&self.key) })
+    }
+}
+
+impl ToJson for crypto::secp256k1::PubKey {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({ "key": base64::encode(&self.key) })
+    }
+}
+
+impl ToJson for distribution::v1beta1::MsgSetWithdrawAddress {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "delegator_address": self.delegator_address,
+            "withdraw_address": self.withdraw_address,
+        })
+    }
+}
+
+impl ToJson for distribution::v1beta1::MsgWithdrawDelegatorReward {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "delegator_address": self.delegator_address,
+            "validator_address": self.validator_address,
+        })
+    }
+}
+
+impl ToJson for distribution::v1beta1::MsgWithdrawValidatorCommission {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({ "validator_address": self.validator_address })
+    }
+}
+
+impl ToJson for distribution::v1beta1::MsgFundCommunityPool {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({ "amount": coins(&self.amount), "depositor": self.depositor })
+    }
+}
+
+/// options of `v1` votes have the same values and names as the ones of `v1beta1`.
+fn vote_option(option: i32) -> Value {
+    match gov::v1beta1::VoteOption::from_i32(option) {
+        Some(option) => Value::from(option.as_str_name()),
+        None => Value::from(option),
+    }
+}
+
+impl ToJson for gov::v1beta1::MsgSubmitProposal {
+    fn to_json(&self, decoder: &MessageDecoder) -> Value {
+        json!({
+            "content": decoder.decode_optional(&self.content),
+            "initial_deposit": coins(&self.initial_deposit),
+            "proposer": self.proposer,
+        })
+    }
+}
+
+impl ToJson for gov::v1beta1::MsgVote {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "proposal_id": self.proposal_id.to_string(),
+            "voter": self.voter,
+            "option": vote_option(self.option),
+        })
+    }
+}
+
+impl ToJson for gov::v1beta1::MsgVoteWeighted {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        let options = self
+            .options
+            .iter()
+            .map(|o| json!({ "option": vote_option(o.option), "weight": o.weight }))
+            .collect::<Vec<_>>();
+
+        json!({
+            "proposal_id": self.proposal_id.to_string(),
+            "voter": self.voter,
+            "options": options,
+        })
+    }
+}
+
+impl ToJson for gov::v1beta1::MsgDeposit {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "proposal_id": self.proposal_id.to_string(),
+            "depositor": self.depositor,
+            "amount": coins(&self.amount),
+        })
+    }
+}
+
+impl ToJson for gov::v1beta1::TextProposal {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({ "title": self.title, "description": self.description })
+    }
+}
+
+impl ToJson for gov_v1::MsgSubmitProposal {
+    fn to_json(&self, decoder: &MessageDecoder) -> Value {
+        let messages = self
+            .messages
+            .iter()
+            .map(|msg| decoder.decode(msg))
+            .collect::<Vec<_>>();
+
+        json!({
+            "messages": messages,
+            "initial_deposit": coins(&self.initial_deposit),
+            "proposer": self.proposer,
+            "metadata": self.metadata,
+            "title": self.title,
+            "summary": self.summary,
+            "expedited": self.expedited,
+        })
+    }
+}
+
+impl ToJson for gov_v1::MsgVote {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "proposal_id": self.proposal_id.to_string(),
+            "voter": self.voter,
+            "option": vote_option(self.option),
+            "metadata": self.metadata,
+        })
+    }
+}
+
+impl ToJson for gov_v1::MsgVoteWeighted {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        let options = self
+            .options
+            .iter()
+            .map(|o| json!({ "option": vote_option(o.option), "weight": o.weight }))
+            .collect::<Vec<_>>();
+
+        json!({
+            "proposal_id": self.proposal_id.to_string(),
+            "voter": self.voter,
+            "options": options,
+            "metadata": self.metadata,
+        })
+    }
+}
+
+impl ToJson for gov_v1::MsgDeposit {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "proposal_id": self.proposal_id.to_string(),
+            "depositor": self.depositor,
+            "amount": coins(&self.amount),
+        })
+    }
+}
+
+impl ToJson for authz::v1beta1::MsgGrant {
+    fn to_json(&self, decoder: &MessageDecoder) -> Value {
+        let grant = self.grant.as_ref().map_or(Value::Null, |g| {
+            json!({
+                "authorization": decoder.decode_optional(&g.authorization),
+                "expiration": timestamp(&g.expiration),
+            })
+        });
+
+        json!({
+            "granter": self.granter,
+            "grantee": self.grantee,
+            "grant": grant,
+        })
+    }
+}
+
+impl ToJson for authz::v1beta1::MsgExec {
+    fn to_json(&self, decoder: &MessageDecoder) -> Value {
+        let msgs = self
+            .msgs
+            .iter()
+            .map(|m| decoder.decode(m))
+            .collect::<Vec<_>>();
+
+        json!({ "grantee": self.grantee, "msgs": msgs })
+    }
+}
+
+impl ToJson for authz::v1beta1::MsgRevoke {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "granter": self.granter,
+            "grantee": self.grantee,
+            "msg_type_url": self.msg_type_url,
+        })
+    }
+}
+
+impl ToJson for authz::v1beta1::GenericAuthorization {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({ "msg": self.msg })
+    }
+}
+
+impl ToJson for feegrant::v1beta1::MsgGrantAllowance {
+    fn to_json(&self, decoder: &MessageDecoder) -> Value {
+        json!({
+            "granter": self.granter,
+            "grantee": self.grantee,
+            "allowance": decoder.decode_optional(&self.allowance),
+        })
+    }
+}
+
+impl ToJson for feegrant::v1beta1::MsgRevokeAllowance {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({ "granter": self.granter, "grantee": self.grantee })
+    }
+}
+
+impl ToJson for feegrant::v1beta1::BasicAllowance {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "spend_limit": coins(&self.spend_limit),
+            "expiration": timestamp(&self.expiration),
+        })
+    }
+}
+
+impl ToJson for feegrant::v1beta1::PeriodicAllowance {
+    fn to_json(&self, decoder: &MessageDecoder) -> Value {
+        json!({
+            "basic": self.basic.as_ref().map_or(Value::Null, |b| b.to_json(decoder)),
+            "period": duration(&self.period),
+            "period_spend_limit": coins(&self.period_spend_limit),
+            "period_can_spend": coins(&self.period_can_spend),
+            "period_reset": timestamp(&self.period_reset),
+        })
+    }
+}
+
+impl ToJson for feegrant::v1beta1::AllowedMsgAllowance {
+    fn to_json(&self, decoder: &MessageDecoder) -> Value {
+        json!({
+            "allowance": decoder.decode_optional(&self.allowance),
+            "allowed_messages": self.allowed_messages,
+        })
+    }
+}
+
+impl ToJson for transfer::v1::MsgTransfer {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "source_port": self.source_port,
+            "source_channel": self.source_channel,
+            "token": optional_coin(&self.token),
+            "sender": self.sender,
+            "receiver": self.receiver,
+            "timeout_height": height(&self.timeout_height),
+            "timeout_timestamp": self.timeout_timestamp.to_string(),
+        })
+    }
+}
+
+impl ToJson for wasm::v1::MsgStoreCode {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        let instantiate_permission =
+            self.instantiate_permission
+                .as_ref()
+                .map_or(Value::Null, |p| {
+                    let permission = match wasm::v1::AccessType::from_i32(p.permission) {
+                        Some(permission) => Value::from(permission.as_str_name()),
+                        None => Value::from(p.permission),
+                    };
+                    json!({ "permission": permission, "address": p.address })
+                });
+
+        json!({
+            "sender": self.sender,
+            "wasm_byte_code": base64::encode(&self.wasm_byte_code),
+            "instantiate_permission": instantiate_permission,
+        })
+    }
+}
+
+impl ToJson for wasm::v1::MsgInstantiateContract {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "sender": self.sender,
+            "admin": self.admin,
+            "code_id": self.code_id.to_string(),
+            "label": self.label,
+            "msg": contract_msg(&self.msg),
+            "funds": coins(&self.funds),
+        })
+    }
+}
+
+impl ToJson for wasm::v1::MsgExecuteContract {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "sender": self.sender,
+            "contract": self.contract,
+            "msg": contract_msg(&self.msg),
+            "funds": coins(&self.funds),
+        })
+    }
+}
+
+impl ToJson for wasm::v1::MsgMigrateContract {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "sender": self.sender,
+            "contract": self.contract,
+            "code_id": self.code_id.to_string(),
+            "msg": contract_msg(&self.msg),
+        })
+    }
+}
+
+impl ToJson for wasm::v1::MsgUpdateAdmin {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({
+            "sender": self.sender,
+            "new_admin": self.new_admin,
+            "contract": self.contract,
+        })
+    }
+}
+
+impl ToJson for wasm::v1::MsgClearAdmin {
+    fn to_json(&self, _: &MessageDecoder) -> Value {
+        json!({ "sender": self.sender, "contract": self.contract })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: &str = "cosmos1jv65s3grqf6v6jl3dp4t6c9t9rk99cd88lyufl";
+    const BOB: &str = "cosmos1xv9tklw7d82sezh9haa573wufgy59vmwe6xxe5";
+    const VALOPER: &str = "cosmosvaloper1sjllsnramtg3ewxqwwrwjxfgc4n4ef9u2lcnj0";
+    const CONTRACT: &str = "juno14hj2tavq8fpesdwxxcu44rty3hh90vhujrvcmstl4zr3txmfvw9skjuwg8";
+
+    fn any<T: Message>(type_url: &str, msg: T) -> Any {
+        Any {
+            type_url: type_url.to_string(),
+            value: msg.encode_to_vec(),
+        }
+    }
+
+    fn uatom(amount: &str) -> Coin {
+        Coin {
+            denom: "uatom".to_string(),
+            amount: amount.to_string(),
+        }
+    }
+
+    fn time() -> Option<prost_types::Timestamp> {
+        Some(prost_types::Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 0,
+        })
+    }
+
+    fn send() -> bank::v1beta1::MsgSend {
+        bank::v1beta1::MsgSend {
+            from_address: ALICE.to_string(),
+            to_address: BOB.to_string(),
+            amount: vec![uatom("1000")],
+        }
+    }
+
+    fn send_json() -> Value {
+        json!({
+            "@type": "/cosmos.bank.v1beta1.MsgSend",
+            "from_address": ALICE,
+            "to_address": BOB,
+            "amount": [{ "denom": "uatom", "amount": "1000" }],
+        })
+    }
+
+    fn basic_allowance() -> feegrant::v1beta1::BasicAllowance {
+        feegrant::v1beta1::BasicAllowance {
+            spend_limit: vec![uatom("500")],
+            expiration: time(),
+        }
+    }
+
+    /// a message of every registered type with its expected JSON.
+    fn cases() -> Vec<(Any, Value)> {
+        vec![
+            // bank
+            (any("/cosmos.bank.v1beta1.MsgSend", send()), send_json()),
+            (
+                any(
+                    "/cosmos.bank.v1beta1.MsgMultiSend",
+                    bank::v1beta1::MsgMultiSend {
+                        inputs: vec![bank::v1beta1::Input {
+                            address: ALICE.to_string(),
+                            coins: vec![uatom("2")],
+                        }],
+                        outputs: vec![bank::v1beta1::Output {
+                            address: BOB.to_string(),
+                            coins: vec![uatom("2")],
+                        }],
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.bank.v1beta1.MsgMultiSend",
+                    "inputs": [{ "address": ALICE, "coins": [{ "denom": "uatom", "amount": "2" }] }],
+                    "outputs": [{ "address": BOB, "coins": [{ "denom": "uatom", "amount": "2" }] }],
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.bank.v1beta1.SendAuthorization",
+                    bank::v1beta1::SendAuthorization {
+                        spend_limit: vec![uatom("10")],
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.bank.v1beta1.SendAuthorization",
+                    "spend_limit": [{ "denom": "uatom", "amount": "10" }],
+                }),
+            ),
+            // staking
+            (
+                any(
+                    "/cosmos.staking.v1beta1.MsgCreateValidator",
+                    staking::v1beta1::MsgCreateValidator {
+                        description: Some(staking::v1beta1::Description {
+                            moniker: "node".to_string(),
+                            ..Default::default()
+                        }),
+                        commission: Some(staking::v1beta1::CommissionRates {
+                            rate: "100000000000000000".to_string(),
+                            max_rate: "200000000000000000".to_string(),
+                            max_change_rate: "10000000000000000".to_string(),
+                        }),
+                        min_self_delegation: "1".to_string(),
+                        delegator_address: ALICE.to_string(),
+                        validator_address: VALOPER.to_string(),
+                        pubkey: Some(any(
+                            "/cosmos.crypto.ed25519.PubKey",
+                            crypto::ed25519::PubKey { key: vec![1, 2, 3] },
+                        )),
+                        value: Some(uatom("1000000")),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.staking.v1beta1.MsgCreateValidator",
+                    "description": {
+                        "moniker": "node",
+                        "identity": "",
+                        "website": "",
+                        "security_contact": "",
+                        "details": "",
+                    },
+                    "commission": {
+                        "rate": "100000000000000000",
+                        "max_rate": "200000000000000000",
+                        "max_change_rate": "10000000000000000",
+                    },
+                    "min_self_delegation": "1",
+                    "delegator_address": ALICE,
+                    "validator_address": VALOPER,
+                    "pubkey": { "@type": "/cosmos.crypto.ed25519.PubKey", "key": "AQID" },
+                    "value": { "denom": "uatom", "amount": "1000000" },
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.staking.v1beta1.MsgEditValidator",
+                    staking::v1beta1::MsgEditValidator {
+                        description: None,
+                        validator_address: VALOPER.to_string(),
+                        commission_rate: "50000000000000000".to_string(),
+                        min_self_delegation: String::new(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.staking.v1beta1.MsgEditValidator",
+                    "description": null,
+                    "validator_address": VALOPER,
+                    "commission_rate": "50000000000000000",
+                    "min_self_delegation": "",
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.staking.v1beta1.MsgDelegate",
+                    staking::v1beta1::MsgDelegate {
+                        delegator_address: ALICE.to_string(),
+                        validator_address: VALOPER.to_string(),
+                        amount: Some(uatom("5")),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.staking.v1beta1.MsgDelegate",
+                    "delegator_address": ALICE,
+                    "validator_address": VALOPER,
+                    "amount": { "denom": "uatom", "amount": "5" },
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.staking.v1beta1.MsgBeginRedelegate",
+                    staking::v1beta1::MsgBeginRedelegate {
+                        delegator_address: ALICE.to_string(),
+                        validator_src_address: VALOPER.to_string(),
+                        validator_dst_address: VALOPER.to_string(),
+                        amount: None,
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.staking.v1beta1.MsgBeginRedelegate",
+                    "delegator_address": ALICE,
+                    "validator_src_address": VALOPER,
+                    "validator_dst_address": VALOPER,
+                    "amount": null,
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.staking.v1beta1.MsgUndelegate",
+                    staking::v1beta1::MsgUndelegate {
+                        delegator_address: ALICE.to_string(),
+                        validator_address: VALOPER.to_string(),
+                        amount: Some(uatom("5")),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.staking.v1beta1.MsgUndelegate",
+                    "delegator_address": ALICE,
+                    "validator_address": VALOPER,
+                    "amount": { "denom": "uatom", "amount": "5" },
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.crypto.ed25519.PubKey",
+                    crypto::ed25519::PubKey { key: vec![255; 3] },
+                ),
+                json!({ "@type": "/cosmos.crypto.ed25519.PubKey", "key": "////" }),
+            ),
+            (
+                any(
+                    "/cosmos.crypto.secp256k1.PubKey",
+                    crypto::secp256k1::PubKey { key: vec![2, 0, 1] },
+                ),
+                json!({ "@type": "/cosmos.crypto.secp256k1.PubKey", "key": "AgAB" }),
+            ),
+            // distribution
+            (
+                any(
+                    "/cosmos.distribution.v1beta1.MsgSetWithdrawAddress",
+                    distribution::v1beta1::MsgSetWithdrawAddress {
+                        delegator_address: ALICE.to_string(),
+                        withdraw_address: BOB.to_string(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.distribution.v1beta1.MsgSetWithdrawAddress",
+                    "delegator_address": ALICE,
+                    "withdraw_address": BOB,
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward",
+                    distribution::v1beta1::MsgWithdrawDelegatorReward {
+                        delegator_address: ALICE.to_string(),
+                        validator_address: VALOPER.to_string(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward",
+                    "delegator_address": ALICE,
+                    "validator_address": VALOPER,
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission",
+                    distribution::v1beta1::MsgWithdrawValidatorCommission {
+                        validator_address: VALOPER.to_string(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission",
+                    "validator_address": VALOPER,
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.distribution.v1beta1.MsgFundCommunityPool",
+                    distribution::v1beta1::MsgFundCommunityPool {
+                        amount: vec![uatom("7")],
+                        depositor: ALICE.to_string(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.distribution.v1beta1.MsgFundCommunityPool",
+                    "amount": [{ "denom": "uatom", "amount": "7" }],
+                    "depositor": ALICE,
+                }),
+            ),
+            // gov
+            (
+                any(
+                    "/cosmos.gov.v1beta1.MsgSubmitProposal",
+                    gov::v1beta1::MsgSubmitProposal {
+                        content: Some(any(
+                            "/cosmos.gov.v1beta1.TextProposal",
+                            gov::v1beta1::TextProposal {
+                                title: "title".to_string(),
+                                description: "description".to_string(),
+                            },
+                        )),
+                        initial_deposit: vec![uatom("100")],
+                        proposer: ALICE.to_string(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.gov.v1beta1.MsgSubmitProposal",
+                    "content": {
+                        "@type": "/cosmos.gov.v1beta1.TextProposal",
+                        "title": "title",
+                        "description": "description",
+                    },
+                    "initial_deposit": [{ "denom": "uatom", "amount": "100" }],
+                    "proposer": ALICE,
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.gov.v1beta1.MsgVote",
+                    gov::v1beta1::MsgVote {
+                        proposal_id: 42,
+                        voter: ALICE.to_string(),
+                        option: gov::v1beta1::VoteOption::Yes as i32,
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.gov.v1beta1.MsgVote",
+                    "proposal_id": "42",
+                    "voter": ALICE,
+                    "option": "VOTE_OPTION_YES",
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.gov.v1beta1.MsgVoteWeighted",
+                    gov::v1beta1::MsgVoteWeighted {
+                        proposal_id: 42,
+                        voter: ALICE.to_string(),
+                        options: vec![
+                            gov::v1beta1::WeightedVoteOption {
+                                option: gov::v1beta1::VoteOption::No as i32,
+                                weight: "700000000000000000".to_string(),
+                            },
+                            // an option unknown to this version is kept as its number.
+                            gov::v1beta1::WeightedVoteOption {
+                                option: 9,
+                                weight: "300000000000000000".to_string(),
+                            },
+                        ],
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.gov.v1beta1.MsgVoteWeighted",
+                    "proposal_id": "42",
+                    "voter": ALICE,
+                    "options": [
+                        { "option": "VOTE_OPTION_NO", "weight": "700000000000000000" },
+                        { "option": 9, "weight": "300000000000000000" },
+                    ],
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.gov.v1beta1.MsgDeposit",
+                    gov::v1beta1::MsgDeposit {
+                        proposal_id: 42,
+                        depositor: BOB.to_string(),
+                        amount: vec![uatom("3")],
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.gov.v1beta1.MsgDeposit",
+                    "proposal_id": "42",
+                    "depositor": BOB,
+                    "amount": [{ "denom": "uatom", "amount": "3" }],
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.gov.v1beta1.TextProposal",
+                    gov::v1beta1::TextProposal {
+                        title: "t".to_string(),
+                        description: "d".to_string(),
+                    },
+                ),
+                json!({ "@type": "/cosmos.gov.v1beta1.TextProposal", "title": "t", "description": "d" }),
+            ),
+            (
+                any(
+                    "/cosmos.gov.v1.MsgSubmitProposal",
+                    gov_v1::MsgSubmitProposal {
+                        messages: vec![any("/cosmos.bank.v1beta1.MsgSend", send())],
+                        initial_deposit: vec![uatom("100")],
+                        proposer: ALICE.to_string(),
+                        metadata: "ipfs://CID".to_string(),
+                        title: "title".to_string(),
+                        summary: "summary".to_string(),
+                        expedited: true,
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.gov.v1.MsgSubmitProposal",
+                    "messages": [send_json()],
+                    "initial_deposit": [{ "denom": "uatom", "amount": "100" }],
+                    "proposer": ALICE,
+                    "metadata": "ipfs://CID",
+                    "title": "title",
+                    "summary": "summary",
+                    "expedited": true,
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.gov.v1.MsgVote",
+                    gov_v1::MsgVote {
+                        proposal_id: 42,
+                        voter: ALICE.to_string(),
+                        option: gov::v1beta1::VoteOption::NoWithVeto as i32,
+                        metadata: String::new(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.gov.v1.MsgVote",
+                    "proposal_id": "42",
+                    "voter": ALICE,
+                    "option": "VOTE_OPTION_NO_WITH_VETO",
+                    "metadata": "",
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.gov.v1.MsgVoteWeighted",
+                    gov_v1::MsgVoteWeighted {
+                        proposal_id: 42,
+                        voter: ALICE.to_string(),
+                        options: vec![gov_v1::WeightedVoteOption {
+                            option: gov::v1beta1::VoteOption::Abstain as i32,
+                            weight: "1.000000000000000000".to_string(),
+                        }],
+                        metadata: "reason".to_string(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.gov.v1.MsgVoteWeighted",
+                    "proposal_id": "42",
+                    "voter": ALICE,
+                    "options": [
+                        { "option": "VOTE_OPTION_ABSTAIN", "weight": "1.000000000000000000" },
+                    ],
+                    "metadata": "reason",
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.gov.v1.MsgDeposit",
+                    gov_v1::MsgDeposit {
+                        proposal_id: 42,
+                        depositor: BOB.to_string(),
+                        amount: vec![uatom("3")],
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.gov.v1.MsgDeposit",
+                    "proposal_id": "42",
+                    "depositor": BOB,
+                    "amount": [{ "denom": "uatom", "amount": "3" }],
+                }),
+            ),
+            // authz
+            (
+                any(
+                    "/cosmos.authz.v1beta1.MsgGrant",
+                    authz::v1beta1::MsgGrant {
+                        granter: ALICE.to_string(),
+                        grantee: BOB.to_string(),
+                        grant: Some(authz::v1beta1::Grant {
+                            authorization: Some(any(
+                                "/cosmos.authz.v1beta1.GenericAuthorization",
+                                authz::v1beta1::GenericAuthorization {
+                                    msg: "/cosmos.gov.v1beta1.MsgVote".to_string(),
+                                },
+                            )),
+                            expiration: time(),
+                        }),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.authz.v1beta1.MsgGrant",
+                    "granter": ALICE,
+                    "grantee": BOB,
+                    "grant": {
+                        "authorization": {
+                            "@type": "/cosmos.authz.v1beta1.GenericAuthorization",
+                            "msg": "/cosmos.gov.v1beta1.MsgVote",
+                        },
+                        "expiration": "2023-11-14T22:13:20Z",
+                    },
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.authz.v1beta1.MsgExec",
+                    authz::v1beta1::MsgExec {
+                        grantee: BOB.to_string(),
+                        msgs: vec![any("/cosmos.bank.v1beta1.MsgSend", send())],
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.authz.v1beta1.MsgExec",
+                    "grantee": BOB,
+                    "msgs": [send_json()],
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.authz.v1beta1.MsgRevoke",
+                    authz::v1beta1::MsgRevoke {
+                        granter: ALICE.to_string(),
+                        grantee: BOB.to_string(),
+                        msg_type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.authz.v1beta1.MsgRevoke",
+                    "granter": ALICE,
+                    "grantee": BOB,
+                    "msg_type_url": "/cosmos.bank.v1beta1.MsgSend",
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.authz.v1beta1.GenericAuthorization",
+                    authz::v1beta1::GenericAuthorization {
+                        msg: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.authz.v1beta1.GenericAuthorization",
+                    "msg": "/cosmos.bank.v1beta1.MsgSend",
+                }),
+            ),
+            // feegrant
+            (
+                any(
+                    "/cosmos.feegrant.v1beta1.MsgGrantAllowance",
+                    feegrant::v1beta1::MsgGrantAllowance {
+                        granter: ALICE.to_string(),
+                        grantee: BOB.to_string(),
+                        allowance: Some(any(
+                            "/cosmos.feegrant.v1beta1.BasicAllowance",
+                            basic_allowance(),
+                        )),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.feegrant.v1beta1.MsgGrantAllowance",
+                    "granter": ALICE,
+                    "grantee": BOB,
+                    "allowance": {
+                        "@type": "/cosmos.feegrant.v1beta1.BasicAllowance",
+                        "spend_limit": [{ "denom": "uatom", "amount": "500" }],
+                        "expiration": "2023-11-14T22:13:20Z",
+                    },
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.feegrant.v1beta1.MsgRevokeAllowance",
+                    feegrant::v1beta1::MsgRevokeAllowance {
+                        granter: ALICE.to_string(),
+                        grantee: BOB.to_string(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.feegrant.v1beta1.MsgRevokeAllowance",
+                    "granter": ALICE,
+                    "grantee": BOB,
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.feegrant.v1beta1.BasicAllowance",
+                    feegrant::v1beta1::BasicAllowance {
+                        spend_limit: vec![],
+                        expiration: None,
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.feegrant.v1beta1.BasicAllowance",
+                    "spend_limit": [],
+                    "expiration": null,
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.feegrant.v1beta1.PeriodicAllowance",
+                    feegrant::v1beta1::PeriodicAllowance {
+                        basic: Some(basic_allowance()),
+                        period: Some(prost_types::Duration {
+                            seconds: 86400,
+                            nanos: 500_000_000,
+                        }),
+                        period_spend_limit: vec![uatom("50")],
+                        period_can_spend: vec![uatom("20")],
+                        period_reset: time(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.feegrant.v1beta1.PeriodicAllowance",
+                    "basic": {
+                        "spend_limit": [{ "denom": "uatom", "amount": "500" }],
+                        "expiration": "2023-11-14T22:13:20Z",
+                    },
+                    "period": "86400.500000000s",
+                    "period_spend_limit": [{ "denom": "uatom", "amount": "50" }],
+                    "period_can_spend": [{ "denom": "uatom", "amount": "20" }],
+                    "period_reset": "2023-11-14T22:13:20Z",
+                }),
+            ),
+            // a negative duration shorter than a second carries the sign on nanos only.
+            (
+                any(
+                    "/cosmos.feegrant.v1beta1.PeriodicAllowance",
+                    feegrant::v1beta1::PeriodicAllowance {
+                        basic: None,
+                        period: Some(prost_types::Duration {
+                            seconds: 0,
+                            nanos: -500_000_000,
+                        }),
+                        period_spend_limit: vec![],
+                        period_can_spend: vec![],
+                        period_reset: None,
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.feegrant.v1beta1.PeriodicAllowance",
+                    "basic": null,
+                    "period": "-0.500000000s",
+                    "period_spend_limit": [],
+                    "period_can_spend": [],
+                    "period_reset": null,
+                }),
+            ),
+            (
+                any(
+                    "/cosmos.feegrant.v1beta1.AllowedMsgAllowance",
+                    feegrant::v1beta1::AllowedMsgAllowance {
+                        allowance: None,
+                        allowed_messages: vec!["/cosmos.gov.v1beta1.MsgVote".to_string()],
+                    },
+                ),
+                json!({
+                    "@type": "/cosmos.feegrant.v1beta1.AllowedMsgAllowance",
+                    "allowance": null,
+                    "allowed_messages": ["/cosmos.gov.v1beta1.MsgVote"],
+                }),
+            ),
+            // ibc transfer
+            (
+                any(
+                    "/ibc.applications.transfer.v1.MsgTransfer",
+                    transfer::v1::MsgTransfer {
+                        source_port: "transfer".to_string(),
+                        source_channel: "channel-141".to_string(),
+                        token: Some(uatom("1")),
+                        sender: ALICE.to_string(),
+                        receiver: "osmo1jv65s3grqf6v6jl3dp4t6c9t9rk99cd80yhvld".to_string(),
+                        timeout_height: Some(Height {
+                            revision_number: 1,
+                            revision_height: 12_000_000,
+                        }),
+                        timeout_timestamp: 1_700_000_000_000_000_000,
+                    },
+                ),
+                json!({
+                    "@type": "/ibc.applications.transfer.v1.MsgTransfer",
+                    "source_port": "transfer",
+                    "source_channel": "channel-141",
+                    "token": { "denom": "uatom", "amount": "1" },
+                    "sender": ALICE,
+                    "receiver": "osmo1jv65s3grqf6v6jl3dp4t6c9t9rk99cd80yhvld",
+                    "timeout_height": { "revision_number": "1", "revision_height": "12000000" },
+                    "timeout_timestamp": "1700000000000000000",
+                }),
+            ),
+            // wasm
+            (
+                any(
+                    "/cosmwasm.wasm.v1.MsgStoreCode",
+                    wasm::v1::MsgStoreCode {
+                        sender: ALICE.to_string(),
+                        wasm_byte_code: vec![0, 97, 115, 109],
+                        instantiate_permission: Some(wasm::v1::AccessConfig {
+                            permission: wasm::v1::AccessType::OnlyAddress as i32,
+                            address: ALICE.to_string(),
+                        }),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmwasm.wasm.v1.MsgStoreCode",
+                    "sender": ALICE,
+                    "wasm_byte_code": "AGFzbQ==",
+                    "instantiate_permission": {
+                        "permission": "ACCESS_TYPE_ONLY_ADDRESS",
+                        "address": ALICE,
+                    },
+                }),
+            ),
+            (
+                any(
+                    "/cosmwasm.wasm.v1.MsgInstantiateContract",
+                    wasm::v1::MsgInstantiateContract {
+                        sender: ALICE.to_string(),
+                        admin: String::new(),
+                        code_id: 7,
+                        label: "counter".to_string(),
+                        msg: br#"{"count":0}"#.to_vec(),
+                        funds: vec![],
+                    },
+                ),
+                json!({
+                    "@type": "/cosmwasm.wasm.v1.MsgInstantiateContract",
+                    "sender": ALICE,
+                    "admin": "",
+                    "code_id": "7",
+                    "label": "counter",
+                    "msg": { "count": 0 },
+                    "funds": [],
+                }),
+            ),
+            (
+                any(
+                    "/cosmwasm.wasm.v1.MsgExecuteContract",
+                    wasm::v1::MsgExecuteContract {
+                        sender: ALICE.to_string(),
+                        contract: CONTRACT.to_string(),
+                        msg: br#"{"increment":{}}"#.to_vec(),
+                        funds: vec![uatom("1")],
+                    },
+                ),
+                json!({
+                    "@type": "/cosmwasm.wasm.v1.MsgExecuteContract",
+                    "sender": ALICE,
+                    "contract": CONTRACT,
+                    "msg": { "increment": {} },
+                    "funds": [{ "denom": "uatom", "amount": "1" }],
+                }),
+            ),
+            (
+                any(
+                    "/cosmwasm.wasm.v1.MsgMigrateContract",
+                    wasm::v1::MsgMigrateContract {
+                        sender: ALICE.to_string(),
+                        contract: CONTRACT.to_string(),
+                        code_id: 8,
+                        // a payload which isn't JSON is kept as base64.
+                        msg: vec![0xff, 0x00],
+                    },
+                ),
+                json!({
+                    "@type": "/cosmwasm.wasm.v1.MsgMigrateContract",
+                    "sender": ALICE,
+                    "contract": CONTRACT,
+                    "code_id": "8",
+                    "msg": "/wA=",
+                }),
+            ),
+            (
+                any(
+                    "/cosmwasm.wasm.v1.MsgUpdateAdmin",
+                    wasm::v1::MsgUpdateAdmin {
+                        sender: ALICE.to_string(),
+                        new_admin: BOB.to_string(),
+                        contract: CONTRACT.to_string(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmwasm.wasm.v1.MsgUpdateAdmin",
+                    "sender": ALICE,
+                    "new_admin": BOB,
+                    "contract": CONTRACT,
+                }),
+            ),
+            (
+                any(
+                    "/cosmwasm.wasm.v1.MsgClearAdmin",
+                    wasm::v1::MsgClearAdmin {
+                        sender: ALICE.to_string(),
+                        contract: CONTRACT.to_string(),
+                    },
+                ),
+                json!({
+                    "@type": "/cosmwasm.wasm.v1.MsgClearAdmin",
+                    "sender": ALICE,
+                    "contract": CONTRACT,
+                }),
+            ),
+        ]
+    }
+
+    #[test]
+    fn decodes_every_registered_type() {
+        let decoder = MessageDecoder::default();
+        let cases = cases();
+
+        let mut tested = cases
+            .iter()
+            .map(|(any, _)| any.type_url.as_str())
+            .collect::<Vec<_>>();
+        tested.sort_unstable();
+        tested.dedup();
+        let mut registered = decoder
+            .decoders
+            .keys()
+            .map(|k| k.as_str())
+            .collect::<Vec<_>>();
+        registered.sort_unstable();
+        assert_eq!(tested, registered);
+
+        for (any, expected) in cases.iter() {
+            assert_eq!(&decoder.decode(any), expected, "{}", any.type_url);
+        }
+    }
+
+    #[test]
+    fn falls_back_to_base64_for_unknown_types() {
+        let decoder = MessageDecoder::default();
+        let any = Any {
+            type_url: "/osmosis.gamm.v1beta1.MsgSwapExactAmountIn".to_string(),
+            value: vec![10, 3, 97, 98, 99],
+        };
+
+        assert_eq!(
+            decoder.decode(&any),
+            json!({ "@type": "/osmosis.gamm.v1beta1.MsgSwapExactAmountIn", "value": "CgNhYmM=" })
+        );
+    }
+
+    #[test]
+    fn falls_back_to_base64_for_undecodable_messages() {
+        let decoder = MessageDecoder::default();
+        // a length-delimited field which is longer than the message.
+        let any = Any {
+            type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+            value: vec![10, 50, 97],
+        };
+
+        assert_eq!(
+            decoder.decode(&any),
+            json!({ "@type": "/cosmos.bank.v1beta1.MsgSend", "value": "CjJh" })
+        );
+    }
+}
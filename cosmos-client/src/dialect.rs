@@ -0,0 +1,501 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use tendermint::block;
+
+use crate::response::{BlockResult, Event, EventType};
+
+/// AbciDialect is the flavour of ABCI results a node returns.
+/// It's detected from the node version reported by `/status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbciDialect {
+    /// Tendermint 0.34, event attributes are base64 encoded.
+    Tendermint034,
+    /// CometBFT 0.37, event attributes are plain strings.
+    CometBft037,
+    /// CometBFT 0.38 and later, begin/end block events are replaced by `finalize_block_events`.
+    CometBft038,
+}
+
+impl AbciDialect {
+    /// detects the dialect from a node version such as `0.34.21` or `v0.38.2`.
+    /// versions which cannot be parsed are treated as Tendermint 0.34.
+    pub fn from_version(version: &str) -> Self {
+        let mut parts = version
+            .trim_start_matches('v')
+            .split('.')
+            .map(|part| part.parse::<u32>().ok());
+
+        match (parts.next().flatten(), parts.next().flatten()) {
+            (Some(0), Some(minor)) if minor >= 38 => AbciDialect::CometBft038,
+            (Some(0), Some(37)) => AbciDialect::CometBft037,
+            (Some(major), Some(_)) if major >= 1 => AbciDialect::CometBft038,
+            _ => AbciDialect::Tendermint034,
+        }
+    }
+
+    fn base64_attributes(&self) -> bool {
+        *self == AbciDialect::Tendermint034
+    }
+}
+
+/// BlockResultsRequest is `/block_results` with a response which parses every dialect.
+/// The response of tendermint-rpc only knows about Tendermint 0.34.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct BlockResultsRequest {
+    pub height: block::Height,
+}
+
+impl tendermint_rpc::Request for BlockResultsRequest {
+    type Response = RawBlockResults;
+
+    fn method(&self) -> tendermint_rpc::Method {
+        tendermint_rpc::Method::BlockResults
+    }
+}
+
+impl tendermint_rpc::SimpleRequest for BlockResultsRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RawBlockResults {
+    #[serde(default)]
+    pub txs_results: Option<Vec<RawTxResult>>,
+    #[serde(default)]
+    pub begin_block_events: Option<Vec<RawEvent>>,
+    #[serde(default)]
+    pub end_block_events: Option<Vec<RawEvent>>,
+    #[serde(default)]
+    pub finalize_block_events: Option<Vec<RawEvent>>,
+}
+
+impl tendermint_rpc::Response for RawBlockResults {}
+
+/// RawTxResult is `ResponseDeliverTx` before 0.38 and `ExecTxResult` after.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RawTxResult {
+    #[serde(default)]
+    pub code: u32,
+    #[serde(default)]
+    pub data: Option<String>,
+    #[serde(default)]
+    pub log: Option<String>,
+    #[serde(default)]
+    pub info: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_i64")]
+    pub gas_wanted: i64,
+    #[serde(default, deserialize_with = "deserialize_i64")]
+    pub gas_used: i64,
+    #[serde(default)]
+    pub events: Option<Vec<RawEvent>>,
+    #[serde(default)]
+    pub codespace: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RawEvent {
+    #[serde(rename = "type")]
+    pub type_str: String,
+    #[serde(default)]
+    pub attributes: Option<Vec<RawAttribute>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RawAttribute {
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub index: Option<bool>,
+}
+
+/// TxResult is the normalized result of a transaction in a block.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TxResult {
+    pub code: i32,
+    pub codespace: String,
+    /// hex encoded, the same as `TxResponse.data`.
+    pub data: String,
+    pub log: String,
+    pub info: String,
+    pub gas_wanted: i64,
+    pub gas_used: i64,
+    /// events without the transaction hash, it's filled by the caller.
+    pub events: Vec<Event>,
+}
+
+impl RawBlockResults {
+    /// converts the results into the shapes shared by every dialect.
+    /// `finalize_block_events` are split into begin/end block events by their `mode` attribute,
+    /// events without it are treated as end block events.
+    pub fn normalize(self, height: i64, dialect: AbciDialect) -> (BlockResult, Vec<TxResult>) {
+        let mut result = BlockResult {
+            height,
+            begin_block_events: convert_events(
+                self.begin_block_events.unwrap_or_default(),
+                height,
+                EventType::BeginBlock,
+                dialect,
+            ),
+            end_block_events: convert_events(
+                self.end_block_events.unwrap_or_default(),
+                height,
+                EventType::EndBlock,
+                dialect,
+            ),
+        };
+
+        let finalize_block_events = convert_events(
+            self.finalize_block_events.unwrap_or_default(),
+            height,
+            EventType::EndBlock,
+            dialect,
+        );
+        let begin_block_seqs = finalize_block_events
+            .iter()
+            .filter(|evt| evt.event_key == "mode" && evt.event_value == "BeginBlock")
+            .map(|evt| evt.event_seq)
+            .collect::<Vec<_>>();
+        for evt in finalize_block_events {
+            if begin_block_seqs.contains(&evt.event_seq) {
+                result.begin_block_events.push(Event {
+                    tx_type: EventType::BeginBlock,
+                    ..evt
+                });
+            } else {
+                result.end_block_events.push(evt);
+            }
+        }
+
+        let tx_results = self
+            .txs_results
+            .unwrap_or_default()
+            .into_iter()
+            .map(|tx| TxResult {
+                code: tx.code as i32,
+                codespace: tx.codespace.unwrap_or_default(),
+                data: tx
+                    .data
+                    .and_then(|data| base64::decode(data).ok())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|b| format!("{:02X}", b))
+                    .collect(),
+                log: tx.log.unwrap_or_default(),
+                info: tx.info.unwrap_or_default(),
+                gas_wanted: tx.gas_wanted,
+                gas_used: tx.gas_used,
+                events: convert_events(
+                    tx.events.unwrap_or_default(),
+                    height,
+                    EventType::Transaction,
+                    dialect,
+                ),
+            })
+            .collect();
+
+        (result, tx_results)
+    }
+}
+
+fn convert_events(
+    raw_events: Vec<RawEvent>,
+    height: i64,
+    event_type: EventType,
+    dialect: AbciDialect,
+) -> Vec<Event> {
+    let decode = |raw: Option<String>| {
+        let raw = raw.unwrap_or_default();
+        if !dialect.base64_attributes() {
+            return raw;
+        }
+        // values which do not decode to a string are kept as they are.
+        match base64::decode(&raw).map(String::from_utf8) {
+            Ok(Ok(decoded)) => decoded,
+            _ => raw,
+        }
+    };
+
+    let mut events = vec![];
+    for (seq, evt) in raw_events.into_iter().enumerate() {
+        for (attr_seq, attr) in evt.attributes.unwrap_or_default().into_iter().enumerate() {
+            events.push(Event {
+                tx_type: event_type.clone(),
+                tx_hash: None,
+                block_height: height,
+                event_seq: seq as i32,
+                attr_seq: attr_seq as i32,
+                event_type: evt.type_str.clone(),
+                event_key: decode(attr.key),
+                event_value: decode(attr.value),
+                indexed: attr.index.unwrap_or(false),
+            });
+        }
+    }
+
+    events
+}
+
+/// 64-bit integers are strings in the JSON-RPC responses, but some nodes send numbers.
+fn deserialize_i64<'de, D>(deserializer: D) -> Result<i64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(i64),
+    }
+
+    match Option::<StringOrNumber>::deserialize(deserializer)? {
+        Some(StringOrNumber::String(s)) => s.parse().map_err(serde::de::Error::custom),
+        Some(StringOrNumber::Number(n)) => Ok(n),
+        None => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `/block_results` of a gaia node on Tendermint 0.34, attributes are base64 encoded.
+    const BLOCK_RESULTS_0_34: &str = r#"{
+        "height": "12",
+        "txs_results": [
+            {
+                "code": 0,
+                "data": "Ch4KHC9jb3Ntb3MuYmFuay52MWJldGExLk1zZ1NlbmQ=",
+                "log": "[{\"events\":[]}]",
+                "info": "",
+                "gas_wanted": "200000",
+                "gas_used": "61887",
+                "events": [
+                    {
+                        "type": "message",
+                        "attributes": [
+                            {"key": "YWN0aW9u", "value": "L2Nvc21vcy5iYW5rLnYxYmV0YTEuTXNnU2VuZA==", "index": true},
+                            {"key": "bW9kdWxl", "value": "YmFuaw==", "index": false}
+                        ]
+                    }
+                ],
+                "codespace": ""
+            }
+        ],
+        "begin_block_events": [
+            {
+                "type": "mint",
+                "attributes": [
+                    {"key": "YW1vdW50", "value": "MTAwMHVhdG9t", "index": true}
+                ]
+            }
+        ],
+        "end_block_events": null,
+        "validator_updates": null,
+        "consensus_param_updates": {"block": {"max_bytes": "22020096", "max_gas": "-1"}}
+    }"#;
+
+    /// `/block_results` of CometBFT 0.37, attributes are plain strings.
+    const BLOCK_RESULTS_0_37: &str = r#"{
+        "height": "12",
+        "txs_results": [
+            {
+                "code": 5,
+                "data": null,
+                "log": "insufficient funds",
+                "info": "",
+                "gas_wanted": "200000",
+                "gas_used": "48712",
+                "events": [
+                    {
+                        "type": "tx",
+                        "attributes": [
+                            {"key": "fee", "value": "500uatom", "index": true},
+                            {"key": "signature", "value": "dGVzdA==", "index": true}
+                        ]
+                    }
+                ],
+                "codespace": "sdk"
+            }
+        ],
+        "begin_block_events": [
+            {
+                "type": "mint",
+                "attributes": [
+                    {"key": "amount", "value": "1000uatom", "index": true}
+                ]
+            }
+        ],
+        "end_block_events": [],
+        "validator_updates": null,
+        "consensus_param_updates": null
+    }"#;
+
+    /// `/block_results` of CometBFT 0.38, begin/end block events are a part of `finalize_block_events`.
+    const BLOCK_RESULTS_0_38: &str = r#"{
+        "height": "12",
+        "txs_results": [],
+        "finalize_block_events": [
+            {
+                "type": "mint",
+                "attributes": [
+                    {"key": "amount", "value": "1000uatom", "index": true},
+                    {"key": "mode", "value": "BeginBlock", "index": true}
+                ]
+            },
+            {
+                "type": "complete_unbonding",
+                "attributes": [
+                    {"key": "amount", "value": "250uatom", "index": true},
+                    {"key": "mode", "value": "EndBlock", "index": true}
+                ]
+            },
+            {
+                "type": "upgrade",
+                "attributes": [
+                    {"key": "name", "value": "v15", "index": true}
+                ]
+            }
+        ],
+        "validator_updates": [],
+        "consensus_param_updates": null,
+        "app_hash": "4F2A7BB3C2A5C1E4A6D3A58D9C11B7F6A3E3D8E0C6D3A9B1F0E5D2C7B8A9F0E1"
+    }"#;
+
+    fn normalize(json: &str, dialect: AbciDialect) -> (BlockResult, Vec<TxResult>) {
+        serde_json::from_str::<RawBlockResults>(json)
+            .unwrap()
+            .normalize(12, dialect)
+    }
+
+    fn attributes(events: &[Event]) -> Vec<(i32, &str, &str, &str)> {
+        events
+            .iter()
+            .map(|e| {
+                (
+                    e.event_seq,
+                    e.event_type.as_str(),
+                    e.event_key.as_str(),
+                    e.event_value.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detects_dialect_from_version() {
+        let cases = [
+            ("0.34.21", AbciDialect::Tendermint034),
+            ("v0.34.27", AbciDialect::Tendermint034),
+            ("0.37.2", AbciDialect::CometBft037),
+            ("v0.37.4", AbciDialect::CometBft037),
+            ("0.38.0", AbciDialect::CometBft038),
+            ("v0.38.12", AbciDialect::CometBft038),
+            ("1.0.0", AbciDialect::CometBft038),
+            ("", AbciDialect::Tendermint034),
+            ("unknown", AbciDialect::Tendermint034),
+        ];
+
+        for (version, dialect) in cases {
+            assert_eq!(AbciDialect::from_version(version), dialect, "{}", version);
+        }
+    }
+
+    #[test]
+    fn decodes_base64_attributes_of_0_34() {
+        let (result, txs) = normalize(BLOCK_RESULTS_0_34, AbciDialect::Tendermint034);
+
+        assert_eq!(
+            attributes(&result.begin_block_events),
+            vec![(0, "mint", "amount", "1000uatom")]
+        );
+        assert!(result.end_block_events.is_empty());
+
+        assert_eq!(txs.len(), 1);
+        assert_eq!(
+            txs[0].data,
+            "0A1E0A1C2F636F736D6F732E62616E6B2E763162657461312E4D736753656E64"
+        );
+        assert_eq!((txs[0].gas_wanted, txs[0].gas_used), (200000, 61887));
+        assert_eq!(
+            attributes(&txs[0].events),
+            vec![
+                (0, "message", "action", "/cosmos.bank.v1beta1.MsgSend"),
+                (0, "message", "module", "bank"),
+            ]
+        );
+        assert!(txs[0].events[0].indexed);
+        assert!(!txs[0].events[1].indexed);
+        assert_eq!(txs[0].events[0].tx_type, EventType::Transaction);
+    }
+
+    #[test]
+    fn keeps_attributes_which_are_not_base64() {
+        let events = convert_events(
+            vec![RawEvent {
+                type_str: "message".to_string(),
+                attributes: Some(vec![RawAttribute {
+                    key: Some("action".to_string()),
+                    value: Some("send".to_string()),
+                    index: None,
+                }]),
+            }],
+            12,
+            EventType::Transaction,
+            AbciDialect::Tendermint034,
+        );
+
+        assert_eq!(attributes(&events), vec![(0, "message", "action", "send")]);
+        assert!(!events[0].indexed);
+    }
+
+    #[test]
+    fn keeps_plain_attributes_of_0_37() {
+        let (result, txs) = normalize(BLOCK_RESULTS_0_37, AbciDialect::CometBft037);
+
+        assert_eq!(
+            attributes(&result.begin_block_events),
+            vec![(0, "mint", "amount", "1000uatom")]
+        );
+        assert_eq!((txs[0].code, txs[0].codespace.as_str()), (5, "sdk"));
+        assert_eq!(txs[0].data, "");
+        // a value which happens to be valid base64 isn't decoded.
+        assert_eq!(
+            attributes(&txs[0].events),
+            vec![
+                (0, "tx", "fee", "500uatom"),
+                (0, "tx", "signature", "dGVzdA==")
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_finalize_block_events_of_0_38_by_mode() {
+        let (result, txs) = normalize(BLOCK_RESULTS_0_38, AbciDialect::CometBft038);
+
+        assert!(txs.is_empty());
+        assert_eq!(
+            attributes(&result.begin_block_events),
+            vec![
+                (0, "mint", "amount", "1000uatom"),
+                (0, "mint", "mode", "BeginBlock"),
+            ]
+        );
+        assert!(result
+            .begin_block_events
+            .iter()
+            .all(|e| e.tx_type == EventType::BeginBlock));
+
+        // events without a mode are end block events.
+        assert_eq!(
+            attributes(&result.end_block_events),
+            vec![
+                (1, "complete_unbonding", "amount", "250uatom"),
+                (1, "complete_unbonding", "mode", "EndBlock"),
+                (2, "upgrade", "name", "v15"),
+            ]
+        );
+        assert!(result
+            .end_block_events
+            .iter()
+            .all(|e| e.tx_type == EventType::EndBlock));
+    }
+}
@@ -0,0 +1,258 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+use crate::errors::Error;
+
+/// EndpointSelection decides which endpoint serves the next request.
+/// Unhealthy endpoints are always tried after the healthy ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointSelection {
+    /// spreads the requests over the endpoints in turn.
+    #[default]
+    RoundRobin,
+    /// prefers the endpoint with the lowest average latency.
+    LeastLatency,
+}
+
+/// EndpointStatus is a snapshot of the counters of an endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointStatus {
+    pub url: String,
+    pub healthy: bool,
+    pub requests: u64,
+    pub errors: u64,
+    /// moving average of the latency of successful requests.
+    pub latency_ms: u64,
+}
+
+struct Endpoint<T> {
+    url: String,
+    client: T,
+    healthy: AtomicBool,
+    requests: AtomicU64,
+    errors: AtomicU64,
+    latency_micros: AtomicU64,
+}
+
+impl<T> Endpoint<T> {
+    fn record_success(&self, elapsed: Duration) {
+        let sample = elapsed.as_micros() as u64;
+        let average = match self.latency_micros.load(Ordering::Relaxed) {
+            0 => sample,
+            prev => (prev * 4 + sample) / 5,
+        };
+        self.latency_micros.store(average.max(1), Ordering::Relaxed);
+
+        if !self.healthy.swap(true, Ordering::Relaxed) {
+            info!("endpoint {} is healthy again", self.url);
+        }
+    }
+
+    fn record_failure(&self, e: &Error) {
+        if self.healthy.swap(false, Ordering::Relaxed) {
+            warn!(
+                "endpoint {} is marked unhealthy | errors: {}, error: {}",
+                self.url,
+                self.errors.load(Ordering::Relaxed),
+                e
+            );
+        }
+    }
+}
+
+/// EndpointPool holds a client per endpoint and fails over between them.
+pub(crate) struct EndpointPool<T> {
+    endpoints: Vec<Endpoint<T>>,
+    selection: EndpointSelection,
+    next: AtomicUsize,
+    /// limits the requests in flight, it may be shared with other pools.
+    limiter: Option<Arc<Semaphore>>,
+}
+
+impl<T: Clone> EndpointPool<T> {
+    pub fn new(
+        clients: Vec<(String, T)>,
+        selection: EndpointSelection,
+        limiter: Option<Arc<Semaphore>>,
+    ) -> Self {
+        let endpoints = clients
+            .into_iter()
+            .map(|(url, client)| Endpoint {
+                url,
+                client,
+                healthy: AtomicBool::new(true),
+                requests: AtomicU64::new(0),
+                errors: AtomicU64::new(0),
+                latency_micros: AtomicU64::new(0),
+            })
+            .collect();
+
+        EndpointPool {
+            endpoints,
+            selection,
+            next: AtomicUsize::new(0),
+            limiter,
+        }
+    }
+
+    /// returns the indexes of the endpoints in the order they should be tried.
+    fn candidates(&self) -> Vec<usize> {
+        let len = self.endpoints.len();
+        let start = match self.selection {
+            EndpointSelection::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % len.max(1),
+            EndpointSelection::LeastLatency => 0,
+        };
+
+        let mut order = (0..len).map(|i| (start + i) % len).collect::<Vec<_>>();
+        if self.selection == EndpointSelection::LeastLatency {
+            order.sort_by_key(|&i| self.endpoints[i].latency_micros.load(Ordering::Relaxed));
+        }
+        // stable, so the order above is kept within healthy and unhealthy endpoints.
+        order.sort_by_key(|&i| !self.endpoints[i].healthy.load(Ordering::Relaxed));
+
+        order
+    }
+
+    /// runs the request against the endpoints until one of them succeeds.
+    /// only transport failures and timeouts are counted as errors and fail over to the next endpoint,
+    /// an error answered by the endpoint is returned right away.
+    /// the error of the last endpoint is returned if every endpoint fails.
+    pub async fn call<R, F, Fut>(&self, mut request: F) -> Result<R, Error>
+    where
+        F: FnMut(T) -> Fut,
+        Fut: Future<Output = Result<R, Error>>,
+    {
+        // the semaphore is never closed, so acquiring only waits for a free permit.
+        let _permit = match &self.limiter {
+            Some(limiter) => limiter.acquire().await.ok(),
+            None => None,
+        };
+
+        let mut last_error = None;
+        for idx in self.candidates() {
+            let endpoint = &self.endpoints[idx];
+            endpoint.requests.fetch_add(1, Ordering::Relaxed);
+
+            let started = Instant::now();
+            match request(endpoint.client.clone()).await {
+                Ok(response) => {
+                    endpoint.record_success(started.elapsed());
+                    return Ok(response);
+                }
+                Err(e) if e.is_transport_failure() => {
+                    endpoint.errors.fetch_add(1, Ordering::Relaxed);
+                    endpoint.record_failure(&e);
+                    last_error = Some(e);
+                }
+                Err(e) => {
+                    // the endpoint is reachable, it just cannot serve this request.
+                    endpoint.record_success(started.elapsed());
+                    return Err(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or(Error::NoEndpointConfigured))
+    }
+
+    /// probes every endpoint, a successful probe marks it healthy again.
+    /// probes aren't counted as requests.
+    pub async fn check_health<F, Fut>(&self, probe: F)
+    where
+        F: Fn(T) -> Fut,
+        Fut: Future<Output = Result<(), Error>>,
+    {
+        for endpoint in self.endpoints.iter() {
+            let started = Instant::now();
+            match probe(endpoint.client.clone()).await {
+                Ok(_) => endpoint.record_success(started.elapsed()),
+                Err(e) => endpoint.record_failure(&e),
+            }
+        }
+    }
+
+    /// runs the request against every endpoint, the results are paired with the endpoint urls.
+    /// it's neither limited nor counted, it's meant for checks rather than for fetching.
+    pub async fn each<R, F, Fut>(&self, request: F) -> Vec<(String, Result<R, Error>)>
+    where
+        F: Fn(T) -> Fut,
+        Fut: Future<Output = Result<R, Error>>,
+    {
+        let mut results = vec![];
+        for endpoint in self.endpoints.iter() {
+            let result = request(endpoint.client.clone()).await;
+            results.push((endpoint.url.clone(), result));
+        }
+        results
+    }
+
+    pub fn status(&self) -> Vec<EndpointStatus> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| EndpointStatus {
+                url: endpoint.url.clone(),
+                healthy: endpoint.healthy.load(Ordering::Relaxed),
+                requests: endpoint.requests.load(Ordering::Relaxed),
+                errors: endpoint.errors.load(Ordering::Relaxed),
+                latency_ms: endpoint.latency_micros.load(Ordering::Relaxed) / 1000,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    fn pool() -> EndpointPool<&'static str> {
+        EndpointPool::new(
+            vec![("a".to_string(), "a"), ("b".to_string(), "b")],
+            EndpointSelection::RoundRobin,
+            None,
+        )
+    }
+
+    #[test]
+    fn transport_failure_fails_over() {
+        let pool = pool();
+        let served = block_on(pool.call(|client| async move {
+            match client {
+                "a" => Err(Error::TonicStatusError(tonic::Status::unavailable(
+                    "connection refused",
+                ))),
+                _ => Ok(client),
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(served, "b");
+        let status = pool.status();
+        assert_eq!((status[0].errors, status[0].healthy), (1, false));
+        assert_eq!((status[1].errors, status[1].healthy), (0, true));
+    }
+
+    #[test]
+    fn application_error_is_returned_without_failover() {
+        let pool = pool();
+        let result: Result<(), Error> = block_on(pool.call(|_| async {
+            Err(Error::TonicStatusError(tonic::Status::not_found(
+                "block not found",
+            )))
+        }));
+
+        assert!(
+            matches!(result, Err(Error::TonicStatusError(s)) if s.code() == tonic::Code::NotFound)
+        );
+        let status = pool.status();
+        assert_eq!(status.iter().map(|s| s.requests).sum::<u64>(), 1);
+        assert!(status.iter().all(|s| s.errors == 0 && s.healthy));
+    }
+}
@@ -1,5 +1,6 @@
 use std::str::Utf8Error;
 
+use cosmos_sdk_proto::prost::DecodeError;
 use tendermint_rpc::error::ErrorDetail;
 use thiserror::Error;
 
@@ -20,13 +21,57 @@ pub enum Error {
     #[error("serde json error")]
     InvalidJSONError(#[from] serde_json::Error),
 
-    #[error("failed to call the rest api with reqwest")]
-    RestAPIERror(#[from] reqwest::Error),
+    #[error("block {0} has {1} transactions but {2} results")]
+    TxResultsMismatch(i64, usize, usize),
+
+    #[error("failed to decode transaction {0}: {1}")]
+    TxDecode(String, DecodeError),
+
+    #[error("tendermint websocket endpoint is not configured")]
+    WebSocketNotConfigured,
+
+    #[error("no tendermint rpc or gRPC endpoint is configured")]
+    NoEndpointConfigured,
+
+    #[error("endpoints return different block results: {0} runs {1}, but {2} runs {3}")]
+    AbciDialectMismatch(String, String, String, String),
 
     #[error("unknown server error")]
     UnknownServerError(tendermint_rpc::Error),
 }
 
+impl Error {
+    /// returns true if the request didn't reach the endpoint or wasn't answered in time,
+    /// another endpoint might serve it. errors answered by the endpoint, e.g. a height which
+    /// isn't available yet, fail the same way on the other endpoints.
+    pub fn is_transport_failure(&self) -> bool {
+        match self {
+            Error::GRPCError(_) => true,
+            // statuses built from a local error, rather than sent by the node, carry it as the source.
+            Error::TonicStatusError(status) => {
+                matches!(
+                    status.code(),
+                    tonic::Code::Unavailable | tonic::Code::DeadlineExceeded
+                ) || std::error::Error::source(status).is_some()
+            }
+            // only I/O, timeout and HTTP status errors fail over, a response which can't be parsed is
+            // answered by the endpoint and would fail the same way on the others.
+            Error::UnknownServerError(tendermint_rpc::Error(detail, _)) => matches!(
+                detail,
+                ErrorDetail::Io(_)
+                    | ErrorDetail::Http(_)
+                    | ErrorDetail::Hyper(_)
+                    | ErrorDetail::Server(_)
+                    | ErrorDetail::Timeout(_)
+                    | ErrorDetail::WebSocket(_)
+                    | ErrorDetail::WebSocketTimeout(_)
+                    | ErrorDetail::Tungstenite(_)
+            ),
+            _ => false,
+        }
+    }
+}
+
 impl From<tendermint_rpc::Error> for Error {
     fn from(err: tendermint_rpc::Error) -> Self {
         match err.clone() {
@@ -0,0 +1,76 @@
+//! protobuf types which aren't generated by `cosmos-sdk-proto` yet.
+
+pub mod gov {
+    /// messages of the `cosmos.gov.v1` module, which replaces `v1beta1` since cosmos-sdk 0.46.
+    pub mod v1 {
+        use cosmos_sdk_proto::{cosmos::base::v1beta1::Coin, Any};
+
+        /// MsgSubmitProposal submits a proposal which executes the given messages once it passes.
+        #[derive(Clone, PartialEq, ::prost::Message)]
+        pub struct MsgSubmitProposal {
+            #[prost(message, repeated, tag = "1")]
+            pub messages: Vec<Any>,
+            #[prost(message, repeated, tag = "2")]
+            pub initial_deposit: Vec<Coin>,
+            #[prost(string, tag = "3")]
+            pub proposer: String,
+            #[prost(string, tag = "4")]
+            pub metadata: String,
+            /// since cosmos-sdk 0.47.
+            #[prost(string, tag = "5")]
+            pub title: String,
+            /// since cosmos-sdk 0.47.
+            #[prost(string, tag = "6")]
+            pub summary: String,
+            /// since cosmos-sdk 0.50.
+            #[prost(bool, tag = "7")]
+            pub expedited: bool,
+        }
+
+        /// MsgVote casts a vote, the options are the same as the ones of `v1beta1`.
+        #[derive(Clone, PartialEq, ::prost::Message)]
+        pub struct MsgVote {
+            #[prost(uint64, tag = "1")]
+            pub proposal_id: u64,
+            #[prost(string, tag = "2")]
+            pub voter: String,
+            #[prost(int32, tag = "3")]
+            pub option: i32,
+            #[prost(string, tag = "4")]
+            pub metadata: String,
+        }
+
+        /// WeightedVoteOption is an option of a weighted vote.
+        #[derive(Clone, PartialEq, ::prost::Message)]
+        pub struct WeightedVoteOption {
+            #[prost(int32, tag = "1")]
+            pub option: i32,
+            #[prost(string, tag = "2")]
+            pub weight: String,
+        }
+
+        /// MsgVoteWeighted casts a vote split between several options.
+        #[derive(Clone, PartialEq, ::prost::Message)]
+        pub struct MsgVoteWeighted {
+            #[prost(uint64, tag = "1")]
+            pub proposal_id: u64,
+            #[prost(string, tag = "2")]
+            pub voter: String,
+            #[prost(message, repeated, tag = "3")]
+            pub options: Vec<WeightedVoteOption>,
+            #[prost(string, tag = "4")]
+            pub metadata: String,
+        }
+
+        /// MsgDeposit adds a deposit to a proposal.
+        #[derive(Clone, PartialEq, ::prost::Message)]
+        pub struct MsgDeposit {
+            #[prost(uint64, tag = "1")]
+            pub proposal_id: u64,
+            #[prost(string, tag = "2")]
+            pub depositor: String,
+            #[prost(message, repeated, tag = "3")]
+            pub amount: Vec<Coin>,
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use chrono::NaiveDateTime;
-use cosmos_sdk_proto::cosmos::tx::v1beta1::GetTxResponse;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -23,6 +22,8 @@ pub struct Event {
     pub tx_hash: Option<String>,
     pub block_height: i64,
     pub event_seq: i32,
+    /// position of the attribute in its event.
+    pub attr_seq: i32,
     pub event_type: String,
     pub event_key: String,
     pub event_value: String,
@@ -36,6 +37,23 @@ pub struct BlockResult {
     pub end_block_events: Vec<Event>,
 }
 
+/// Represents an amount of a single denom.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Coin {
+    pub denom: String,
+    pub amount: String,
+}
+
+/// Represents a member of the validator set at a height.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Validator {
+    pub address: String,
+    pub pub_key_type: String,
+    pub pub_key: String,
+    pub voting_power: i64,
+    pub proposer_priority: i64,
+}
+
 /// Represents a transaction as readable and inlined format.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Transaction {
@@ -51,6 +69,20 @@ pub struct Transaction {
     pub gas_used: i64,
     pub tx_timestamp: String,
     pub messages: Vec<String>,
+    pub fee: Vec<Coin>,
+    pub gas_limit: i64,
+    pub fee_payer: Option<String>,
+    pub fee_granter: Option<String>,
+    pub signers: Vec<Signer>,
+}
+
+/// Represents a signer of a transaction, taken from its auth info.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Signer {
+    pub pub_key_type: Option<String>,
+    pub pub_key: Option<String>,
+    pub sequence: i64,
+    pub sign_mode: String,
 }
 
 /// Prepresents a block as inlined format.
@@ -71,6 +103,18 @@ pub struct Block {
     pub block_time: NaiveDateTime,
 }
 
+/// NodeStatus is the part of `/status` the indexer relies on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeStatus {
+    /// chain id of the node.
+    pub network: String,
+    pub version: String,
+    /// lowest height the node still serves, it's above 1 on pruned or state-synced nodes.
+    pub earliest_block_height: i64,
+    pub latest_block_height: i64,
+    pub catching_up: bool,
+}
+
 impl From<tendermint::block::Block> for Block {
     fn from(block: tendermint::block::Block) -> Self {
         let date_format_raw_str = "YYYY-MM-DDTHH:mm:ss";
@@ -111,25 +155,26 @@ impl From<tendermint::block::Block> for Block {
     }
 }
 
-impl From<&GetTxResponse> for Transaction {
-    fn from(tx: &GetTxResponse) -> Self {
-        let tx_info = tx.tx.as_ref().unwrap();
-        let tx_body = tx_info.body.as_ref().unwrap();
-        let tx_response = tx.tx_response.as_ref().unwrap();
-
-        Transaction {
-            transaction_hash: tx_response.txhash.clone(),
-            height: tx_response.height,
-            code: tx_response.code as i32,
-            code_space: tx_response.codespace.clone(),
-            tx_data: tx_response.data.clone(),
-            raw_log: tx_response.raw_log.clone(),
-            info: tx_response.info.clone(),
-            memo: Some(tx_body.memo.clone()),
-            gas_wanted: tx_response.gas_wanted,
-            gas_used: tx_response.gas_used,
-            tx_timestamp: tx_response.timestamp.clone(),
-            messages: vec![],
+impl From<cosmos_sdk_proto::cosmos::base::v1beta1::Coin> for Coin {
+    fn from(coin: cosmos_sdk_proto::cosmos::base::v1beta1::Coin) -> Self {
+        Coin {
+            denom: coin.denom,
+            amount: coin.amount,
+        }
+    }
+}
+
+impl From<tendermint::validator::Info> for Validator {
+    fn from(info: tendermint::validator::Info) -> Self {
+        // the json form of the key is `{"type": "tendermint/PubKeyEd25519", "value": "<base64>"}`.
+        let pub_key = serde_json::to_value(info.pub_key).unwrap_or_default();
+
+        Validator {
+            address: info.address.to_string(),
+            pub_key_type: pub_key["type"].as_str().unwrap_or_default().to_string(),
+            pub_key: pub_key["value"].as_str().unwrap_or_default().to_string(),
+            voting_power: info.power.value() as i64,
+            proposer_priority: info.proposer_priority.value(),
         }
     }
 }
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use crate::response::NodeStatus;
+
+/// StatusRequest is `/status` with a response which keeps the earliest heights.
+/// The response of tendermint-rpc doesn't parse `earliest_block_height`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct StatusRequest;
+
+impl tendermint_rpc::Request for StatusRequest {
+    type Response = RawStatus;
+
+    fn method(&self) -> tendermint_rpc::Method {
+        tendermint_rpc::Method::Status
+    }
+}
+
+impl tendermint_rpc::SimpleRequest for StatusRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RawStatus {
+    pub node_info: RawNodeInfo,
+    pub sync_info: RawSyncInfo,
+}
+
+impl tendermint_rpc::Response for RawStatus {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RawNodeInfo {
+    pub network: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RawSyncInfo {
+    #[serde(default)]
+    pub earliest_block_height: Option<String>,
+    pub latest_block_height: String,
+    #[serde(default)]
+    pub catching_up: bool,
+}
+
+impl From<RawStatus> for NodeStatus {
+    fn from(status: RawStatus) -> Self {
+        let parse = |height: &str| height.parse::<i64>().unwrap_or_default();
+        let latest_block_height = parse(&status.sync_info.latest_block_height);
+        // nodes which don't report it keep every block from genesis.
+        let earliest_block_height = status
+            .sync_info
+            .earliest_block_height
+            .as_deref()
+            .map(parse)
+            .filter(|height| *height > 0)
+            .unwrap_or(1);
+
+        NodeStatus {
+            network: status.node_info.network,
+            version: status.node_info.version,
+            earliest_block_height,
+            latest_block_height,
+            catching_up: status.sync_info.catching_up,
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use futures::StreamExt;
+use tendermint_rpc::{
+    event::EventData, query::EventType, Subscription, SubscriptionClient, WebSocketClient,
+};
+use tokio::task::JoinHandle;
+
+use crate::errors::Error;
+
+/// NewBlockSubscription receives `NewBlock` events from the tendermint websocket endpoint.
+/// The websocket connection is closed when the subscription is dropped.
+pub struct NewBlockSubscription {
+    ws_client: Option<WebSocketClient>,
+    subscription: Subscription,
+    driver_handle: JoinHandle<()>,
+}
+
+impl NewBlockSubscription {
+    /// Connects to the websocket endpoint and subscribes to `NewBlock` events.
+    pub async fn connect(endpoint: &str) -> Result<Self, Error> {
+        let (ws_client, driver) = WebSocketClient::new(endpoint).await.map_err(Error::from)?;
+
+        let driver_handle = tokio::spawn(async move {
+            if let Err(e) = driver.run().await {
+                log::warn!("tendermint websocket driver stopped: {}", e);
+            }
+        });
+
+        let subscription = match ws_client.subscribe(EventType::NewBlock.into()).await {
+            Ok(subscription) => subscription,
+            Err(e) => {
+                let _ = ws_client.close();
+                return Err(Error::from(e));
+            }
+        };
+
+        Ok(NewBlockSubscription {
+            ws_client: Some(ws_client),
+            subscription,
+            driver_handle,
+        })
+    }
+
+    /// Waits for the next `NewBlock` event and returns its height.
+    /// Returns `None` once the subscription has been dropped by the node.
+    pub async fn next_height(&mut self) -> Option<i64> {
+        while let Some(result) = self.subscription.next().await {
+            match result {
+                Ok(event) => {
+                    if let EventData::NewBlock {
+                        block: Some(block), ..
+                    } = event.data
+                    {
+                        return Some(block.header.height.into());
+                    }
+                }
+                Err(e) => {
+                    log::warn!("failed to receive NewBlock event: {}", e);
+                    return None;
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl Drop for NewBlockSubscription {
+    fn drop(&mut self) {
+        if let Some(ws_client) = self.ws_client.take() {
+            let _ = ws_client.close();
+        }
+        self.driver_handle.abort();
+    }
+}
@@ -0,0 +1,925 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use crate::committer::{Committer, DEFAULT_COMMIT_BATCH_BYTES, DEFAULT_COMMIT_BATCH_SIZE};
+use crate::config::{ChainSectionConfig, IndexerConfig, PrunedStartPolicy};
+use crate::errors::Error;
+use crate::fetchers::account_fetcher::{touched_addresses, AccountFetcher};
+use crate::fetchers::committed_block_fetcher::{CommittedBlockFetcher, DEFAULT_BACKFILL_WORKERS};
+use crate::fetchers::gap_fetcher::{GapFetcher, DEFAULT_GAP_SCAN_INTERVAL_SECS};
+use crate::fetchers::validator_set_fetcher::ValidatorSetFetcher;
+use crate::messages::{BlockSource, MsgCommittedBlock, MsgValidatorsHash};
+use crate::retry::RetryPolicy;
+use crate::{current_time, SharedClient};
+
+use cosmscan_models::async_storage::AsyncStorage;
+use cosmscan_models::errors::Error as ModelError;
+use cosmscan_models::models::chain::{Chain, NewChain};
+use cosmscan_models::models::indexer_cursor::{NewIndexerCursor, PIPELINE_BLOCKS};
+use cosmscan_models::models::unindexable_range::NewUnindexableRange;
+use cosmscan_models::storage::Storage;
+
+use futures::{stream, StreamExt};
+use log::{error, info, warn};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// default interval between two health checks of the rpc and gRPC endpoints.
+pub const DEFAULT_HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
+
+/// default interval between two reports of the endpoint counters.
+pub const DEFAULT_ENDPOINT_STATUS_INTERVAL_SECS: u64 = 300;
+
+/// default maximum number of stored heights rolled back when the tip forks.
+pub const DEFAULT_MAX_REORG_DEPTH: i64 = 100;
+
+/// reason recorded for a missing block which doesn't link to the stored previous block.
+const UNLINKED_BLOCK_REASON: &str = "prev_hash mismatch";
+
+/// ChainIndexer runs the fetcher/committer pipeline of a single chain.
+pub struct ChainIndexer<S> {
+    pub config: ChainSectionConfig,
+    pub indexer_config: IndexerConfig,
+    pub retry_policy: RetryPolicy,
+    /// storage operations run on the blocking thread pool, see [`AsyncStorage`].
+    pub storage: AsyncStorage<S>,
+    pub client: SharedClient,
+    /// stops the pipeline once it's cancelled, see [`ChainIndexer::start`].
+    pub shutdown: CancellationToken,
+}
+
+impl<S: Storage + Send + Sync + 'static> ChainIndexer<S> {
+    /// creates a pipeline for the given chain, it shares the storage with the other chains.
+    pub async fn new(
+        config: ChainSectionConfig,
+        indexer_config: IndexerConfig,
+        retry_policy: RetryPolicy,
+        storage: AsyncStorage<S>,
+        shutdown: CancellationToken,
+    ) -> Result<Self, Error> {
+        // create a shared cosmos client
+        let client_config = cosmos_client::client::ClientConfig {
+            tendermint_rpc_endpoints: config.fetcher.rpc_endpoints(),
+            grpc_endpoints: config.fetcher.grpc_endpoints(),
+            tendermint_ws_endpoint: config.fetcher.tendermint_ws_endpoint.clone(),
+            endpoint_selection: config.fetcher.endpoint_selection.unwrap_or_default(),
+            health_check_interval: match config.fetcher.health_check_interval_secs {
+                Some(0) => None,
+                Some(secs) => Some(Duration::from_secs(secs)),
+                None => Some(Duration::from_secs(DEFAULT_HEALTH_CHECK_INTERVAL_SECS)),
+            },
+            max_in_flight_requests: config.fetcher.max_in_flight_requests,
+        };
+
+        let client = cosmos_client::client::Client::new(client_config).await?;
+
+        Ok(ChainIndexer {
+            config,
+            indexer_config,
+            retry_policy,
+            storage,
+            client,
+            shutdown,
+        })
+    }
+
+    pub fn chain_id(&self) -> &str {
+        &self.config.chain.chain_id
+    }
+
+    /// runs the pipeline until it fails or the shutdown token is cancelled.
+    /// on shutdown, the fetchers are stopped and the blocks fetched already are committed before it returns.
+    pub async fn start(&self) -> Result<(), Error> {
+        self.verify_chain_id().await?;
+
+        // get chain info from database, if it doesn't exists, create a new one.
+        let chain = self.load_chain_or_store().await?;
+        let start_block = match self.load_latest_block_height(&chain).await {
+            Some(height) => height + 1,
+            None => self.config.fetcher.start_block,
+        };
+        let earliest_block = self.client.node_status().await?.earliest_block_height;
+        let start_block = self
+            .resolve_start_block(&chain, start_block, earliest_block)
+            .await?;
+        info!(
+            "start indexing | chain_id: {}, block_number: {}",
+            chain.chain_id, start_block
+        );
+
+        let status_handle = self.report_endpoint_status();
+
+        // run fetcher
+        let backfill_workers = self.backfill_workers();
+        let retry_policy = self.retry_policy;
+        let committed_block_fetcher =
+            CommittedBlockFetcher::new(self.client.clone(), backfill_workers, retry_policy).await?;
+        let (committed_block_s, mut committed_block_r) = mpsc::channel::<MsgCommittedBlock>(100);
+        let mut fetcher_handle = committed_block_fetcher.run_loop(committed_block_s, start_block);
+
+        // scan the blocks table for missing heights, on startup and then periodically.
+        let scan_interval = match self.indexer_config.gap_scan_interval_secs {
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => Some(Duration::from_secs(DEFAULT_GAP_SCAN_INTERVAL_SECS)),
+        };
+        let gap_fetcher = GapFetcher::new(
+            self.client.clone(),
+            self.storage.clone(),
+            chain.id,
+            // heights below the earliest block cannot be repaired.
+            self.config.fetcher.start_block.max(earliest_block),
+            backfill_workers,
+            retry_policy,
+            scan_interval,
+        );
+        let (gap_block_s, mut gap_block_r) = mpsc::channel::<MsgCommittedBlock>(100);
+        let gap_handle = gap_fetcher.run_loop(gap_block_s);
+
+        // refresh balances of the touched accounts if it's enabled.
+        let account_s = if self.indexer_config.fetcher_account_enabled {
+            let account_fetcher = AccountFetcher::new(
+                self.client.clone(),
+                self.storage.clone(),
+                chain.id,
+                retry_policy,
+            );
+            let (account_s, account_r) = mpsc::channel::<Vec<String>>(1000);
+            Some((account_s, account_fetcher.run_loop(account_r)))
+        } else {
+            None
+        };
+
+        // store the validator set history if it's enabled.
+        let validator_s = if self.indexer_config.fetcher_validator_enabled {
+            let validator_set_fetcher = ValidatorSetFetcher::new(
+                self.client.clone(),
+                self.storage.clone(),
+                chain.id,
+                retry_policy,
+            );
+            let (validator_s, validator_r) = mpsc::channel::<MsgValidatorsHash>(1000);
+            Some((validator_s, validator_set_fetcher.run_loop(validator_r)))
+        } else {
+            None
+        };
+
+        // create a committer and run it
+        let chain_id = chain.id;
+        let committer = Committer::new(self.storage.clone(), chain);
+        let mut reorg_guard = ReorgGuard::new(
+            self.config
+                .fetcher
+                .max_reorg_depth
+                .unwrap_or(DEFAULT_MAX_REORG_DEPTH),
+        );
+
+        loop {
+            // blocks queued behind the received one are committed together while catching up,
+            // at the tip the channel is empty and every block gets its own transaction.
+            let batch = tokio::select! {
+                // shutdown goes first, fetched blocks are drained after it.
+                // they're also drained before looking at the fetcher's exit status.
+                biased;
+
+                _ = self.shutdown.cancelled() => break,
+                Some(val) = committed_block_r.recv() => self.fill_batch(val, &mut committed_block_r),
+                Some(val) = gap_block_r.recv() => self.fill_batch(val, &mut gap_block_r),
+                result = &mut fetcher_handle => {
+                    // the fetcher only stops when it cannot recover from a failure.
+                    return match result {
+                        Ok(Ok(())) => Ok(()),
+                        Ok(Err(e)) => Err(e),
+                        Err(e) => Err(Error::Other(e.to_string())),
+                    };
+                }
+            };
+
+            // a repaired gap doesn't move the tip, it never rolls the stored blocks back.
+            if batch[0].source == BlockSource::Gap {
+                self.commit_gap_batch(&committer, &account_s, &validator_s, chain_id, batch)
+                    .await?;
+                continue;
+            }
+
+            let first_height = batch[0].block.height;
+            let last_height = batch[batch.len() - 1].block.height;
+            let sources = batch
+                .iter()
+                .map(|val| (val.block.height, val.source))
+                .collect::<Vec<_>>();
+            match self
+                .commit_and_forward(&committer, &account_s, &validator_s, batch)
+                .await
+            {
+                Ok(_) => reorg_guard.committed(last_height),
+                Err(Error::ChainLinkMismatch(height)) => {
+                    let source = sources
+                        .iter()
+                        .find(|(h, _)| *h == height)
+                        .map(|(_, source)| *source);
+                    if !matches!(source, Some(BlockSource::Tip) | Some(BlockSource::Backfill)) {
+                        return Err(Error::ChainLinkMismatch(height));
+                    }
+
+                    // the stored previous block belongs to another fork,
+                    // drop it and fetch again from there. if the re-fetched block still doesn't link,
+                    // it walks back one more height. the blocks of the batch below it are fetched again as well,
+                    // they haven't been committed.
+                    let rollback_height = reorg_guard.rollback_height(height)?;
+                    warn!(
+                        "prev_hash mismatch, rolling back | chain_id: {}, block_number: {}, rollback_from: {}",
+                        self.chain_id(), height, rollback_height
+                    );
+                    fetcher_handle.abort();
+                    committer.rollback_from(rollback_height).await?;
+
+                    let (committed_block_s, new_committed_block_r) =
+                        mpsc::channel::<MsgCommittedBlock>(100);
+                    committed_block_r = new_committed_block_r;
+                    let start_block = rollback_height.min(first_height);
+                    fetcher_handle = match source {
+                        // e.g. the tip stored before a restart has been orphaned,
+                        // the backfill goes on from the rolled back height.
+                        Some(BlockSource::Backfill) => {
+                            committed_block_fetcher.run_loop(committed_block_s, start_block)
+                        }
+                        // the heights are fetched again as tip blocks, so that the rollback can go on.
+                        _ => committed_block_fetcher.follow_tip(committed_block_s, start_block),
+                    };
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // stop fetching, and commit the blocks which are in the channels already.
+        info!("shutting down | chain_id: {}", self.chain_id());
+        fetcher_handle.abort();
+        gap_handle.abort();
+        committed_block_r.close();
+        gap_block_r.close();
+
+        let mut drained = 0;
+        'drain: for receiver in [&mut committed_block_r, &mut gap_block_r] {
+            while let Some(val) = receiver.recv().await {
+                let batch = self.fill_batch(val, receiver);
+                let size = batch.len();
+                let result = if batch[0].source == BlockSource::Gap {
+                    self.commit_gap_batch(&committer, &account_s, &validator_s, chain_id, batch)
+                        .await
+                } else {
+                    self.commit_and_forward(&committer, &account_s, &validator_s, batch)
+                        .await
+                };
+                match result {
+                    Ok(_) => drained += size,
+                    // the fork is resolved on the next start, the cursor still points to the last linked block.
+                    Err(Error::ChainLinkMismatch(height)) => {
+                        warn!(
+                            "prev_hash mismatch while shutting down, the rest isn't committed | chain_id: {}, block_number: {}",
+                            self.chain_id(),
+                            height
+                        );
+                        break 'drain;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        // sub-pipelines finish what they have received, once their senders are dropped.
+        Self::wait_for(account_s).await;
+        Self::wait_for(validator_s).await;
+        if let Err(e) = status_handle.await {
+            warn!("endpoint status reporter stopped unexpectedly: {}", e);
+        }
+        info!(
+            "shut down | chain_id: {}, committed_blocks: {}",
+            self.chain_id(),
+            drained
+        );
+
+        Ok(())
+    }
+
+    /// spawns a task which logs the counters of the endpoints whenever new errors happened,
+    /// and once more when the pipeline shuts down.
+    fn report_endpoint_status(&self) -> JoinHandle<()> {
+        let client = self.client.clone();
+        let chain_id = self.chain_id().to_string();
+        let shutdown = self.shutdown.clone();
+        let interval = Duration::from_secs(DEFAULT_ENDPOINT_STATUS_INTERVAL_SECS);
+
+        tokio::spawn(async move {
+            let mut reported_errors = vec![];
+            loop {
+                let stopping = tokio::select! {
+                    _ = shutdown.cancelled() => true,
+                    _ = tokio::time::sleep(interval) => false,
+                };
+
+                let status = client.endpoint_status();
+                let errors = status.iter().map(|s| s.errors).collect::<Vec<_>>();
+                if stopping || errors != reported_errors {
+                    for s in status.iter() {
+                        info!(
+                            "endpoint status | chain_id: {}, url: {}, healthy: {}, requests: {}, errors: {}, latency: {}ms",
+                            chain_id, s.url, s.healthy, s.requests, s.errors, s.latency_ms
+                        );
+                    }
+                    reported_errors = errors;
+                }
+                if stopping {
+                    return;
+                }
+            }
+        })
+    }
+
+    /// takes the blocks queued behind `first` in the channel, up to the configured batch size and bytes.
+    fn fill_batch(
+        &self,
+        first: MsgCommittedBlock,
+        receiver: &mut mpsc::Receiver<MsgCommittedBlock>,
+    ) -> Vec<MsgCommittedBlock> {
+        let (max_blocks, max_bytes) = self.commit_batch_limits();
+        let mut bytes = first.size_hint();
+        let mut batch = vec![first];
+        while batch.len() < max_blocks && bytes < max_bytes {
+            match receiver.try_recv() {
+                Ok(val) => {
+                    bytes += val.size_hint();
+                    batch.push(val);
+                }
+                Err(_) => break,
+            }
+        }
+
+        batch
+    }
+
+    /// returns the maximum number of blocks and the approximate size in bytes of a commit batch.
+    fn commit_batch_limits(&self) -> (usize, usize) {
+        let max_blocks = self
+            .indexer_config
+            .commit_batch_size
+            .unwrap_or(DEFAULT_COMMIT_BATCH_SIZE)
+            .max(1);
+        let max_bytes = self
+            .indexer_config
+            .commit_batch_bytes
+            .unwrap_or(DEFAULT_COMMIT_BATCH_BYTES);
+        (max_blocks, max_bytes)
+    }
+
+    /// commits the blocks in a single transaction and hands them over to the enabled sub-pipelines.
+    async fn commit_and_forward(
+        &self,
+        committer: &Committer<S>,
+        account_s: &Option<(mpsc::Sender<Vec<String>>, JoinHandle<()>)>,
+        validator_s: &Option<(mpsc::Sender<MsgValidatorsHash>, JoinHandle<()>)>,
+        batch: Vec<MsgCommittedBlock>,
+    ) -> Result<(), Error> {
+        let touched_accounts = account_s.as_ref().map(|_| {
+            batch
+                .iter()
+                .flat_map(touched_addresses)
+                .collect::<BTreeSet<_>>()
+        });
+        let validators_hashes = batch
+            .iter()
+            .map(|val| MsgValidatorsHash {
+                height: val.block.height,
+                validators_hash: val.block.validators_hash.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        Self::commit_with_retry(committer, self.retry_policy, batch, false).await?;
+
+        // the account fetcher must not hold back the blocks, accounts are dropped while it's behind.
+        // they're refreshed again the next time they're touched.
+        if let (Some((sender, _)), Some(touched_accounts)) = (account_s, touched_accounts) {
+            if !touched_accounts.is_empty() {
+                let count = touched_accounts.len();
+                match sender.try_send(touched_accounts.into_iter().collect()) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(_)) => warn!(
+                        "account fetcher is behind, touched accounts are dropped | chain_id: {}, accounts: {}",
+                        self.chain_id(),
+                        count
+                    ),
+                    Err(TrySendError::Closed(_)) => warn!(
+                        "account fetcher has been stopped | chain_id: {}",
+                        self.chain_id()
+                    ),
+                }
+            }
+        }
+        if let Some((sender, _)) = validator_s {
+            for validators_hash in validators_hashes {
+                if sender.send(validators_hash).await.is_err() {
+                    warn!(
+                        "validator set fetcher has been stopped | chain_id: {}",
+                        self.chain_id()
+                    );
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// commits a batch of repaired missing blocks.
+    /// a block which doesn't link to the stored previous block is recorded as unindexable,
+    /// the gap scan skips it from then on, and the rest of the batch is committed.
+    async fn commit_gap_batch(
+        &self,
+        committer: &Committer<S>,
+        account_s: &Option<(mpsc::Sender<Vec<String>>, JoinHandle<()>)>,
+        validator_s: &Option<(mpsc::Sender<MsgValidatorsHash>, JoinHandle<()>)>,
+        chain_id: i32,
+        mut batch: Vec<MsgCommittedBlock>,
+    ) -> Result<(), Error> {
+        loop {
+            match self
+                .commit_and_forward(committer, account_s, validator_s, batch.clone())
+                .await
+            {
+                Err(Error::ChainLinkMismatch(height)) => {
+                    warn!(
+                        "prev_hash mismatch of a missing block, recorded as unindexable | chain_id: {}, block_number: {}",
+                        self.chain_id(), height
+                    );
+                    let range = NewUnindexableRange {
+                        chain_id,
+                        start_height: height,
+                        end_height: height,
+                        reason: UNLINKED_BLOCK_REASON.to_string(),
+                        inserted_at: current_time(),
+                    };
+                    self.storage
+                        .run(move |storage| storage.upsert_unindexable_range(&range))
+                        .await?;
+
+                    batch.retain(|val| val.block.height != height);
+                    if batch.is_empty() {
+                        return Ok(());
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// drops the sender of a sub-pipeline and waits until it has processed the queued messages.
+    async fn wait_for<T>(pipeline: Option<(mpsc::Sender<T>, JoinHandle<()>)>) {
+        if let Some((sender, handle)) = pipeline {
+            drop(sender);
+            if let Err(e) = handle.await {
+                warn!("sub-pipeline stopped unexpectedly: {}", e);
+            }
+        }
+    }
+
+    /// re-indexes the blocks between the given heights (inclusive).
+    /// blocks are fetched first, then each batch replaces the stored data of its heights
+    /// in a single transaction, so that a failure keeps the data which haven't been replaced yet.
+    /// the range is extended while the stored block above it doesn't link to the re-indexed ones.
+    pub async fn reindex(&self, from_height: i64, to_height: i64) -> Result<(), Error> {
+        if from_height <= 0 || from_height > to_height {
+            return Err(Error::InvalidHeightRange(from_height, to_height));
+        }
+
+        self.verify_chain_id().await?;
+        let chain = self.find_indexed_chain().await?;
+
+        let earliest_block = self.client.node_status().await?.earliest_block_height;
+        if from_height < earliest_block {
+            return Err(Error::BlockPruned(from_height, earliest_block));
+        }
+
+        let retry_policy = self.retry_policy;
+        let chain_id = chain.id;
+        let committer = Committer::new(self.storage.clone(), chain);
+
+        // validator sets in the range are deleted along with the blocks, they are synced again.
+        let validator_set_fetcher = self.indexer_config.fetcher_validator_enabled.then(|| {
+            ValidatorSetFetcher::new(
+                self.client.clone(),
+                self.storage.clone(),
+                chain_id,
+                retry_policy,
+            )
+        });
+
+        // the stored block above the range has to link to the last re-indexed one.
+        // if it doesn't, the stored blocks above belong to another fork and the range is extended
+        // until it links again, or up to the highest stored block.
+        let (mut from_height, mut to_height) = (from_height, to_height);
+        loop {
+            let last_hash = self
+                .reindex_range(
+                    &committer,
+                    validator_set_fetcher.as_ref(),
+                    from_height,
+                    to_height,
+                )
+                .await?;
+
+            let next_height = to_height + 1;
+            let (next_block, latest_height) = self
+                .storage
+                .run(move |storage| -> Result<_, Error> {
+                    let next_block = match storage.find_block_by_height(chain_id, next_height) {
+                        Ok(block) => Some(block),
+                        Err(ModelError::NotFound) => None,
+                        Err(e) => return Err(e.into()),
+                    };
+                    Ok((next_block, storage.latest_block_height(chain_id)?))
+                })
+                .await?;
+            match next_block {
+                Some(block) if block.prev_hash != last_hash => {
+                    let (max_blocks, _) = self.commit_batch_limits();
+                    from_height = next_height;
+                    to_height = latest_height.min(to_height + max_blocks as i64);
+                    warn!(
+                        "stored block above the range doesn't link to it, extending the range | chain_id: {}, from: {}, to: {}",
+                        self.chain_id(),
+                        from_height,
+                        to_height
+                    );
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// fetches the blocks between the given heights (inclusive), and replaces the stored ones batch by batch.
+    /// returns the hash of the last block.
+    async fn reindex_range(
+        &self,
+        committer: &Committer<S>,
+        validator_set_fetcher: Option<&ValidatorSetFetcher<S>>,
+        from_height: i64,
+        to_height: i64,
+    ) -> Result<String, Error> {
+        let mut committed_blocks = stream::iter(from_height..=to_height)
+            .map(|height| {
+                CommittedBlockFetcher::fetch_with_retry(
+                    self.client.clone(),
+                    self.retry_policy,
+                    height,
+                    BlockSource::Reindex,
+                )
+            })
+            .buffered(self.backfill_workers());
+
+        let (max_blocks, max_bytes) = self.commit_batch_limits();
+        let mut batch = vec![];
+        let mut bytes = 0;
+        let mut last_hash = String::new();
+        while let Some(committed_block) = committed_blocks.next().await.transpose()? {
+            bytes += committed_block.size_hint();
+            last_hash = committed_block.block.block_hash.clone();
+            batch.push(committed_block);
+            if batch.len() >= max_blocks || bytes >= max_bytes {
+                let batch = std::mem::take(&mut batch);
+                self.replace_batch(committer, validator_set_fetcher, batch)
+                    .await?;
+                bytes = 0;
+            }
+        }
+        if !batch.is_empty() {
+            self.replace_batch(committer, validator_set_fetcher, batch)
+                .await?;
+        }
+
+        Ok(last_hash)
+    }
+
+    /// replaces the stored data of the batch's heights with it, then syncs their validator sets.
+    async fn replace_batch(
+        &self,
+        committer: &Committer<S>,
+        validator_set_fetcher: Option<&ValidatorSetFetcher<S>>,
+        batch: Vec<MsgCommittedBlock>,
+    ) -> Result<(), Error> {
+        let validators_hashes = batch
+            .iter()
+            .map(|val| MsgValidatorsHash {
+                height: val.block.height,
+                validators_hash: val.block.validators_hash.clone(),
+            })
+            .collect::<Vec<_>>();
+        let (from_height, to_height) = match (validators_hashes.first(), validators_hashes.last()) {
+            (Some(first), Some(last)) => (first.height, last.height),
+            _ => return Ok(()),
+        };
+
+        Self::commit_with_retry(committer, self.retry_policy, batch, true).await?;
+        info!(
+            "reindexed blocks | chain_id: {}, from: {}, to: {}",
+            self.chain_id(),
+            from_height,
+            to_height
+        );
+
+        if let Some(fetcher) = validator_set_fetcher {
+            fetcher.sync(&validators_hashes).await?;
+        }
+
+        Ok(())
+    }
+
+    fn backfill_workers(&self) -> usize {
+        self.config
+            .fetcher
+            .backfill_workers
+            .unwrap_or(DEFAULT_BACKFILL_WORKERS)
+    }
+
+    /// commits the blocks in a single transaction, retrying database failures with the given policy.
+    /// with `replace`, the stored data between the heights of the batch are replaced by it.
+    async fn commit_with_retry(
+        committer: &Committer<S>,
+        policy: RetryPolicy,
+        batch: Vec<MsgCommittedBlock>,
+        replace: bool,
+    ) -> Result<usize, Error> {
+        let description = match (batch.first(), batch.last()) {
+            (Some(first), Some(last)) if batch.len() > 1 => format!(
+                "committing blocks {}..={}",
+                first.block.height, last.block.height
+            ),
+            (Some(first), _) => format!("committing block {}", first.block.height),
+            _ => return Ok(0),
+        };
+
+        policy
+            .retry(
+                &description,
+                || {
+                    let batch = batch.clone();
+                    async move {
+                        if replace {
+                            committer.replace_blocks(batch).await
+                        } else {
+                            committer.commit_blocks(batch).await
+                        }
+                    }
+                },
+                Error::is_transient,
+            )
+            .await
+    }
+
+    // load chain info from storage
+    // if it doesn't exists we'll create a new chain in the storage.
+    pub async fn load_chain_or_store(&self) -> Result<Chain, Error> {
+        let new_chain = NewChain {
+            chain_id: self.chain_id().to_string(),
+            chain_name: self.config.chain.chain_name.clone(),
+            inserted_at: current_time(),
+        };
+
+        self.storage
+            .run(move |storage| Self::find_or_insert_chain(storage, new_chain))
+            .await
+            .map_err(|e| {
+                error!("unexpected error: {:?}", e);
+                Error::DBError(e)
+            })
+    }
+
+    fn find_or_insert_chain(
+        storage: &S,
+        new_chain: NewChain,
+    ) -> Result<Chain, cosmscan_models::errors::Error> {
+        match storage.find_by_chain_id(new_chain.chain_id.clone()) {
+            Err(cosmscan_models::errors::Error::NotFound) => {
+                // create a new chain info, and read it back to get the assigned id.
+                storage.insert_chain(&new_chain)?;
+                storage.find_by_chain_id(new_chain.chain_id)
+            }
+            result => result,
+        }
+    }
+
+    /// loads the stored chain, it fails if the chain has never been indexed.
+    async fn find_indexed_chain(&self) -> Result<Chain, Error> {
+        let chain_id = self.chain_id().to_string();
+        let result = self
+            .storage
+            .run(move |storage| storage.find_by_chain_id(chain_id))
+            .await;
+
+        match result {
+            Ok(chain) => Ok(chain),
+            Err(cosmscan_models::errors::Error::NotFound) => {
+                Err(Error::ChainNotIndexed(self.chain_id().to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// checks that every endpoint serves the configured chain, so that blocks of another network
+    /// aren't stored under it. endpoints which cannot be reached are skipped.
+    async fn verify_chain_id(&self) -> Result<(), Error> {
+        let chain = &self.config.chain;
+        if chain.skip_chain_id_check {
+            warn!("chain id check is skipped | chain_id: {}", chain.chain_id);
+            return Ok(());
+        }
+
+        for (endpoint, network) in self.client.endpoint_chain_ids().await {
+            match network {
+                Ok(network)
+                    if network == chain.chain_id || chain.network_aliases.contains(&network) => {}
+                Ok(network) => {
+                    return Err(Error::ChainIdMismatch(
+                        chain.chain_id.clone(),
+                        endpoint,
+                        network,
+                    ))
+                }
+                Err(e) => warn!(
+                    "failed to check the chain id of the endpoint | chain_id: {}, endpoint: {}, error: {}",
+                    chain.chain_id, endpoint, e
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// checks the start block against the earliest block the node still serves.
+    /// pruned heights are either skipped and recorded as unindexable, or refused, see [`PrunedStartPolicy`].
+    async fn resolve_start_block(
+        &self,
+        chain: &Chain,
+        start_block: i64,
+        earliest_block: i64,
+    ) -> Result<i64, Error> {
+        if start_block >= earliest_block {
+            return Ok(start_block);
+        }
+
+        match self.config.fetcher.pruned_start.unwrap_or_default() {
+            PrunedStartPolicy::Refuse => Err(Error::BlockPruned(start_block, earliest_block)),
+            PrunedStartPolicy::Clamp => {
+                warn!(
+                    "blocks have been pruned by the node, skipping them | chain_id: {}, from: {}, to: {}",
+                    self.chain_id(),
+                    start_block,
+                    earliest_block - 1
+                );
+                let range = NewUnindexableRange {
+                    chain_id: chain.id,
+                    start_height: start_block,
+                    end_height: earliest_block - 1,
+                    reason: "pruned".to_string(),
+                    inserted_at: current_time(),
+                };
+                self.storage
+                    .run(move |storage| storage.upsert_unindexable_range(&range))
+                    .await?;
+                Ok(earliest_block)
+            }
+        }
+    }
+
+    /// returns the last height committed by the blocks pipeline.
+    /// the highest stored block is used for databases indexed before cursors were stored.
+    pub async fn load_latest_block_height(&self, chain: &Chain) -> Option<i64> {
+        let chain_id = chain.id;
+        self.storage
+            .run(move |storage| {
+                storage
+                    .find_indexer_cursor(chain_id, PIPELINE_BLOCKS)
+                    .map(|cursor| cursor.height)
+                    .or_else(|_| storage.latest_block_height(chain_id))
+            })
+            .await
+            .ok()
+    }
+
+    /// moves the cursor of a derived pipeline, e.g. `validators`, so that it syncs again from the next height.
+    /// the blocks pipeline cannot be reset, blocks are indexed again with [`ChainIndexer::reindex`].
+    pub async fn reset_cursor(&self, pipeline: &str, height: i64) -> Result<(), Error> {
+        if pipeline == PIPELINE_BLOCKS {
+            return Err(Error::InvalidConfig(format!(
+                "{} cursor cannot be reset, reindex the blocks instead",
+                PIPELINE_BLOCKS
+            )));
+        }
+
+        let chain = self.find_indexed_chain().await?;
+        let cursor = NewIndexerCursor {
+            chain_id: chain.id,
+            pipeline: pipeline.to_string(),
+            height,
+            inserted_at: current_time(),
+        };
+        self.storage
+            .run(move |storage| storage.upsert_indexer_cursor(&cursor))
+            .await?;
+        info!(
+            "reset cursor | chain_id: {}, pipeline: {}, height: {}",
+            self.chain_id(),
+            pipeline,
+            height
+        );
+
+        Ok(())
+    }
+}
+
+/// ReorgGuard bounds the rollbacks caused by forks at the tip,
+/// so that a misbehaving node cannot make the indexer delete the whole history.
+struct ReorgGuard {
+    max_depth: i64,
+    /// height of the first block which didn't link, until the chain is committed up to it again.
+    fork_height: Option<i64>,
+}
+
+impl ReorgGuard {
+    fn new(max_depth: i64) -> Self {
+        ReorgGuard {
+            max_depth,
+            fork_height: None,
+        }
+    }
+
+    /// returns the height to roll back from, for a block at `height` which doesn't link to the stored one.
+    /// it fails if the rollback would go deeper than the max depth below the height the fork was found at.
+    fn rollback_height(&mut self, height: i64) -> Result<i64, Error> {
+        let fork_height = *self.fork_height.get_or_insert(height);
+        let rollback_height = height - 1;
+        if fork_height - rollback_height > self.max_depth {
+            return Err(Error::ReorgTooDeep(fork_height, self.max_depth));
+        }
+
+        Ok(rollback_height)
+    }
+
+    /// forgets the fork once the blocks have been committed up to the height it was found at.
+    fn committed(&mut self, height: i64) {
+        if matches!(self.fork_height, Some(fork_height) if height >= fork_height) {
+            self.fork_height = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolls_back_one_height_at_a_time() {
+        let mut guard = ReorgGuard::new(3);
+
+        assert_eq!(guard.rollback_height(100).unwrap(), 99);
+        assert_eq!(guard.rollback_height(99).unwrap(), 98);
+        assert_eq!(guard.rollback_height(98).unwrap(), 97);
+    }
+
+    #[test]
+    fn refuses_a_fork_deeper_than_the_max_depth() {
+        let mut guard = ReorgGuard::new(2);
+
+        assert_eq!(guard.rollback_height(100).unwrap(), 99);
+        assert_eq!(guard.rollback_height(99).unwrap(), 98);
+        assert!(matches!(
+            guard.rollback_height(98),
+            Err(Error::ReorgTooDeep(100, 2))
+        ));
+    }
+
+    #[test]
+    fn depth_is_kept_until_the_fork_height_is_committed_again() {
+        let mut guard = ReorgGuard::new(2);
+
+        assert_eq!(guard.rollback_height(100).unwrap(), 99);
+        // the re-fetched block links, but the next one doesn't, the fork isn't resolved yet.
+        guard.committed(99);
+        assert_eq!(guard.rollback_height(100).unwrap(), 99);
+        assert_eq!(guard.rollback_height(99).unwrap(), 98);
+        assert!(guard.rollback_height(98).is_err());
+
+        guard.committed(100);
+        assert_eq!(guard.rollback_height(150).unwrap(), 149);
+    }
+
+    #[test]
+    fn zero_depth_refuses_every_rollback() {
+        let mut guard = ReorgGuard::new(0);
+
+        assert!(matches!(
+            guard.rollback_height(100),
+            Err(Error::ReorgTooDeep(100, 0))
+        ));
+    }
+}
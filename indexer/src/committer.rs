@@ -1,116 +1,548 @@
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
-use cosmos_client::response::EventType;
+use cosmos_client::response::{self, EventType};
 use cosmscan_models::{
-    config::DBConfig,
-    db::BackendDB,
+    async_storage::AsyncStorage,
+    errors::Error as ModelError,
     models::{
         block::NewBlock,
         chain::Chain,
         event::{NewEvent, TX_TYPE_BEGIN_BLOCK, TX_TYPE_END_BLOCK, TX_TYPE_TRANSACTION},
+        indexer_cursor::{NewIndexerCursor, PIPELINE_BLOCKS},
         message::NewMessage,
         transaction::NewTransaction,
+        transaction_fee::NewTransactionFee,
+        transaction_signer::NewTransactionSigner,
     },
-    storage::{PersistenceStorage, StorageWriter},
+    storage::Storage,
 };
 
 use crate::{current_time, errors::Error, messages::MsgCommittedBlock};
 
-pub struct Committer {
-    storage: PersistenceStorage<BackendDB>,
+/// default maximum number of blocks committed in a single transaction.
+pub const DEFAULT_COMMIT_BATCH_SIZE: usize = 100;
+
+/// default approximate size of a commit batch, 8 MiB.
+pub const DEFAULT_COMMIT_BATCH_BYTES: usize = 8 * 1024 * 1024;
+
+/// Committer writes committed blocks of a chain to the storage.
+/// the writes run on the blocking thread pool, so that they don't stall the async runtime.
+pub struct Committer<S> {
+    storage: AsyncStorage<S>,
     chain_info: Chain,
 }
 
-impl Committer {
+impl<S: Storage + Send + Sync + 'static> Committer<S> {
     /// Creates a new committer instance
-    pub fn new(dbconfig: DBConfig, chain_info: Chain) -> Committer {
-        let backend_db = BackendDB::new(dbconfig);
-        let storage = PersistenceStorage::new(backend_db);
-
+    pub fn new(storage: AsyncStorage<S>, chain_info: Chain) -> Committer<S> {
         Committer {
             storage,
             chain_info,
         }
     }
 
-    pub fn commit_block(&self, msg: MsgCommittedBlock) -> Result<bool, Error> {
+    /// commits a single block, see [`Committer::commit_blocks`].
+    pub async fn commit_block(&self, msg: MsgCommittedBlock) -> Result<bool, Error> {
+        self.commit_blocks(vec![msg]).await.map(|_| true)
+    }
+
+    /// commits the blocks in a single transaction, rows of each table are written with multi-row inserts.
+    /// blocks must be sorted by height, each one has to link to the previous block in the batch
+    /// or to the stored one. returns the number of committed blocks.
+    pub async fn commit_blocks(&self, msgs: Vec<MsgCommittedBlock>) -> Result<usize, Error> {
+        let chain_id = self.chain_info.id;
         self.storage
-            .within_transaction(|| {
-                let block = msg.block;
-                let txs = msg.txs;
-
-                // insert block
-                self.storage.insert_block(&NewBlock {
-                    chain_id: self.chain_info.id,
-                    height: block.height,
-                    block_hash: block.block_hash,
-                    prev_hash: block.prev_hash,
-                    proposer_address: block.proposer_address,
-                    last_commit_hash: block.last_commit_hash,
-                    data_hash: block.data_hash,
-                    validators_hash: block.validators_hash,
-                    next_validators_hash: block.next_validators_hash,
-                    consensus_hash: block.consensus_hash,
-                    app_hash: block.app_hash,
-                    last_result_hash: block.last_result_hash,
-                    evidence_hash: block.evidence_hash,
-                    block_time: block.block_time,
-                    inserted_at: current_time(),
-                })?;
-
-                // insert transactions
-                for tx in txs {
-                    let new_tx = self.storage.insert_transaction(&NewTransaction {
-                        chain_id: self.chain_info.id,
-                        height: tx.height,
-                        transaction_hash: tx.transaction_hash,
-                        code: tx.code,
-                        code_space: tx.code_space,
-                        tx_data: tx.tx_data,
-                        raw_log: tx.raw_log,
-                        info: tx.info,
-                        memo: tx.memo,
-                        gas_wanted: tx.gas_wanted,
-                        gas_used: tx.gas_used,
-                        tx_timestamp: tx.tx_timestamp,
-                        inserted_at: current_time(),
-                    })?;
-
-                    for (seq, msg) in tx.messages.iter().enumerate() {
-                        self.storage.insert_message(&NewMessage {
-                            transaction_id: new_tx.id,
-                            seq: seq as i32,
-                            rawdata: serde_json::Value::from_str(msg.clone().as_str())
-                                .expect("cannot unmarshal cosmos message to json"),
-                            inserted_at: current_time(),
-                        })?;
+            .run(move |storage| Self::write_blocks(storage, chain_id, msgs, false))
+            .await
+    }
+
+    /// replaces the stored data between the heights of the first and the last block with the blocks,
+    /// the range is deleted and the blocks are written in a single transaction.
+    /// if the blocks can't be written, the stored data are kept.
+    pub async fn replace_blocks(&self, msgs: Vec<MsgCommittedBlock>) -> Result<usize, Error> {
+        let chain_id = self.chain_info.id;
+        self.storage
+            .run(move |storage| Self::write_blocks(storage, chain_id, msgs, true))
+            .await
+    }
+
+    fn write_blocks(
+        storage: &S,
+        chain_id: i32,
+        msgs: Vec<MsgCommittedBlock>,
+        replace: bool,
+    ) -> Result<usize, Error> {
+        // blocks linking to their predecessor in the batch are checked here,
+        // the others are checked against the stored blocks in the write transaction.
+        let mut unlinked_blocks = vec![];
+        let mut prev_block: Option<&response::Block> = None;
+        for msg in msgs.iter() {
+            match prev_block {
+                Some(prev) if prev.height == msg.block.height - 1 => {
+                    if prev.block_hash != msg.block.prev_hash {
+                        return Err(Error::ChainLinkMismatch(msg.block.height));
                     }
                 }
+                _ => unlinked_blocks.push((msg.block.height, msg.block.prev_hash.clone())),
+            }
+            prev_block = Some(&msg.block);
+        }
+
+        let (first_height, last_height) = match (msgs.first(), msgs.last()) {
+            (Some(first), Some(last)) => (first.block.height, last.block.height),
+            _ => return Ok(0),
+        };
+        let committed = msgs.len();
+
+        let mut new_blocks = Vec::with_capacity(committed);
+        let mut new_txs = vec![];
+        let mut tx_bodies = vec![];
+        let mut new_events = vec![];
+        let mut tx_hashes = HashSet::new();
+        for msg in msgs {
+            let block = msg.block;
+            new_blocks.push(NewBlock {
+                chain_id,
+                height: block.height,
+                block_hash: block.block_hash,
+                prev_hash: block.prev_hash,
+                proposer_address: block.proposer_address,
+                last_commit_hash: block.last_commit_hash,
+                data_hash: block.data_hash,
+                validators_hash: block.validators_hash,
+                next_validators_hash: block.next_validators_hash,
+                consensus_hash: block.consensus_hash,
+                app_hash: block.app_hash,
+                last_result_hash: block.last_result_hash,
+                evidence_hash: block.evidence_hash,
+                block_time: block.block_time,
+                inserted_at: current_time(),
+            });
+
+            for tx in msg.txs {
+                // a hash which appears again in the batch is the transaction stored at the first height,
+                // a single insert can't carry it twice.
+                if !tx_hashes.insert(tx.transaction_hash.clone()) {
+                    continue;
+                }
+                // signers, fee coins and messages need the id of the stored transaction.
+                let messages = tx
+                    .messages
+                    .iter()
+                    .map(|msg| serde_json::Value::from_str(msg.as_str()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                tx_bodies.push((tx.transaction_hash.clone(), tx.signers, tx.fee, messages));
+                new_txs.push(NewTransaction {
+                    chain_id,
+                    height: tx.height,
+                    transaction_hash: tx.transaction_hash,
+                    code: tx.code,
+                    code_space: tx.code_space,
+                    tx_data: tx.tx_data,
+                    raw_log: tx.raw_log,
+                    info: tx.info,
+                    memo: tx.memo,
+                    gas_wanted: tx.gas_wanted,
+                    gas_used: tx.gas_used,
+                    tx_timestamp: tx.tx_timestamp,
+                    gas_limit: tx.gas_limit,
+                    fee_payer: tx.fee_payer,
+                    fee_granter: tx.fee_granter,
+                    inserted_at: current_time(),
+                });
+            }
 
-                // insert events
-                for event in msg.events {
-                    let _type = match event.tx_type {
-                        EventType::BeginBlock => TX_TYPE_BEGIN_BLOCK,
-                        EventType::EndBlock => TX_TYPE_END_BLOCK,
-                        EventType::Transaction => TX_TYPE_TRANSACTION,
-                    };
-
-                    self.storage.insert_event(&NewEvent {
-                        chain_id: self.chain_info.id,
-                        tx_type: _type,
-                        tx_hash: event.tx_hash,
-                        block_height: event.block_height,
-                        event_seq: event.event_seq,
-                        event_type: event.event_type,
-                        event_key: event.event_key,
-                        event_value: event.event_value,
-                        indexed: event.indexed,
+            for event in msg.events {
+                let _type = match event.tx_type {
+                    EventType::BeginBlock => TX_TYPE_BEGIN_BLOCK,
+                    EventType::EndBlock => TX_TYPE_END_BLOCK,
+                    EventType::Transaction => TX_TYPE_TRANSACTION,
+                };
+
+                new_events.push(NewEvent {
+                    chain_id,
+                    tx_type: _type,
+                    tx_hash: event.tx_hash,
+                    block_height: event.block_height,
+                    event_seq: event.event_seq,
+                    attr_seq: event.attr_seq,
+                    event_type: event.event_type,
+                    event_key: event.event_key,
+                    event_value: event.event_value,
+                    indexed: event.indexed,
+                    inserted_at: current_time(),
+                });
+            }
+        }
+
+        storage.within_transaction(|storage| {
+            // nothing has been written yet, the mismatch is returned as the result of the transaction.
+            for (height, prev_hash) in unlinked_blocks.iter() {
+                if let Err(e) = Self::verify_chain_link(storage, chain_id, *height, prev_hash)? {
+                    return Ok(Err(e));
+                }
+            }
+            if replace {
+                storage.delete_blocks_in_range(chain_id, first_height, last_height)?;
+            }
+            storage.insert_blocks(&new_blocks)?;
+
+            // hashes are unique per chain, an upsert of an already stored hash
+            // returns the stored row, even if it has been indexed at another height.
+            let tx_ids = storage
+                .insert_transactions(&new_txs)?
+                .into_iter()
+                .map(|tx| (tx.transaction_hash, tx.id))
+                .collect::<HashMap<_, _>>();
+
+            let mut new_signers = vec![];
+            let mut new_fees = vec![];
+            let mut new_messages = vec![];
+            for (hash, signers, fee, messages) in tx_bodies {
+                let transaction_id = *tx_ids.get(&hash).ok_or(ModelError::NotFound)?;
+                for (seq, signer) in signers.into_iter().enumerate() {
+                    new_signers.push(NewTransactionSigner {
+                        transaction_id,
+                        signer_seq: seq as i32,
+                        pub_key_type: signer.pub_key_type,
+                        pub_key: signer.pub_key,
+                        sequence: signer.sequence,
+                        sign_mode: signer.sign_mode,
                         inserted_at: current_time(),
-                    })?;
+                    });
                 }
+                for (seq, coin) in fee.into_iter().enumerate() {
+                    new_fees.push(NewTransactionFee {
+                        transaction_id,
+                        seq: seq as i32,
+                        denom: coin.denom,
+                        amount: coin.amount,
+                        inserted_at: current_time(),
+                    });
+                }
+                for (seq, rawdata) in messages.into_iter().enumerate() {
+                    new_messages.push(NewMessage {
+                        transaction_id,
+                        seq: seq as i32,
+                        rawdata,
+                        inserted_at: current_time(),
+                    });
+                }
+            }
+            storage.insert_transaction_signers(&new_signers)?;
+            storage.insert_transaction_fees(&new_fees)?;
+            storage.insert_messages(&new_messages)?;
+            storage.insert_events(&new_events)?;
 
-                Ok(true)
-            })
+            // the cursor is moved in the same transaction, so that it never points to a partial block.
+            storage.advance_indexer_cursor(&NewIndexerCursor {
+                chain_id,
+                pipeline: PIPELINE_BLOCKS.to_string(),
+                height: last_height,
+                inserted_at: current_time(),
+            })?;
+
+            Ok(Ok(committed))
+        })?
+    }
+
+    /// checks that `prev_hash` of the block at `height` matches the hash of the stored block at `height - 1`.
+    /// it's skipped if the previous block hasn't been indexed.
+    fn verify_chain_link(
+        storage: &dyn Storage,
+        chain_id: i32,
+        height: i64,
+        prev_hash: &str,
+    ) -> Result<Result<(), Error>, ModelError> {
+        match storage.find_block_by_height(chain_id, height - 1) {
+            Ok(prev_block) if prev_block.block_hash != prev_hash => {
+                Ok(Err(Error::ChainLinkMismatch(height)))
+            }
+            Ok(_) | Err(ModelError::NotFound) => Ok(Ok(())),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// removes every indexed data at or above the given height, so that they can be fetched again.
+    pub async fn rollback_from(&self, height: i64) -> Result<usize, Error> {
+        let chain_id = self.chain_info.id;
+        self.storage
+            .run(move |storage| storage.delete_blocks_from(chain_id, height))
+            .await
             .map_err(|e| e.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cosmos_client::response::{Block, Coin, Event, Signer, Transaction};
+    use std::sync::Arc;
+
+    use crate::messages::BlockSource;
+    use cosmscan_models::{
+        memory_storage::MemoryStorage,
+        models::chain::NewChain,
+        storage::{StorageReader, StorageWriter},
+    };
+
+    use super::*;
+
+    fn committer() -> (Arc<MemoryStorage>, Committer<MemoryStorage>) {
+        let storage = Arc::new(MemoryStorage::new());
+        storage
+            .insert_chain(&NewChain {
+                chain_id: "cosmoshub-4".to_string(),
+                chain_name: "cosmoshub".to_string(),
+                inserted_at: current_time(),
+            })
+            .unwrap();
+        let chain = storage.find_by_chain_id("cosmoshub-4".to_string()).unwrap();
+
+        let committer = Committer::new(AsyncStorage::from_shared(storage.clone(), 1), chain);
+        (storage, committer)
+    }
+
+    fn block(height: i64, prev_hash: &str) -> MsgCommittedBlock {
+        MsgCommittedBlock {
+            block: Block {
+                height,
+                block_hash: format!("HASH{}", height),
+                prev_hash: prev_hash.to_string(),
+                proposer_address: "PROPOSER".to_string(),
+                last_commit_hash: String::new(),
+                data_hash: String::new(),
+                validators_hash: "VALIDATORS".to_string(),
+                next_validators_hash: "VALIDATORS".to_string(),
+                consensus_hash: String::new(),
+                app_hash: String::new(),
+                last_result_hash: String::new(),
+                evidence_hash: String::new(),
+                block_time: current_time(),
+            },
+            txs: vec![],
+            events: vec![],
+            source: BlockSource::Tip,
+        }
+    }
+
+    fn with_tx(mut msg: MsgCommittedBlock, hash: &str, messages: &[&str]) -> MsgCommittedBlock {
+        msg.txs.push(Transaction {
+            transaction_hash: hash.to_string(),
+            height: msg.block.height,
+            code: 0,
+            code_space: String::new(),
+            tx_data: String::new(),
+            raw_log: String::new(),
+            info: String::new(),
+            memo: None,
+            gas_wanted: 200000,
+            gas_used: 100000,
+            tx_timestamp: String::new(),
+            messages: messages.iter().map(|m| m.to_string()).collect(),
+            fee: vec![
+                Coin {
+                    denom: "uatom".to_string(),
+                    amount: "500".to_string(),
+                },
+                Coin {
+                    denom: "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2"
+                        .to_string(),
+                    amount: "1000000000000000000000".to_string(),
+                },
+            ],
+            gas_limit: 200000,
+            fee_payer: None,
+            fee_granter: None,
+            signers: vec![Signer {
+                pub_key_type: None,
+                pub_key: None,
+                sequence: 1,
+                sign_mode: "SIGN_MODE_DIRECT".to_string(),
+            }],
+        });
+        msg.events.push(Event {
+            tx_type: EventType::Transaction,
+            tx_hash: Some(hash.to_string()),
+            block_height: msg.block.height,
+            event_seq: 0,
+            attr_seq: 0,
+            event_type: "message".to_string(),
+            event_key: "action".to_string(),
+            event_value: "send".to_string(),
+            indexed: true,
+        });
+        msg
+    }
+
+    const SEND_MSG: &str = r#"{"@type":"/cosmos.bank.v1beta1.MsgSend"}"#;
+
+    #[tokio::test]
+    async fn commits_blocks_with_their_transactions() {
+        let (storage, committer) = committer();
+        let chain_id = committer.chain_info.id;
+
+        let batch = vec![
+            block(1, ""),
+            with_tx(block(2, "HASH1"), "TX2", &[SEND_MSG, SEND_MSG]),
+        ];
+        assert_eq!(committer.commit_blocks(batch.clone()).await.unwrap(), 2);
+        // commits are idempotent, a retried batch doesn't duplicate rows.
+        assert_eq!(committer.commit_blocks(batch).await.unwrap(), 2);
+
+        assert_eq!(storage.latest_block_height(chain_id).unwrap(), 2);
+        let tx = storage
+            .find_transaction_by_hash(chain_id, "TX2".to_string())
+            .unwrap();
+        assert_eq!(tx.height, 2);
+        assert_eq!(storage.list_messages_by_tx(tx.id).unwrap().len(), 2);
+        assert_eq!(storage.list_signers_by_tx(tx.id).unwrap().len(), 1);
+        let fee = storage.list_fees_by_tx(tx.id).unwrap();
+        assert_eq!(
+            fee.iter().map(|f| f.amount.as_str()).collect::<Vec<_>>(),
+            vec!["500", "1000000000000000000000"]
+        );
+        assert_eq!(
+            storage
+                .list_events_by_tx(chain_id, "TX2".to_string())
+                .unwrap()
+                .len(),
+            1
+        );
+        let cursor = storage
+            .find_indexer_cursor(chain_id, PIPELINE_BLOCKS)
+            .unwrap();
+        assert_eq!(cursor.height, 2);
+    }
+
+    #[tokio::test]
+    async fn refuses_a_block_which_doesnt_link_to_the_stored_one() {
+        let (storage, committer) = committer();
+        let chain_id = committer.chain_info.id;
+        committer.commit_block(block(1, "")).await.unwrap();
+
+        let result = committer.commit_block(block(2, "FORKED")).await;
+        assert!(matches!(result, Err(Error::ChainLinkMismatch(2))));
+
+        let result = committer
+            .commit_blocks(vec![block(2, "HASH1"), block(3, "FORKED")])
+            .await;
+        assert!(matches!(result, Err(Error::ChainLinkMismatch(3))));
+        assert_eq!(storage.latest_block_height(chain_id).unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn invalid_message_json_fails_the_whole_batch() {
+        let (storage, committer) = committer();
+        let chain_id = committer.chain_info.id;
+
+        let result = committer
+            .commit_blocks(vec![
+                block(1, ""),
+                with_tx(block(2, "HASH1"), "TX2", &["not json"]),
+            ])
+            .await;
+        assert!(matches!(result, Err(Error::InvalidJSONError(_))));
+        assert!(storage.latest_block_height(chain_id).is_err());
+    }
+
+    #[tokio::test]
+    async fn a_known_hash_at_another_height_uses_the_stored_transaction() {
+        let (storage, committer) = committer();
+        let chain_id = committer.chain_info.id;
+
+        committer
+            .commit_blocks(vec![
+                block(1, ""),
+                with_tx(block(2, "HASH1"), "TX", &[SEND_MSG]),
+                with_tx(block(3, "HASH2"), "TX", &[SEND_MSG]),
+            ])
+            .await
+            .unwrap();
+
+        let tx = storage
+            .find_transaction_by_hash(chain_id, "TX".to_string())
+            .unwrap();
+        assert_eq!(tx.height, 2);
+        assert_eq!(storage.list_messages_by_tx(tx.id).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rollback_removes_the_blocks_from_the_height() {
+        let (storage, committer) = committer();
+        let chain_id = committer.chain_info.id;
+        committer
+            .commit_blocks(vec![
+                block(1, ""),
+                block(2, "HASH1"),
+                with_tx(block(3, "HASH2"), "TX3", &[SEND_MSG]),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(committer.rollback_from(2).await.unwrap(), 2);
+        assert_eq!(storage.latest_block_height(chain_id).unwrap(), 1);
+        assert!(storage
+            .find_transaction_by_hash(chain_id, "TX3".to_string())
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn replace_swaps_the_stored_range() {
+        let (storage, committer) = committer();
+        let chain_id = committer.chain_info.id;
+        committer
+            .commit_blocks(vec![
+                block(1, ""),
+                with_tx(block(2, "HASH1"), "OLD", &[SEND_MSG, SEND_MSG]),
+                with_tx(block(3, "HASH2"), "TX3", &[SEND_MSG]),
+            ])
+            .await
+            .unwrap();
+
+        let replaced = committer
+            .replace_blocks(vec![with_tx(block(2, "HASH1"), "NEW", &[SEND_MSG])])
+            .await
+            .unwrap();
+        assert_eq!(replaced, 1);
+
+        assert!(storage
+            .find_transaction_by_hash(chain_id, "OLD".to_string())
+            .is_err());
+        let tx = storage
+            .find_transaction_by_hash(chain_id, "NEW".to_string())
+            .unwrap();
+        assert_eq!(storage.list_messages_by_tx(tx.id).unwrap().len(), 1);
+        // blocks outside of the range are kept, and so is the cursor.
+        assert!(storage
+            .find_transaction_by_hash(chain_id, "TX3".to_string())
+            .is_ok());
+        let cursor = storage
+            .find_indexer_cursor(chain_id, PIPELINE_BLOCKS)
+            .unwrap();
+        assert_eq!(cursor.height, 3);
+    }
+
+    #[tokio::test]
+    async fn a_failed_replace_keeps_the_stored_range() {
+        let (storage, committer) = committer();
+        let chain_id = committer.chain_info.id;
+        committer
+            .commit_blocks(vec![
+                block(1, ""),
+                with_tx(block(2, "HASH1"), "OLD", &[SEND_MSG]),
+            ])
+            .await
+            .unwrap();
+
+        let result = committer
+            .replace_blocks(vec![with_tx(block(2, "FORKED"), "NEW", &[SEND_MSG])])
+            .await;
+        assert!(matches!(result, Err(Error::ChainLinkMismatch(2))));
+
+        assert_eq!(storage.latest_block_height(chain_id).unwrap(), 2);
+        assert!(storage
+            .find_transaction_by_hash(chain_id, "OLD".to_string())
+            .is_ok());
+    }
+}
@@ -1,14 +1,28 @@
+use crate::retry::RetryConfig;
+use cosmos_client::endpoint::EndpointSelection;
 use cosmscan_models::config::DBConfig;
 use serde::Deserialize;
+use std::collections::HashSet;
 use std::error::Error;
 use std::fs;
 
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct Config {
     pub indexer: IndexerConfig,
-    pub fetcher: FetcherConfig,
-    pub chain: ChainConfig,
+    pub fetcher: Option<FetcherConfig>,
+    pub chain: Option<ChainConfig>,
+    /// additional chains indexed by the same process, declared as `[[chains]]`.
+    #[serde(default)]
+    pub chains: Vec<ChainSectionConfig>,
     pub db: DBConfig,
+    pub retry: Option<RetryConfig>,
+}
+
+/// ChainSectionConfig pairs a chain with the fetcher for it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ChainSectionConfig {
+    pub chain: ChainConfig,
+    pub fetcher: FetcherConfig,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
@@ -17,23 +31,158 @@ pub struct ChainConfig {
     pub chain_name: String,
     pub icon_url: Option<String>,
     pub website: Option<String>,
+    /// chain ids reported by the nodes which are accepted besides `chain_id`.
+    #[serde(default)]
+    pub network_aliases: Vec<String>,
+    /// skips the check that every endpoint serves `chain_id`.
+    #[serde(default)]
+    pub skip_chain_id_check: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct FetcherConfig {
-    pub tendermint_rpc_endpoint: String,
-    pub grpc_endpoint: String,
-    pub rest_api_endpoint: String,
+    pub tendermint_rpc_endpoint: Option<String>,
+    pub grpc_endpoint: Option<String>,
+    /// additional endpoints of the same chain, requests fail over between every endpoint.
+    #[serde(default)]
+    pub tendermint_rpc_endpoints: Vec<String>,
+    #[serde(default)]
+    pub grpc_endpoints: Vec<String>,
+    /// `round_robin` (default) or `least_latency`.
+    pub endpoint_selection: Option<EndpointSelection>,
+    /// seconds between two health checks of the endpoints, 0 disables them.
+    pub health_check_interval_secs: Option<u64>,
+    /// maximum number of requests in flight to the endpoints, unlimited if it's not set.
+    pub max_in_flight_requests: Option<usize>,
+    /// websocket endpoint for `NewBlock` subscriptions, e.g. ws://localhost:26657/websocket.
+    /// the fetcher polls the rpc endpoint if it's not set.
+    pub tendermint_ws_endpoint: Option<String>,
     pub start_block: i64,
     pub try_resume_from_db: bool,
+    /// number of heights fetched concurrently while catching up to the tip.
+    pub backfill_workers: Option<usize>,
+    /// what to do when the start block has been pruned by the node, `clamp` (default) or `refuse`.
+    pub pruned_start: Option<PrunedStartPolicy>,
+    /// maximum number of stored heights rolled back when the tip forks, indexing stops beyond it.
+    pub max_reorg_depth: Option<i64>,
+}
+
+/// PrunedStartPolicy decides how indexing starts below the earliest block of the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrunedStartPolicy {
+    /// starts from the earliest block, the skipped heights are recorded as unindexable.
+    #[default]
+    Clamp,
+    /// refuses to start.
+    Refuse,
+}
+
+impl FetcherConfig {
+    /// returns `tendermint_rpc_endpoint` followed by `tendermint_rpc_endpoints`.
+    pub fn rpc_endpoints(&self) -> Vec<String> {
+        merge_endpoints(
+            &self.tendermint_rpc_endpoint,
+            &self.tendermint_rpc_endpoints,
+        )
+    }
+
+    /// returns `grpc_endpoint` followed by `grpc_endpoints`.
+    pub fn grpc_endpoints(&self) -> Vec<String> {
+        merge_endpoints(&self.grpc_endpoint, &self.grpc_endpoints)
+    }
+}
+
+fn merge_endpoints(single: &Option<String>, list: &[String]) -> Vec<String> {
+    let mut endpoints = single.iter().cloned().collect::<Vec<_>>();
+    for endpoint in list {
+        if !endpoints.contains(endpoint) {
+            endpoints.push(endpoint.clone());
+        }
+    }
+    endpoints
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct IndexerConfig {
     pub fetcher_account_enabled: bool,
+    /// stores the validator set history, fetched whenever `validators_hash` changes.
+    #[serde(default)]
+    pub fetcher_validator_enabled: bool,
+    /// seconds between two scans for missing blocks, 0 scans only on startup.
+    pub gap_scan_interval_secs: Option<u64>,
+    /// maximum number of queued blocks committed in a single transaction while catching up.
+    pub commit_batch_size: Option<usize>,
+    /// approximate size in bytes of a batch, a single block larger than it is still committed.
+    pub commit_batch_bytes: Option<usize>,
 }
 
 impl Config {
+    /// returns every chain to be indexed.
+    /// the top-level `[chain]` and `[fetcher]` pair comes first, followed by `[[chains]]`.
+    pub fn chain_sections(&self) -> Result<Vec<ChainSectionConfig>, String> {
+        let mut sections = vec![];
+        match (&self.chain, &self.fetcher) {
+            (Some(chain), Some(fetcher)) => sections.push(ChainSectionConfig {
+                chain: chain.clone(),
+                fetcher: fetcher.clone(),
+            }),
+            (None, None) => {}
+            _ => return Err("[chain] and [fetcher] must be configured together".to_string()),
+        }
+        sections.extend(self.chains.iter().cloned());
+
+        if sections.is_empty() {
+            return Err("at least one chain must be configured".to_string());
+        }
+
+        let mut chain_ids = HashSet::new();
+        for section in sections.iter() {
+            if !chain_ids.insert(section.chain.chain_id.clone()) {
+                return Err(format!(
+                    "chain {} is configured more than once",
+                    section.chain.chain_id
+                ));
+            }
+            if section.fetcher.rpc_endpoints().is_empty()
+                || section.fetcher.grpc_endpoints().is_empty()
+            {
+                return Err(format!(
+                    "chain {} needs at least one tendermint rpc and one gRPC endpoint",
+                    section.chain.chain_id
+                ));
+            }
+        }
+
+        Ok(sections)
+    }
+
+    /// keeps only the given chain, so that an indexer built from it connects to that chain's nodes only.
+    /// the chain can be omitted if only one chain is configured.
+    pub fn select_chain(self, chain_id: Option<&str>) -> Result<Self, String> {
+        let mut sections = self.chain_sections()?;
+        let section = match chain_id {
+            Some(chain_id) => {
+                let position = sections
+                    .iter()
+                    .position(|section| section.chain.chain_id == chain_id)
+                    .ok_or_else(|| format!("chain {} is not configured", chain_id))?;
+                sections.swap_remove(position)
+            }
+            None if sections.len() == 1 => sections.remove(0),
+            None => {
+                return Err("chain id must be given when several chains are configured".to_string())
+            }
+        };
+
+        Ok(Config {
+            fetcher: None,
+            chain: None,
+            chains: vec![section],
+            ..self
+        })
+    }
+
     pub fn from_file(file: String) -> Result<Self, Box<dyn Error>> {
         let raw_config = fs::read_to_string(file)?;
         let config: Config = toml::from_str(raw_config.as_str()).unwrap();
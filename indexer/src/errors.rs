@@ -8,7 +8,7 @@ pub enum Error {
     FetchingTransactionFailed,
 
     #[error("cosmos client error occurred")]
-    CosmosClientError(#[from] cosmos_client::errors::Error),
+    CosmosClientError(Box<cosmos_client::errors::Error>),
 
     #[error("database model error")]
     DBError(#[from] cosmscan_models::errors::Error),
@@ -17,7 +17,7 @@ pub enum Error {
     InvalidJSONError(#[from] serde_json::Error),
 
     #[error("unknown server error")]
-    UnknownServerError(tendermint_rpc::Error),
+    UnknownServerError(Box<tendermint_rpc::Error>),
 
     #[error("failed to convert attribute to the string")]
     Utf8ConversionError(#[from] Utf8Error),
@@ -25,9 +25,50 @@ pub enum Error {
     #[error("start block must be greater than 0")]
     StartBlockMustBeGreaterThanZero,
 
+    #[error("block at height {0} doesn't link to the stored previous block")]
+    ChainLinkMismatch(i64),
+
+    #[error("fork at height {0} is deeper than the maximum reorg depth of {1} blocks")]
+    ReorgTooDeep(i64, i64),
+
+    #[error("committed block channel has been closed")]
+    CommittedBlockChannelClosed,
+
+    #[error("block {0} has been pruned by the node, the earliest available block is {1}")]
+    BlockPruned(i64, i64),
+
+    #[error("chain id mismatch: {0} is configured, but {1} serves {2}")]
+    ChainIdMismatch(String, String, String),
+
+    #[error("invalid height range: from {0} to {1}")]
+    InvalidHeightRange(i64, i64),
+
+    #[error("chain {0} has not been indexed yet")]
+    ChainNotIndexed(String),
+
+    #[error("invalid config: {0}")]
+    InvalidConfig(String),
+
     #[error("Unexpected error")]
     UnexpectedError,
 
     #[error("unknown error ${0}")]
     Other(String),
 }
+
+impl Error {
+    /// returns true if retrying the same call may succeed.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::CosmosClientError(e) => e.is_transport_failure(),
+            Error::DBError(e) => e.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+impl From<cosmos_client::errors::Error> for Error {
+    fn from(err: cosmos_client::errors::Error) -> Self {
+        Error::CosmosClientError(Box::new(err))
+    }
+}
@@ -0,0 +1,212 @@
+use crate::errors::Error;
+use crate::messages::MsgCommittedBlock;
+use crate::retry::RetryPolicy;
+use crate::{current_time, SharedClient};
+
+use cosmscan_models::{
+    async_storage::AsyncStorage,
+    models::{account::NewAccount, account_balance::NewAccountBalance},
+    storage::Storage,
+};
+use futures::{stream, StreamExt};
+use log::{info, warn};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+pub type TouchedAccountsReceiver = mpsc::Receiver<Vec<String>>;
+
+/// number of accounts refreshed concurrently.
+const REFRESH_WORKERS: usize = 8;
+
+/// number of rounds a failed account is refreshed in before it's dropped.
+const MAX_REFRESH_ROUNDS: u32 = 3;
+
+/// delay before the failed accounts are refreshed again when nothing else is touched.
+const REQUEUE_DELAY: Duration = Duration::from_secs(10);
+
+/// event attribute keys which hold an account address.
+const ADDRESS_EVENT_KEYS: [&str; 9] = [
+    "sender",
+    "recipient",
+    "receiver",
+    "spender",
+    "minter",
+    "burner",
+    "delegator",
+    "granter",
+    "grantee",
+];
+
+/// AccountFetcher keeps the accounts and their bank balances up to date.
+/// It receives addresses touched by committed blocks, and refreshes their balances at the latest height.
+pub struct AccountFetcher<S> {
+    pub client: SharedClient,
+    pub storage: AsyncStorage<S>,
+    pub chain_id: i32,
+    pub retry_policy: RetryPolicy,
+}
+
+impl<S: Storage + Send + Sync + 'static> AccountFetcher<S> {
+    /// creates a new AccountFetcher instance.
+    pub fn new(
+        client: SharedClient,
+        storage: AsyncStorage<S>,
+        chain_id: i32,
+        retry_policy: RetryPolicy,
+    ) -> Self {
+        AccountFetcher {
+            client,
+            storage,
+            chain_id,
+            retry_policy,
+        }
+    }
+
+    /// spawns the refreshing loop, it runs until every sender has been dropped.
+    /// addresses queued while a round is running are merged, so that each is refreshed once per round.
+    /// addresses which failed to refresh are added to the next rounds, up to `MAX_REFRESH_ROUNDS`.
+    pub fn run_loop(self, mut receiver: TouchedAccountsReceiver) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            // failed addresses, with the number of rounds they have failed in.
+            let mut failed: BTreeMap<String, u32> = BTreeMap::new();
+            loop {
+                let received = if failed.is_empty() {
+                    receiver.recv().await
+                } else {
+                    tokio::time::timeout(REQUEUE_DELAY, receiver.recv())
+                        .await
+                        .unwrap_or_else(|_| Some(Vec::new()))
+                };
+                let addresses = match received {
+                    Some(addresses) => addresses,
+                    None => break,
+                };
+
+                let mut pending: BTreeSet<String> = addresses.into_iter().collect();
+                while let Ok(addresses) = receiver.try_recv() {
+                    pending.extend(addresses);
+                }
+                pending.extend(failed.keys().cloned());
+
+                let fetcher = &self;
+                let results = stream::iter(pending)
+                    .map(|address| async move {
+                        let result = fetcher.refresh_account(address.clone()).await;
+                        (address, result)
+                    })
+                    .buffer_unordered(REFRESH_WORKERS)
+                    .collect::<Vec<_>>()
+                    .await;
+
+                for (address, result) in results {
+                    let e = match result {
+                        Ok(()) => {
+                            failed.remove(&address);
+                            continue;
+                        }
+                        Err(e) => e,
+                    };
+
+                    let rounds = failed.entry(address.clone()).or_insert(0);
+                    *rounds += 1;
+                    if *rounds < MAX_REFRESH_ROUNDS {
+                        warn!(
+                            "failed to refresh account, it's re-queued | address: {}, rounds: {}, error: {}",
+                            address, rounds, e
+                        );
+                    } else {
+                        warn!(
+                            "failed to refresh account, giving up | address: {}, rounds: {}, error: {}",
+                            address, rounds, e
+                        );
+                        failed.remove(&address);
+                    }
+                }
+            }
+        })
+    }
+
+    /// upserts the account and replaces its balances with the ones from the bank module.
+    async fn refresh_account(&self, address: String) -> Result<(), Error> {
+        let coins = self
+            .retry_policy
+            .retry(
+                &format!("fetching balances of {}", address),
+                || {
+                    let client = self.client.clone();
+                    let address = address.clone();
+                    async move { client.get_all_balances(address).await.map_err(Error::from) }
+                },
+                Error::is_transient,
+            )
+            .await?;
+
+        // amounts are kept as the decimal strings of the bank module, they may exceed 64 bits.
+        let mut balances = coins
+            .into_iter()
+            .map(|coin| NewAccountBalance {
+                // assigned once the account has been stored.
+                account_id: 0,
+                amount: coin.amount,
+                denom: coin.denom,
+                inserted_at: current_time(),
+            })
+            .collect::<Vec<_>>();
+        let denoms = balances.len();
+
+        let new_account = NewAccount {
+            chain_id: self.chain_id,
+            address: address.clone(),
+            inserted_at: current_time(),
+        };
+        self.storage
+            .run(move |storage| {
+                let account = storage.upsert_account(&new_account)?;
+                for balance in balances.iter_mut() {
+                    balance.account_id = account.id;
+                }
+                storage.replace_account_balances(account.id, &balances)
+            })
+            .await?;
+        info!(
+            "refreshed account | address: {}, denoms: {}",
+            address, denoms
+        );
+
+        Ok(())
+    }
+}
+
+/// returns the account addresses which appear in the events of the committed block.
+pub fn touched_addresses(msg: &MsgCommittedBlock) -> Vec<String> {
+    let mut addresses = HashSet::new();
+    for event in msg.events.iter() {
+        if ADDRESS_EVENT_KEYS.contains(&event.event_key.as_str())
+            && is_account_address(&event.event_value)
+        {
+            addresses.insert(event.event_value.clone());
+        }
+    }
+
+    addresses.into_iter().collect()
+}
+
+/// checks if the value looks like a bech32 account address.
+/// validator operator and consensus addresses are excluded, they don't hold bank balances.
+fn is_account_address(value: &str) -> bool {
+    let (hrp, data) = match value.rsplit_once('1') {
+        Some(parts) => parts,
+        None => return false,
+    };
+
+    !hrp.is_empty()
+        && !hrp.ends_with("valoper")
+        && !hrp.ends_with("valcons")
+        && data.len() >= 38
+        && value.len() <= 90
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+}
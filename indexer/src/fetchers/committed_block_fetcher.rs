@@ -1,144 +1,372 @@
 use crate::errors::Error;
-use crate::messages::MsgCommittedBlock;
+use crate::messages::{BlockSource, MsgCommittedBlock};
+use crate::retry::RetryPolicy;
 use crate::SharedClient;
 
-use cosmos_client::response;
+use cosmos_client::subscription::NewBlockSubscription;
 
+use futures::{stream, StreamExt};
 use log::info;
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 
-type CommittedBlockSender = mpsc::Sender<MsgCommittedBlock>;
+pub(crate) type CommittedBlockSender = mpsc::Sender<MsgCommittedBlock>;
+
+/// default number of concurrent fetches during the historical backfill.
+pub const DEFAULT_BACKFILL_WORKERS: usize = 4;
+
+/// maximum number of heights scheduled in a single backfill round.
+const BACKFILL_BATCH_SIZE: i64 = 1000;
+
+/// first wait for a new block while polling, it doubles up to `MAX_POLL_INTERVAL` until a block arrives.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(1000);
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(10000);
+
+/// time spent polling before the NewBlock subscription is tried again.
+const RESUBSCRIBE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// message of the node for a height above the tip, e.g.
+/// "height 129 must be less than or equal to the current blockchain height 128".
+const BLOCK_NOT_READY_MESSAGE: &str = "must be less than or equal to the current blockchain height";
 
 /// CommittedBlockFetcher fetches blocks, transactions, and events from Tendermint RPC and Cosmos REST API
 pub struct CommittedBlockFetcher {
     pub client: SharedClient,
+    pub backfill_workers: usize,
+    pub retry_policy: RetryPolicy,
 }
 
 impl CommittedBlockFetcher {
     /// creates a new CommittedBlockFetcher instance.
-    pub async fn new(client: SharedClient) -> Result<Self, Error> {
-        Ok(CommittedBlockFetcher { client })
+    pub async fn new(
+        client: SharedClient,
+        backfill_workers: usize,
+        retry_policy: RetryPolicy,
+    ) -> Result<Self, Error> {
+        Ok(CommittedBlockFetcher {
+            client,
+            backfill_workers: backfill_workers.max(1),
+            retry_policy,
+        })
+    }
+
+    /// spawns the fetching loop, it sends committed blocks in height order starting from `start_block`.
+    /// the task only finishes with an error, when a block cannot be fetched even after retrying
+    /// or the receiver has been dropped.
+    pub fn run_loop(
+        &self,
+        sender: CommittedBlockSender,
+        start_block: i64,
+    ) -> JoinHandle<Result<(), Error>> {
+        self.spawn(sender, start_block, true)
+    }
+
+    /// same as [`CommittedBlockFetcher::run_loop`] without the concurrent backfill,
+    /// every block is fetched as a tip block. it's used to fetch the heights rolled back after a fork again.
+    pub fn follow_tip(
+        &self,
+        sender: CommittedBlockSender,
+        start_block: i64,
+    ) -> JoinHandle<Result<(), Error>> {
+        self.spawn(sender, start_block, false)
     }
 
-    pub fn run_loop(&self, sender: CommittedBlockSender, start_block: i64) -> JoinHandle<()> {
+    fn spawn(
+        &self,
+        sender: CommittedBlockSender,
+        start_block: i64,
+        backfill: bool,
+    ) -> JoinHandle<Result<(), Error>> {
         let client = self.client.clone();
+        let workers = self.backfill_workers;
+        let policy = self.retry_policy;
         tokio::spawn(async move {
-            let mut current_block = start_block;
+            // catch up with the chain first, then follow the tip one block at a time.
+            let mut current_block = if backfill {
+                Self::backfill(client.clone(), &sender, policy, start_block, workers).await?
+            } else {
+                start_block
+            };
 
             loop {
-                match Self::committed_block_at(client.clone(), current_block).await {
-                    Ok(committed_block) => {
-                        let sender = sender.clone();
+                // prefer push-based notifications, polling is used while the subscription is unavailable.
+                let mut resubscribe_after = Some(RESUBSCRIBE_INTERVAL);
+                match client.subscribe_new_blocks().await {
+                    Ok(subscription) => {
                         info!(
-                            "commit block | block_number: {}, hash: {}",
-                            current_block,
-                            committed_block.block.block_hash.clone(),
+                            "subscribed to NewBlock events | block_number: {}",
+                            current_block
                         );
-                        sender.send(committed_block).await.unwrap();
-                        current_block += 1;
+                        current_block = Self::follow_subscription(
+                            client.clone(),
+                            &sender,
+                            policy,
+                            subscription,
+                            current_block,
+                        )
+                        .await?;
+                        log::warn!("NewBlock subscription dropped, falling back to polling");
+                    }
+                    Err(cosmos_client::errors::Error::WebSocketNotConfigured) => {
+                        resubscribe_after = None;
                     }
                     Err(e) => {
-                        match e {
-                            Error::CosmosClientError(cosmos_client::errors::Error::RPCError(
-                                tendermint_rpc::Error(
-                                    tendermint_rpc::error::ErrorDetail::Response(ref resp),
-                                    _,
-                                ),
-                            )) => {
-                                if resp.source.code() == tendermint_rpc::Code::InternalError {
-                                    // wait for new block
-                                    // this error occurred when the block given as parameter is not yet proposed by the validator
-                                    // Caused by:
-                                    //      Internal error: height 129 must be less than or equal to the current blockchain height 128 (code: -32603)
-                                    tokio::time::sleep(Duration::from_millis(2000)).await;
-                                } else {
-                                    log::error!("error: {}", e);
-                                    panic!("committed block worker has been failed unexpectedly");
-                                }
-                            }
-                            _ => {
-                                panic!("committed block worker has been failed unexpectedly");
-                            }
-                        }
+                        log::warn!("failed to subscribe NewBlock events: {}", e);
                     }
                 }
+
+                current_block = Self::poll(
+                    client.clone(),
+                    &sender,
+                    policy,
+                    current_block,
+                    resubscribe_after,
+                )
+                .await?;
             }
         })
     }
 
-    async fn committed_block_at(
+    /// fetches blocks whenever the subscription notifies a new height.
+    /// returns the next height to be fetched once the subscription has been dropped.
+    async fn follow_subscription(
         client: SharedClient,
-        block_height: i64,
-    ) -> Result<MsgCommittedBlock, Error> {
-        // get block info from given height
-        let (block, tx_hashes) = client.lock().await.get_block(block_height).await?;
-        info!(
-            "found new block | block_number:{}, hash: {}",
-            block.height,
-            block.block_hash.clone(),
-        );
+        sender: &CommittedBlockSender,
+        policy: RetryPolicy,
+        mut subscription: NewBlockSubscription,
+        start_block: i64,
+    ) -> Result<i64, Error> {
+        let mut current_block = start_block;
+
+        while let Some(latest_height) = subscription.next_height().await {
+            while current_block <= latest_height {
+                match Self::fetch_with_retry(
+                    client.clone(),
+                    policy,
+                    current_block,
+                    BlockSource::Tip,
+                )
+                .await
+                {
+                    Ok(committed_block) => {
+                        Self::send(sender, committed_block).await?;
+                        current_block += 1;
+                    }
+                    // the event can arrive before the block is queryable, retry with the next event.
+                    Err(e) if Self::is_block_not_ready(&e) => break,
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Ok(current_block)
+    }
+
+    /// polls blocks one by one, waiting with backoff while the requested height is not yet available.
+    /// returns the next height to be fetched once it has been polling for `resubscribe_after`,
+    /// it polls forever if it's `None`.
+    async fn poll(
+        client: SharedClient,
+        sender: &CommittedBlockSender,
+        policy: RetryPolicy,
+        start_block: i64,
+        resubscribe_after: Option<Duration>,
+    ) -> Result<i64, Error> {
+        let started = tokio::time::Instant::now();
+        let mut current_block = start_block;
+        let mut interval = MIN_POLL_INTERVAL;
 
-        // get block result from given height
-        let block_result = client.lock().await.get_block_result(block_height).await?;
-
-        // fetch trasnactions
-        let mut transactions: Vec<response::Transaction> = vec![];
-        let mut events: Vec<response::Event> = vec![];
-        events.extend(block_result.begin_block_events);
-        events.extend(block_result.end_block_events);
-
-        let future_transactions = tx_hashes.into_iter().map(|tx_hash| {
-            let client = client.clone();
-            async move { client.lock().await.get_transaction(tx_hash.clone()).await }
-        });
-
-        for result in futures::future::join_all(future_transactions).await {
-            match result {
-                Ok((tx, _events)) => {
-                    transactions.push(tx);
-                    events.extend(_events);
+        loop {
+            match Self::fetch_with_retry(client.clone(), policy, current_block, BlockSource::Tip)
+                .await
+            {
+                Ok(committed_block) => {
+                    Self::send(sender, committed_block).await?;
+                    current_block += 1;
+                    interval = MIN_POLL_INTERVAL;
                 }
-                Err(e) => {
-                    return Err(Error::Other(e.to_string()));
+                Err(e) if Self::is_block_not_ready(&e) => {
+                    // the block has not been committed yet, the subscription is only tried again at the tip.
+                    if matches!(resubscribe_after, Some(after) if started.elapsed() >= after) {
+                        return Ok(current_block);
+                    }
+                    tokio::time::sleep(interval).await;
+                    interval = (interval * 2).min(MAX_POLL_INTERVAL);
                 }
+                Err(e) => return Err(e),
             }
         }
+    }
 
-        // wrap transactions with messages
-        let tx_with_messages = transactions.into_iter().map(|tx| {
-            let client = client.clone();
+    /// fetches every height from `start_block` up to the current tip with `workers` concurrent requests.
+    /// results are forwarded to the sender in strict height order.
+    /// returns the next height to be fetched once the backfill is caught up.
+    async fn backfill(
+        client: SharedClient,
+        sender: &CommittedBlockSender,
+        policy: RetryPolicy,
+        start_block: i64,
+        workers: usize,
+    ) -> Result<i64, Error> {
+        let mut current_block = start_block;
 
-            async move {
-                let messages = client
-                    .lock()
-                    .await
-                    .get_tx_messages(tx.transaction_hash.clone())
-                    .await?;
+        loop {
+            // following the tip from a stale height would skip the backfill, the fetcher fails once retries run out.
+            let latest_height = policy
+                .retry(
+                    "getting the latest block height",
+                    || async { client.latest_block_height().await.map_err(Error::from) },
+                    |_| true,
+                )
+                .await?;
 
-                let mut _tx = tx.clone();
-                _tx.messages.extend(messages);
-                Ok::<response::Transaction, Error>(_tx)
+            // close enough to the tip, let the tip follower take over
+            if latest_height - current_block < workers as i64 {
+                info!(
+                    "backfill caught up | block_number: {}, latest: {}",
+                    current_block, latest_height
+                );
+                return Ok(current_block);
             }
-        });
 
-        let mut transactions: Vec<response::Transaction> = vec![];
-        for result in futures::future::join_all(tx_with_messages).await {
-            match result {
-                Ok(tx) => {
-                    transactions.push(tx);
-                }
-                Err(e) => {
-                    return Err(Error::Other(e.to_string()));
+            let end_block = latest_height.min(current_block + BACKFILL_BATCH_SIZE - 1);
+            info!(
+                "backfill blocks | from: {}, to: {}, workers: {}",
+                current_block, end_block, workers
+            );
+
+            // `buffered` keeps the original order of heights even though requests run concurrently.
+            let mut committed_blocks = stream::iter(current_block..=end_block)
+                .map(|height| {
+                    Self::fetch_with_retry(client.clone(), policy, height, BlockSource::Backfill)
+                })
+                .buffered(workers);
+
+            while let Some(result) = committed_blocks.next().await {
+                match result {
+                    Ok(committed_block) => {
+                        Self::send(sender, committed_block).await?;
+                        current_block += 1;
+                    }
+                    Err(e) if Self::is_block_not_ready(&e) => return Ok(current_block),
+                    Err(e) => return Err(e),
                 }
             }
         }
+    }
+
+    /// fetches a committed block, retrying failures other than the block not being ready yet.
+    pub(crate) async fn fetch_with_retry(
+        client: SharedClient,
+        policy: RetryPolicy,
+        block_height: i64,
+        source: BlockSource,
+    ) -> Result<MsgCommittedBlock, Error> {
+        policy
+            .retry(
+                &format!("fetching block {}", block_height),
+                || Self::committed_block_at(client.clone(), block_height, source),
+                |e| !Self::is_block_not_ready(e),
+            )
+            .await
+    }
+
+    /// forwards a committed block to the committer.
+    pub(crate) async fn send(
+        sender: &CommittedBlockSender,
+        committed_block: MsgCommittedBlock,
+    ) -> Result<(), Error> {
+        info!(
+            "commit block | block_number: {}, hash: {}",
+            committed_block.block.height,
+            committed_block.block.block_hash.clone(),
+        );
+        sender
+            .send(committed_block)
+            .await
+            .map_err(|_| Error::CommittedBlockChannelClosed)
+    }
+
+    /// returns true if the error means the requested height has not been committed yet.
+    fn is_block_not_ready(e: &Error) -> bool {
+        match e {
+            Error::CosmosClientError(err) => match err.as_ref() {
+                cosmos_client::errors::Error::RPCError(tendermint_rpc::Error(
+                    tendermint_rpc::error::ErrorDetail::Response(resp),
+                    _,
+                )) => {
+                    let resp = &resp.source;
+                    resp.code() == tendermint_rpc::Code::InternalError
+                        && (resp.message().contains(BLOCK_NOT_READY_MESSAGE)
+                            || matches!(resp.data(), Some(data) if data.contains(BLOCK_NOT_READY_MESSAGE)))
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// builds the committed block from `/block` and `/block_results` of the height.
+    async fn committed_block_at(
+        client: SharedClient,
+        block_height: i64,
+        source: BlockSource,
+    ) -> Result<MsgCommittedBlock, Error> {
+        let (block, transactions, events) = client.get_block_with_txs(block_height).await?;
+        info!(
+            "found new block | block_number:{}, hash: {}, txs: {}",
+            block.height,
+            block.block_hash.clone(),
+            transactions.len(),
+        );
 
         Ok(MsgCommittedBlock {
-            block: block,
+            block,
             txs: transactions,
             events,
+            source,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tendermint_rpc::response_error::{Code, ResponseError};
+
+    fn rpc_error(code: Code, data: &str) -> Error {
+        cosmos_client::errors::Error::RPCError(tendermint_rpc::Error::response(ResponseError::new(
+            code,
+            Some(data.to_string()),
+        )))
+        .into()
+    }
+
+    #[test]
+    fn block_above_the_tip_is_not_ready() {
+        let e = rpc_error(
+            Code::InternalError,
+            "height 129 must be less than or equal to the current blockchain height 128",
+        );
+        assert!(CommittedBlockFetcher::is_block_not_ready(&e));
+    }
+
+    #[test]
+    fn other_internal_errors_are_failures() {
+        let pruned = rpc_error(
+            Code::InternalError,
+            "height 5 is not available, lowest height is 100",
+        );
+        let invalid = rpc_error(
+            Code::InvalidParams,
+            "height 129 must be less than or equal to the current blockchain height 128",
+        );
+
+        assert!(!CommittedBlockFetcher::is_block_not_ready(&pruned));
+        assert!(!CommittedBlockFetcher::is_block_not_ready(&invalid));
+        assert!(!CommittedBlockFetcher::is_block_not_ready(
+            &Error::UnexpectedError
+        ));
+    }
+}
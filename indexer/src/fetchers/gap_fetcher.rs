@@ -0,0 +1,183 @@
+use crate::errors::Error;
+use crate::fetchers::committed_block_fetcher::{CommittedBlockFetcher, CommittedBlockSender};
+use crate::messages::BlockSource;
+use crate::retry::RetryPolicy;
+use crate::SharedClient;
+
+use cosmscan_models::{async_storage::AsyncStorage, storage::Storage};
+use futures::{stream, StreamExt};
+use log::{info, warn};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// default interval between two gap scans.
+pub const DEFAULT_GAP_SCAN_INTERVAL_SECS: u64 = 600;
+
+/// GapFetcher finds heights missing from the blocks table and fetches them again.
+/// It scans once on startup, and then periodically if an interval is given.
+pub struct GapFetcher<S> {
+    pub client: SharedClient,
+    pub storage: AsyncStorage<S>,
+    pub chain_id: i32,
+    pub from_height: i64,
+    pub workers: usize,
+    pub retry_policy: RetryPolicy,
+    pub scan_interval: Option<Duration>,
+}
+
+impl<S: Storage + Send + Sync + 'static> GapFetcher<S> {
+    /// creates a new GapFetcher instance.
+    pub fn new(
+        client: SharedClient,
+        storage: AsyncStorage<S>,
+        chain_id: i32,
+        from_height: i64,
+        workers: usize,
+        retry_policy: RetryPolicy,
+        scan_interval: Option<Duration>,
+    ) -> Self {
+        GapFetcher {
+            client,
+            storage,
+            chain_id,
+            from_height,
+            workers: workers.max(1),
+            retry_policy,
+            scan_interval,
+        }
+    }
+
+    /// spawns the scanning loop. repaired blocks are sent to the given sender.
+    /// a failed scan is logged and tried again at the next interval.
+    pub fn run_loop(self, sender: CommittedBlockSender) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match self.repair_gaps(&sender).await {
+                    Ok(0) => info!("gap scan finished, no missing blocks"),
+                    Ok(repaired) => info!("gap scan finished | repaired_blocks: {}", repaired),
+                    Err(Error::CommittedBlockChannelClosed) => return,
+                    Err(e) => warn!("gap scan failed: {}", e),
+                }
+
+                match self.scan_interval {
+                    Some(interval) => tokio::time::sleep(interval).await,
+                    None => return,
+                }
+            }
+        })
+    }
+
+    /// fetches every missing height and forwards it to the sender.
+    /// heights recorded as unindexable are skipped. returns the number of repaired blocks.
+    async fn repair_gaps(&self, sender: &CommittedBlockSender) -> Result<usize, Error> {
+        let (chain_id, from_height) = (self.chain_id, self.from_height);
+        let (gaps, unindexable_ranges) = self
+            .storage
+            .run(move |storage| -> Result<_, Error> {
+                Ok((
+                    storage.find_block_gaps(chain_id, from_height)?,
+                    storage.list_unindexable_ranges(chain_id)?,
+                ))
+            })
+            .await?;
+        let unindexable_ranges = unindexable_ranges
+            .iter()
+            .map(|range| (range.start_height, range.end_height))
+            .collect::<Vec<_>>();
+        let mut repaired = 0;
+
+        for gap in gaps {
+            for (start_height, end_height) in
+                indexable_parts((gap.start_height, gap.end_height), &unindexable_ranges)
+            {
+                repaired += self.repair_range(sender, start_height, end_height).await?;
+            }
+        }
+
+        Ok(repaired)
+    }
+
+    /// fetches the heights between the given ones (inclusive) and forwards them to the sender.
+    async fn repair_range(
+        &self,
+        sender: &CommittedBlockSender,
+        start_height: i64,
+        end_height: i64,
+    ) -> Result<usize, Error> {
+        warn!(
+            "found missing blocks | from: {}, to: {}",
+            start_height, end_height
+        );
+        let mut repaired = 0;
+
+        let mut committed_blocks = stream::iter(start_height..=end_height)
+            .map(|height| {
+                CommittedBlockFetcher::fetch_with_retry(
+                    self.client.clone(),
+                    self.retry_policy,
+                    height,
+                    BlockSource::Gap,
+                )
+            })
+            .buffered(self.workers);
+
+        while let Some(result) = committed_blocks.next().await {
+            CommittedBlockFetcher::send(sender, result?).await?;
+            repaired += 1;
+        }
+
+        Ok(repaired)
+    }
+}
+
+/// returns the parts of the gap which aren't covered by the unindexable ranges, in height order.
+fn indexable_parts(gap: (i64, i64), unindexable_ranges: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut parts = vec![gap];
+    for &(start, end) in unindexable_ranges {
+        parts = parts
+            .into_iter()
+            .flat_map(|(from, to)| {
+                // the part doesn't overlap with the range.
+                if to < start || from > end {
+                    return vec![(from, to)];
+                }
+                let mut rest = vec![];
+                if from < start {
+                    rest.push((from, start - 1));
+                }
+                if to > end {
+                    rest.push((end + 1, to));
+                }
+                rest
+            })
+            .collect();
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_the_unindexable_heights_of_a_gap() {
+        assert_eq!(indexable_parts((10, 20), &[]), vec![(10, 20)]);
+        assert_eq!(
+            indexable_parts((10, 20), &[(1, 5), (30, 40)]),
+            vec![(10, 20)]
+        );
+        assert_eq!(
+            indexable_parts((10, 20), &[(15, 15)]),
+            vec![(10, 14), (16, 20)]
+        );
+        assert_eq!(
+            indexable_parts((10, 20), &[(5, 12), (19, 25)]),
+            vec![(13, 18)]
+        );
+        assert_eq!(indexable_parts((10, 20), &[(10, 20)]), vec![]);
+        assert_eq!(
+            indexable_parts((10, 20), &[(12, 12), (14, 14)]),
+            vec![(10, 11), (13, 13), (15, 20)]
+        );
+    }
+}
@@ -1 +1,4 @@
+pub mod account_fetcher;
 pub mod committed_block_fetcher;
+pub mod gap_fetcher;
+pub mod validator_set_fetcher;
@@ -0,0 +1,246 @@
+use crate::errors::Error;
+use crate::messages::MsgValidatorsHash;
+use crate::retry::RetryPolicy;
+use crate::{current_time, SharedClient};
+
+use cosmscan_models::{
+    async_storage::AsyncStorage,
+    errors::Error as DBError,
+    models::{
+        indexer_cursor::{NewIndexerCursor, PIPELINE_BLOCKS, PIPELINE_VALIDATORS},
+        validator_set::{NewValidatorSet, NewValidatorSetMember},
+    },
+    storage::Storage,
+};
+use log::{error, info};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+pub type ValidatorsHashReceiver = mpsc::Receiver<MsgValidatorsHash>;
+
+/// number of stored blocks loaded at once while catching up.
+const CATCH_UP_BATCH_SIZE: i64 = 1000;
+
+/// maximum number of received blocks synced before the cursor is written.
+const SYNC_BATCH_SIZE: usize = 100;
+
+/// ValidatorSetFetcher keeps the history of validator sets.
+/// It fetches the validator set of a committed block whenever its `validators_hash`
+/// differs from the one of the set active at that height.
+pub struct ValidatorSetFetcher<S> {
+    pub client: SharedClient,
+    pub storage: AsyncStorage<S>,
+    pub chain_id: i32,
+    pub retry_policy: RetryPolicy,
+}
+
+impl<S: Storage + Send + Sync + 'static> ValidatorSetFetcher<S> {
+    /// creates a new ValidatorSetFetcher instance.
+    pub fn new(
+        client: SharedClient,
+        storage: AsyncStorage<S>,
+        chain_id: i32,
+        retry_policy: RetryPolicy,
+    ) -> Self {
+        ValidatorSetFetcher {
+            client,
+            storage,
+            chain_id,
+            retry_policy,
+        }
+    }
+
+    /// spawns the syncing loop, it runs until every sender has been dropped.
+    /// blocks committed while the pipeline wasn't running are synced first.
+    ///
+    /// the loop stops at the first height which cannot be synced even after retrying,
+    /// the cursor stays below it and the next start syncs again from there.
+    pub fn run_loop(self, mut receiver: ValidatorsHashReceiver) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            if let Err(e) = self.catch_up().await {
+                error!(
+                    "failed to catch up validator sets, the pipeline is stopped | chain_id: {}, error: {}",
+                    self.chain_id, e
+                );
+                return;
+            }
+
+            while let Some(msg) = receiver.recv().await {
+                let mut msgs = vec![msg];
+                while msgs.len() < SYNC_BATCH_SIZE {
+                    match receiver.try_recv() {
+                        Ok(msg) => msgs.push(msg),
+                        Err(_) => break,
+                    }
+                }
+
+                if let Err(e) = self.sync(&msgs).await {
+                    error!(
+                        "failed to sync validator sets, the pipeline is stopped | chain_id: {}, error: {}",
+                        self.chain_id, e
+                    );
+                    return;
+                }
+            }
+        })
+    }
+
+    /// syncs the blocks in order and moves the cursor to the last one, see [`ValidatorSetFetcher::sync_block`].
+    /// the cursor is written once, or up to the last synced block if one of them fails.
+    /// returns the number of stored sets.
+    pub async fn sync(&self, msgs: &[MsgValidatorsHash]) -> Result<usize, Error> {
+        let mut stored = 0;
+        let mut synced_height = None;
+        for msg in msgs {
+            match self.sync_block(msg).await {
+                Ok(true) => stored += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    if let Some(height) = synced_height {
+                        self.advance_cursor(height).await?;
+                    }
+                    return Err(e);
+                }
+            }
+            synced_height = Some(msg.height);
+        }
+
+        if let Some(height) = synced_height {
+            self.advance_cursor(height).await?;
+        }
+        Ok(stored)
+    }
+
+    /// stores the validator set of the block if it isn't the active one at that height.
+    /// the cursor is moved along with a new set, otherwise it's left to [`ValidatorSetFetcher::sync`].
+    /// returns true if a new set has been stored.
+    async fn sync_block(&self, msg: &MsgValidatorsHash) -> Result<bool, Error> {
+        let (chain_id, height) = (self.chain_id, msg.height);
+        let active = self
+            .storage
+            .run(move |storage| storage.find_validator_set_at_height(chain_id, height))
+            .await;
+        match active {
+            Ok(active) if active.validators_hash == msg.validators_hash => return Ok(false),
+            Ok(_) | Err(DBError::NotFound) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let validators = self
+            .retry_policy
+            .retry(
+                &format!("fetching validators at {}", msg.height),
+                || {
+                    let client = self.client.clone();
+                    async move { client.get_validators(msg.height).await.map_err(Error::from) }
+                },
+                |_| true,
+            )
+            .await?;
+
+        let members = validators
+            .into_iter()
+            .map(|v| NewValidatorSetMember {
+                // assigned by the storage.
+                validator_set_id: 0,
+                address: v.address,
+                pub_key_type: v.pub_key_type,
+                pub_key: v.pub_key,
+                voting_power: v.voting_power,
+                proposer_priority: v.proposer_priority,
+                inserted_at: current_time(),
+            })
+            .collect::<Vec<_>>();
+
+        let validator_set = NewValidatorSet {
+            chain_id: self.chain_id,
+            start_height: msg.height,
+            validators_hash: msg.validators_hash.clone(),
+            inserted_at: current_time(),
+        };
+        let cursor = self.cursor(msg.height);
+        let validators = members.len();
+        self.storage
+            .run(move |storage| {
+                storage.within_transaction(|storage| {
+                    storage.insert_validator_set(&validator_set, &members)?;
+                    storage.advance_indexer_cursor(&cursor)
+                })
+            })
+            .await?;
+        info!(
+            "stored validator set | block_number: {}, validators: {}",
+            msg.height, validators
+        );
+
+        Ok(true)
+    }
+
+    /// syncs the stored blocks between the cursor of this pipeline and the one of the blocks.
+    /// nothing is synced if the pipeline has never run, it starts from the next committed block.
+    async fn catch_up(&self) -> Result<(), Error> {
+        let chain_id = self.chain_id;
+        let cursors = self
+            .storage
+            .run(move |storage| {
+                let validators = storage.find_indexer_cursor(chain_id, PIPELINE_VALIDATORS)?;
+                let blocks = storage.find_indexer_cursor(chain_id, PIPELINE_BLOCKS)?;
+                Ok((validators, blocks))
+            })
+            .await;
+        let (mut height, to_height) = match cursors {
+            Ok((validators, blocks)) => (validators.height + 1, blocks.height),
+            Err(DBError::NotFound) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        if height > to_height {
+            return Ok(());
+        }
+
+        info!(
+            "catching up validator sets | from: {}, to: {}",
+            height, to_height
+        );
+        while height <= to_height {
+            let blocks = self
+                .storage
+                .run(move |storage| {
+                    storage.list_blocks_in_range(chain_id, height, to_height, CATCH_UP_BATCH_SIZE)
+                })
+                .await?;
+            let last_height = match blocks.last() {
+                Some(block) => block.height,
+                None => break,
+            };
+
+            let msgs = blocks
+                .into_iter()
+                .map(|block| MsgValidatorsHash {
+                    height: block.height,
+                    validators_hash: block.validators_hash,
+                })
+                .collect::<Vec<_>>();
+            self.sync(&msgs).await?;
+            height = last_height + 1;
+        }
+
+        Ok(())
+    }
+
+    async fn advance_cursor(&self, height: i64) -> Result<(), Error> {
+        let cursor = self.cursor(height);
+        self.storage
+            .run(move |storage| storage.advance_indexer_cursor(&cursor))
+            .await?;
+        Ok(())
+    }
+
+    fn cursor(&self, height: i64) -> NewIndexerCursor {
+        NewIndexerCursor {
+            chain_id: self.chain_id,
+            pipeline: PIPELINE_VALIDATORS.to_string(),
+            height,
+            inserted_at: current_time(),
+        }
+    }
+}
@@ -1,122 +1,163 @@
-use std::sync::Arc;
-
-use crate::committer::Committer;
-use crate::fetchers::committed_block_fetcher::CommittedBlockFetcher;
-use crate::messages::MsgCommittedBlock;
+use crate::chain_indexer::ChainIndexer;
+use crate::retry::RetryPolicy;
 use crate::{config::Config, errors::Error};
-use crate::{current_time, SharedClient};
 
-use cosmscan_models::models::chain::{Chain, NewChain};
-use cosmscan_models::storage::StorageReader;
 use cosmscan_models::{
-    db::BackendDB,
-    storage::{PersistenceStorage, StorageWriter},
+    async_storage::AsyncStorage,
+    db::{BackendDB, Database},
+    storage::{PersistenceStorage, Storage},
 };
+#[cfg(feature = "sqlite")]
+use cosmscan_models::{db::SqliteDB, sqlite_storage::SqliteStorage};
 
+use futures::stream::{FuturesUnordered, StreamExt};
 use log::error;
-use tokio::sync::{mpsc, Mutex};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 /// Indexer is for fetching ABCI blocks, transactions and logs.
-pub struct Indexer<T: StorageWriter + StorageReader> {
+/// It runs a [`ChainIndexer`] for each configured chain, all sharing the same storage.
+pub struct Indexer<S> {
     pub config: Config,
-    pub storage: T,
-    pub client: SharedClient,
+    pub storage: Arc<S>,
+    pub chains: Vec<ChainIndexer<S>>,
+    shutdown: CancellationToken,
 }
 
 impl Indexer<PersistenceStorage<BackendDB>> {
+    /// creates an indexer which stores the chains in the configured database.
     pub async fn new(config: Config) -> Result<Self, Error> {
-        let db = BackendDB::new(config.db.clone());
-        let storage = PersistenceStorage::new(db);
+        // connect once, every chain pipeline shares the pool.
+        let mut db = BackendDB::new(config.db.clone());
+        db.connect();
 
-        // create a shared cosmos client
-        let client_config = cosmos_client::client::ClientConfig {
-            tendermint_rpc_endpoint: config.fetcher.tendermint_rpc_endpoint.clone(),
-            grpc_endpoint: config.fetcher.grpc_endpoint.clone(),
-            rest_api_endpoint: config.fetcher.rest_api_endpoint.clone(),
-        };
+        Self::with_storage(config, Arc::new(PersistenceStorage::new(db))).await
+    }
+}
 
-        let client = cosmos_client::client::Client::new(client_config)
-            .await
-            .map(Mutex::new)
-            .map(Arc::new)?;
+#[cfg(feature = "sqlite")]
+impl Indexer<SqliteStorage<SqliteDB>> {
+    /// creates an indexer which stores the chains in the configured sqlite database file.
+    pub async fn new_sqlite(config: Config) -> Result<Self, Error> {
+        let mut db = SqliteDB::new(config.db.clone());
+        db.connect();
+
+        Self::with_storage(config, Arc::new(SqliteStorage::new(db))).await
+    }
+}
+
+impl<S: Storage + Send + Sync + 'static> Indexer<S> {
+    /// creates an indexer on top of the given storage, e.g. a [`MemoryStorage`] in tests.
+    ///
+    /// [`MemoryStorage`]: cosmscan_models::memory_storage::MemoryStorage
+    pub async fn with_storage(config: Config, storage: Arc<S>) -> Result<Self, Error> {
+        let sections = config.chain_sections().map_err(Error::InvalidConfig)?;
+        let retry_policy = config
+            .retry
+            .as_ref()
+            .map(RetryPolicy::from)
+            .unwrap_or_default();
+
+        // storage operations of every chain share the connection pool.
+        let async_storage =
+            AsyncStorage::from_shared(storage.clone(), config.db.max_connections() as usize);
+        let shutdown = CancellationToken::new();
+        let mut chains = vec![];
+        for section in sections {
+            let chain_indexer = ChainIndexer::new(
+                section,
+                config.indexer.clone(),
+                retry_policy,
+                async_storage.clone(),
+                shutdown.child_token(),
+            )
+            .await?;
+            chains.push(chain_indexer);
+        }
 
         Ok(Indexer {
             config,
             storage,
-            client,
+            chains,
+            shutdown,
         })
     }
 
+    /// returns the token which shuts every chain pipeline down once it's cancelled.
+    /// blocks which have been fetched already are committed before [`Indexer::start`] returns.
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    /// runs every chain pipeline concurrently.
+    /// once one of them fails, the others are shut down and commit the blocks they have fetched,
+    /// then the first error is returned.
     pub async fn start(&self) -> Result<(), Error> {
-        let (committed_block_s, mut committed_block_r) = mpsc::channel::<MsgCommittedBlock>(100);
-
-        // get chain info from database, if it doesn't exists, create a new one.
-        let chain = self.load_chain_or_store()?;
-        let start_block = match self.load_latest_block_height(&chain) {
-            Some(height) => height + 1,
-            None => self.config.fetcher.start_block,
-        };
-
-        // run fetcher
-        let committed_block_fetcher = CommittedBlockFetcher::new(self.client.clone()).await?;
-        committed_block_fetcher.run_loop(committed_block_s, start_block);
-
-        // create a committer and run it
-        let db_config = self.config.db.clone();
-        let committer = Committer::new(db_config, chain);
-
-        loop {
-            tokio::select! {
-                Some(val) = committed_block_r.recv() => {
-                    committer.commit_block(val).unwrap();
+        let mut pipelines = self
+            .chains
+            .iter()
+            .map(|chain| async move { (chain.chain_id(), chain.start().await) })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut first_error = None;
+        while let Some((chain_id, result)) = pipelines.next().await {
+            if let Err(e) = result {
+                error!(
+                    "chain pipeline failed | chain_id: {}, error: {}",
+                    chain_id, e
+                );
+                if first_error.is_none() {
+                    self.shutdown.cancel();
+                    first_error = Some(e);
                 }
             }
         }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
     }
 
-    // load chain info from storage
-    // if it doesn't exists we'll create a new chain in the storage.
-    pub fn load_chain_or_store(&self) -> Result<Chain, Error> {
-        match self
-            .storage
-            .find_by_chain_id(self.config.chain.chain_id.clone())
-        {
-            Ok(chain) => {
-                return Ok(chain);
-            }
-            Err(e) => {
-                // match with not found error
-                match e {
-                    cosmscan_models::errors::Error::NotFound => {
-                        // create a new chain info
-                        let new_chain_id = self.storage.insert_chain(&NewChain {
-                            chain_id: self.config.chain.chain_id.clone(),
-                            chain_name: self.config.chain.chain_name.clone(),
-                            inserted_at: current_time(),
-                        })?;
-                        return Ok(Chain {
-                            id: new_chain_id as i32,
-                            chain_id: self.config.chain.chain_id.clone(),
-                            chain_name: self.config.chain.chain_name.clone(),
-                            icon_url: None,
-                            webisite: None,
-                            inserted_at: current_time(),
-                            updated_at: None,
-                        });
-                    }
-                    _ => {
-                        error!("unexpected error: {:?}", e);
-                        return Err(Error::DBError(e));
-                    }
-                }
-            }
-        };
+    /// re-indexes the blocks between the given heights (inclusive) of a chain.
+    /// the chain can be omitted if only one chain is configured.
+    pub async fn reindex(
+        &self,
+        chain_id: Option<String>,
+        from_height: i64,
+        to_height: i64,
+    ) -> Result<(), Error> {
+        self.find_chain(chain_id)?
+            .reindex(from_height, to_height)
+            .await
+    }
+
+    /// moves the cursor of a derived pipeline of a chain, see [`ChainIndexer::reset_cursor`].
+    /// the chain can be omitted if only one chain is configured.
+    pub async fn reset_cursor(
+        &self,
+        chain_id: Option<String>,
+        pipeline: &str,
+        height: i64,
+    ) -> Result<(), Error> {
+        self.find_chain(chain_id)?
+            .reset_cursor(pipeline, height)
+            .await
     }
 
-    pub fn load_latest_block_height(&self, chain: &Chain) -> Option<i64> {
-        match self.storage.latest_block_height(chain.id) {
-            Ok(height) => Some(height),
-            Err(_) => None,
+    fn find_chain(&self, chain_id: Option<String>) -> Result<&ChainIndexer<S>, Error> {
+        match chain_id {
+            Some(chain_id) => self
+                .chains
+                .iter()
+                .find(|chain| chain.chain_id() == chain_id)
+                .ok_or_else(|| {
+                    Error::InvalidConfig(format!("chain {} is not configured", chain_id))
+                }),
+            None if self.chains.len() == 1 => Ok(&self.chains[0]),
+            None => Err(Error::InvalidConfig(
+                "chain id must be given when several chains are configured".to_string(),
+            )),
         }
     }
 }
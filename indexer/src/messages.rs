@@ -7,4 +7,59 @@ pub struct MsgCommittedBlock {
     pub block: response::Block,
     pub txs: Vec<response::Transaction>,
     pub events: Vec<response::Event>,
+    pub source: BlockSource,
+}
+
+/// BlockSource tells how a committed block has been fetched.
+/// only blocks at the tip or of the backfill can roll the stored ones back when they don't link to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockSource {
+    /// fetched concurrently while catching up with the chain.
+    Backfill,
+    /// fetched while following the tip of the chain.
+    Tip,
+    /// fetched to repair a height missing from the blocks table.
+    Gap,
+    /// fetched by the reindex command.
+    Reindex,
+}
+
+impl MsgCommittedBlock {
+    /// returns the approximate number of bytes stored for the block.
+    /// only variable length fields are counted, it's meant for sizing commit batches.
+    pub fn size_hint(&self) -> usize {
+        let block = &self.block;
+        let mut size = block.block_hash.len()
+            + block.prev_hash.len()
+            + block.proposer_address.len()
+            + block.last_commit_hash.len()
+            + block.data_hash.len()
+            + block.validators_hash.len()
+            + block.next_validators_hash.len()
+            + block.consensus_hash.len()
+            + block.app_hash.len()
+            + block.last_result_hash.len()
+            + block.evidence_hash.len();
+
+        for tx in self.txs.iter() {
+            size += tx.transaction_hash.len()
+                + tx.tx_data.len()
+                + tx.raw_log.len()
+                + tx.info.len()
+                + tx.messages.iter().map(|m| m.len()).sum::<usize>();
+        }
+        for event in self.events.iter() {
+            size += event.event_type.len() + event.event_key.len() + event.event_value.len();
+        }
+
+        size
+    }
+}
+
+/// MsgValidatorsHash carries the `validators_hash` of a committed block.
+/// It's intended to be sent to the receiver of [`ValidatorSetFetcher`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MsgValidatorsHash {
+    pub height: i64,
+    pub validators_hash: String,
 }
@@ -0,0 +1,96 @@
+use std::{future::Future, time::Duration};
+
+use log::{error, warn};
+use serde::Deserialize;
+
+use crate::errors::Error;
+
+/// RetryConfig is the `[retry]` section of the indexer config.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RetryConfig {
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts: 10,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+/// RetryPolicy retries a failed operation with exponential backoff.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl From<&RetryConfig> for RetryPolicy {
+    fn from(config: &RetryConfig) -> Self {
+        RetryPolicy {
+            max_attempts: config.max_attempts.max(1),
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            max_backoff: Duration::from_millis(config.max_backoff_ms),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::from(&RetryConfig::default())
+    }
+}
+
+impl RetryPolicy {
+    /// returns the delay before the given retry attempt, starting from 1.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// runs the operation until it succeeds, fails with an error that isn't retriable,
+    /// or `max_attempts` is reached. the last error is returned in the latter cases.
+    pub async fn retry<T, F, Fut, R>(
+        &self,
+        operation: &str,
+        mut f: F,
+        is_retriable: R,
+    ) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+        R: Fn(&Error) -> bool,
+    {
+        let mut attempt = 1;
+        loop {
+            match f().await {
+                Ok(val) => return Ok(val),
+                Err(e) if !is_retriable(&e) => return Err(e),
+                Err(e) if attempt >= self.max_attempts => {
+                    error!(
+                        "{} failed, giving up | attempts: {}, error: {}",
+                        operation, attempt, e
+                    );
+                    return Err(e);
+                }
+                Err(e) => {
+                    let backoff = self.backoff(attempt);
+                    warn!(
+                        "{} failed, retrying | attempt: {}/{}, backoff: {:?}, error: {}",
+                        operation, attempt, self.max_attempts, backoff, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,9 @@
+-- balances which don't fit in BIGINT are dropped, they are refetched once the migration is applied again --
+DELETE FROM account_balance WHERE amount !~ '^-?[0-9]{1,18}$';
+ALTER TABLE account_balance ALTER COLUMN amount TYPE BIGINT USING amount::BIGINT;
+
+-- denom is kept at VARCHAR(256), shortening it would fail on the stored ibc denoms --
+ALTER TABLE account_balance DROP CONSTRAINT IF EXISTS uq_account_balance_account_id_denom;
+
+ALTER TABLE accounts DROP CONSTRAINT IF EXISTS uq_accounts_chain_id_address;
+ALTER TABLE accounts ADD CONSTRAINT accounts_address_key UNIQUE (address);
@@ -0,0 +1,10 @@
+-- accounts are unique per chain, not globally --
+ALTER TABLE accounts DROP CONSTRAINT IF EXISTS accounts_address_key;
+ALTER TABLE accounts ADD CONSTRAINT uq_accounts_chain_id_address UNIQUE (chain_id, address);
+
+-- a balance row per denom, ibc denoms are longer than 20 characters --
+ALTER TABLE account_balance ALTER COLUMN denom TYPE VARCHAR(256);
+ALTER TABLE account_balance ADD CONSTRAINT uq_account_balance_account_id_denom UNIQUE (account_id, denom);
+
+-- amounts are arbitrary precision integers, they don't fit in BIGINT --
+ALTER TABLE account_balance ALTER COLUMN amount TYPE TEXT USING amount::TEXT;
@@ -0,0 +1,2 @@
+DROP TABLE validator_set_members;
+DROP TABLE validator_sets;
@@ -0,0 +1,25 @@
+-- validator set which becomes active at start_height, a new row is stored whenever validators_hash changes --
+CREATE TABLE validator_sets (
+    id SERIAL PRIMARY KEY,
+    chain_id INT NOT NULL,
+    start_height BIGINT NOT NULL,
+    validators_hash VARCHAR(256) NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT uq_validator_sets_chain_id_start_height UNIQUE (chain_id, start_height)
+);
+
+CREATE TABLE validator_set_members (
+    id SERIAL PRIMARY KEY,
+    validator_set_id INT NOT NULL,
+    address VARCHAR(256) NOT NULL,
+    pub_key_type VARCHAR(256) NOT NULL,
+    pub_key VARCHAR(256) NOT NULL,
+    voting_power BIGINT NOT NULL,
+    proposer_priority BIGINT NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT fk_validator_set_id FOREIGN KEY (validator_set_id) REFERENCES validator_sets(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_validator_set_members_validator_set_id ON validator_set_members(validator_set_id);
@@ -0,0 +1,7 @@
+DROP TABLE transaction_signers;
+
+ALTER TABLE transactions DROP COLUMN fee_granter;
+ALTER TABLE transactions DROP COLUMN fee_payer;
+ALTER TABLE transactions DROP COLUMN gas_limit;
+ALTER TABLE transactions DROP COLUMN fee_denom;
+ALTER TABLE transactions DROP COLUMN fee_amount;
@@ -0,0 +1,22 @@
+-- fee and gas limit from the auth info, fee_amount/fee_denom hold the first coin of the fee --
+ALTER TABLE transactions ADD COLUMN fee_amount VARCHAR(256);
+ALTER TABLE transactions ADD COLUMN fee_denom VARCHAR(256);
+ALTER TABLE transactions ADD COLUMN gas_limit BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE transactions ADD COLUMN fee_payer VARCHAR(256);
+ALTER TABLE transactions ADD COLUMN fee_granter VARCHAR(256);
+
+-- transaction signers, in the order of the auth info --
+CREATE TABLE IF NOT EXISTS transaction_signers (
+    id SERIAL PRIMARY KEY,
+    transaction_id INT NOT NULL,
+    signer_seq INT NOT NULL,
+    pub_key_type VARCHAR(256),
+    pub_key TEXT,
+    sequence BIGINT NOT NULL,
+    sign_mode VARCHAR(256) NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT fk_transaction_id FOREIGN KEY (transaction_id) REFERENCES transactions(id)
+);
+
+CREATE INDEX idx_transaction_signers_transaction_id ON transaction_signers(transaction_id);
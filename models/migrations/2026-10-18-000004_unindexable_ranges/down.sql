@@ -0,0 +1 @@
+DROP TABLE unindexable_ranges;
@@ -0,0 +1,11 @@
+-- heights which cannot be indexed, e.g. pruned by the node before the indexer reached them --
+CREATE TABLE unindexable_ranges (
+    id SERIAL PRIMARY KEY,
+    chain_id INT NOT NULL,
+    start_height BIGINT NOT NULL,
+    end_height BIGINT NOT NULL,
+    reason VARCHAR(256) NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT uq_unindexable_ranges_chain_id_start_height UNIQUE (chain_id, start_height)
+);
@@ -0,0 +1,10 @@
+-- last height processed by each pipeline of a chain, e.g. "blocks" or "validators" --
+CREATE TABLE indexer_cursors (
+    id SERIAL PRIMARY KEY,
+    chain_id INT NOT NULL,
+    pipeline VARCHAR(64) NOT NULL,
+    height BIGINT NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT uq_indexer_cursors_chain_id_pipeline UNIQUE (chain_id, pipeline)
+);
@@ -0,0 +1,10 @@
+DROP INDEX IF EXISTS uq_events_attribute;
+ALTER TABLE events DROP COLUMN attr_seq;
+
+ALTER TABLE transaction_signers DROP CONSTRAINT IF EXISTS uq_transaction_signers_transaction_id_signer_seq;
+ALTER TABLE messages DROP CONSTRAINT IF EXISTS uq_messages_transaction_id_seq;
+
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS uq_transactions_chain_id_transaction_hash;
+ALTER TABLE transactions ADD CONSTRAINT transactions_transaction_hash_key UNIQUE (transaction_hash);
+
+ALTER TABLE blocks DROP CONSTRAINT IF EXISTS uq_blocks_chain_id_height;
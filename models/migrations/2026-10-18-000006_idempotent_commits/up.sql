@@ -0,0 +1,55 @@
+-- heights committed more than once, their rows are deduplicated below --
+CREATE TEMPORARY TABLE duplicated_heights AS
+    SELECT chain_id, height, COUNT(*) AS copies FROM blocks GROUP BY chain_id, height HAVING COUNT(*) > 1;
+
+-- a block per height, the first stored one is kept --
+DELETE FROM blocks a USING blocks b WHERE a.chain_id = b.chain_id AND a.height = b.height AND a.id > b.id;
+ALTER TABLE blocks ADD CONSTRAINT uq_blocks_chain_id_height UNIQUE (chain_id, height);
+
+-- transaction hashes are unique per chain --
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transactions_transaction_hash_key;
+ALTER TABLE transactions ADD CONSTRAINT uq_transactions_chain_id_transaction_hash UNIQUE (chain_id, transaction_hash);
+
+DELETE FROM messages a USING messages b WHERE a.transaction_id = b.transaction_id AND a.seq = b.seq AND a.id > b.id;
+ALTER TABLE messages ADD CONSTRAINT uq_messages_transaction_id_seq UNIQUE (transaction_id, seq);
+
+DELETE FROM transaction_signers a USING transaction_signers b
+    WHERE a.transaction_id = b.transaction_id AND a.signer_seq = b.signer_seq AND a.id > b.id;
+ALTER TABLE transaction_signers ADD CONSTRAINT uq_transaction_signers_transaction_id_signer_seq UNIQUE (transaction_id, signer_seq);
+
+-- events of a height committed N times are stored N times, only the first copy is kept --
+DELETE FROM events WHERE id IN (
+    SELECT id FROM (
+        SELECT events.id, duplicated_heights.copies,
+            ROW_NUMBER() OVER (
+                PARTITION BY events.chain_id, events.block_height, events.tx_type, events.tx_hash, events.event_seq,
+                    events.event_type, events.event_key, events.event_value
+                ORDER BY events.id
+            ) AS position,
+            COUNT(*) OVER (
+                PARTITION BY events.chain_id, events.block_height, events.tx_type, events.tx_hash, events.event_seq,
+                    events.event_type, events.event_key, events.event_value
+            ) AS total
+        FROM events
+        JOIN duplicated_heights
+            ON duplicated_heights.chain_id = events.chain_id AND duplicated_heights.height = events.block_height
+    ) AS numbered
+    WHERE position > total / copies
+);
+
+-- position of the attribute in its event, keys may be repeated in an event --
+ALTER TABLE events ADD COLUMN attr_seq INT NOT NULL DEFAULT 0;
+UPDATE events SET attr_seq = numbered.attr_seq
+FROM (
+    SELECT id, ROW_NUMBER() OVER (
+        PARTITION BY chain_id, block_height, tx_type, tx_hash, event_seq ORDER BY id
+    ) - 1 AS attr_seq
+    FROM events
+) AS numbered
+WHERE events.id = numbered.id;
+
+-- begin/end block events have no tx_hash --
+CREATE UNIQUE INDEX uq_events_attribute
+    ON events (chain_id, block_height, tx_type, COALESCE(tx_hash, ''), event_seq, attr_seq);
+
+DROP TABLE duplicated_heights;
@@ -0,0 +1,7 @@
+ALTER TABLE transactions ADD COLUMN fee_amount VARCHAR(256);
+ALTER TABLE transactions ADD COLUMN fee_denom VARCHAR(256);
+
+UPDATE transactions SET fee_amount = f.amount, fee_denom = f.denom
+    FROM transaction_fees f WHERE f.transaction_id = transactions.id AND f.seq = 0;
+
+DROP TABLE transaction_fees;
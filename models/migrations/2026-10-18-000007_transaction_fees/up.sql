@@ -0,0 +1,20 @@
+-- every coin of the fee, in the order of the auth info --
+CREATE TABLE IF NOT EXISTS transaction_fees (
+    id SERIAL PRIMARY KEY,
+    transaction_id INT NOT NULL,
+    seq INT NOT NULL,
+    denom VARCHAR(256) NOT NULL,
+    amount TEXT NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT fk_transaction_id FOREIGN KEY (transaction_id) REFERENCES transactions(id),
+    CONSTRAINT uq_transaction_fees_transaction_id_seq UNIQUE (transaction_id, seq)
+);
+
+-- only the first coin has been stored so far, a reindex fetches the others --
+INSERT INTO transaction_fees (transaction_id, seq, denom, amount, inserted_at)
+    SELECT id, 0, fee_denom, fee_amount, inserted_at FROM transactions
+    WHERE fee_denom IS NOT NULL AND fee_amount IS NOT NULL;
+
+ALTER TABLE transactions DROP COLUMN fee_denom;
+ALTER TABLE transactions DROP COLUMN fee_amount;
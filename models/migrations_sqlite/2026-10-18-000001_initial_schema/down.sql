@@ -0,0 +1,13 @@
+DROP TABLE IF EXISTS indexer_cursors;
+DROP TABLE IF EXISTS unindexable_ranges;
+DROP TABLE IF EXISTS validator_set_members;
+DROP TABLE IF EXISTS validator_sets;
+DROP TABLE IF EXISTS account_balance;
+DROP TABLE IF EXISTS accounts;
+DROP TABLE IF EXISTS events;
+DROP TABLE IF EXISTS transaction_fees;
+DROP TABLE IF EXISTS transaction_signers;
+DROP TABLE IF EXISTS messages;
+DROP TABLE IF EXISTS transactions;
+DROP TABLE IF EXISTS blocks;
+DROP TABLE IF EXISTS chains;
@@ -0,0 +1,200 @@
+-- the schema of every postgres migration in a single step, ids are never reused like postgres serials --
+-- timestamps are stored as text, `messages.rawdata` is JSON text instead of JSONB --
+
+-- chains --
+CREATE TABLE chains (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_id VARCHAR(32) UNIQUE NOT NULL,
+    chain_name VARCHAR(128) NOT NULL,
+    icon_url VARCHAR(256) NULL,
+    website VARCHAR(256) NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP
+);
+
+-- blocks --
+CREATE TABLE blocks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_id INT NOT NULL,
+    height BIGINT NOT NULL,
+    block_hash VARCHAR(256) NOT NULL,
+    prev_hash VARCHAR(256) NOT NULL,
+    proposer_address VARCHAR(256) NOT NULL,
+    last_commit_hash VARCHAR(256) NOT NULL,
+    data_hash VARCHAR(256) NOT NULL,
+    validators_hash VARCHAR(256) NOT NULL,
+    next_validators_hash VARCHAR(256) NOT NULL,
+    consensus_hash VARCHAR(256) NOT NULL,
+    app_hash VARCHAR(256) NOT NULL,
+    last_result_hash VARCHAR(256) NOT NULL,
+    evidence_hash VARCHAR(256) NOT NULL,
+    block_time TIMESTAMP NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT uq_blocks_chain_id_height UNIQUE (chain_id, height)
+);
+
+CREATE INDEX idx_blocks_chain_id ON blocks(chain_id);
+CREATE INDEX idx_blocks_height ON blocks(height);
+CREATE INDEX idx_blocks_block_hash ON blocks(block_hash);
+
+-- transactions --
+CREATE TABLE transactions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_id INT NOT NULL,
+    transaction_hash VARCHAR(256) NOT NULL,
+    height BIGINT NOT NULL,
+    code INT NOT NULL,
+    code_space VARCHAR(256) NOT NULL,
+    tx_data TEXT NOT NULL,
+    raw_log TEXT NOT NULL,
+    info TEXT NOT NULL,
+    memo VARCHAR(1024),
+    gas_wanted BIGINT NOT NULL,
+    gas_used BIGINT NOT NULL,
+    tx_timestamp VARCHAR(256) NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    gas_limit BIGINT NOT NULL DEFAULT 0,
+    fee_payer VARCHAR(256),
+    fee_granter VARCHAR(256),
+    CONSTRAINT uq_transactions_chain_id_transaction_hash UNIQUE (chain_id, transaction_hash)
+);
+
+CREATE INDEX idx_transactions_chain_id ON transactions(chain_id);
+CREATE INDEX idx_transactions_tx_hash ON transactions(transaction_hash);
+
+-- messages --
+CREATE TABLE messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_id INT NOT NULL,
+    seq INT NOT NULL,
+    rawdata TEXT NOT NULL CHECK (json_valid(rawdata)),
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT fk_transaction_id FOREIGN KEY (transaction_id) REFERENCES transactions(id),
+    CONSTRAINT uq_messages_transaction_id_seq UNIQUE (transaction_id, seq)
+);
+
+-- transaction signers --
+CREATE TABLE transaction_signers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_id INT NOT NULL,
+    signer_seq INT NOT NULL,
+    pub_key_type VARCHAR(256),
+    pub_key TEXT,
+    sequence BIGINT NOT NULL,
+    sign_mode VARCHAR(256) NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT fk_transaction_id FOREIGN KEY (transaction_id) REFERENCES transactions(id),
+    CONSTRAINT uq_transaction_signers_transaction_id_signer_seq UNIQUE (transaction_id, signer_seq)
+);
+
+CREATE INDEX idx_transaction_signers_transaction_id ON transaction_signers(transaction_id);
+
+-- transaction fees, a row per coin --
+CREATE TABLE transaction_fees (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    transaction_id INT NOT NULL,
+    seq INT NOT NULL,
+    denom VARCHAR(256) NOT NULL,
+    amount TEXT NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT fk_transaction_id FOREIGN KEY (transaction_id) REFERENCES transactions(id),
+    CONSTRAINT uq_transaction_fees_transaction_id_seq UNIQUE (transaction_id, seq)
+);
+
+-- events --
+CREATE TABLE events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_id INT NOT NULL,
+    tx_type SMALLINT NOT NULL,
+    tx_hash VARCHAR(256),
+    block_height BIGINT NOT NULL,
+    event_seq INT NOT NULL,
+    event_type VARCHAR(256) NOT NULL,
+    event_key VARCHAR(256) NOT NULL,
+    event_value VARCHAR(256) NOT NULL,
+    indexed BOOLEAN NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    attr_seq INT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_events_chain_id ON events(chain_id);
+CREATE INDEX idx_events_tx_hash ON events(tx_hash);
+CREATE UNIQUE INDEX uq_events_attribute ON events (chain_id, block_height, tx_type, COALESCE(tx_hash, ''), event_seq, attr_seq);
+
+-- accounts --
+CREATE TABLE accounts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_id INT NOT NULL,
+    address VARCHAR(256) NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT uq_accounts_chain_id_address UNIQUE (chain_id, address)
+);
+
+CREATE INDEX idx_accounts_chain_id ON accounts(chain_id);
+
+CREATE TABLE account_balance (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account_id INT NOT NULL,
+    amount TEXT NOT NULL,
+    denom VARCHAR(256) NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT fk_account_id FOREIGN KEY (account_id) REFERENCES accounts(id),
+    CONSTRAINT uq_account_balance_account_id_denom UNIQUE (account_id, denom)
+);
+
+-- validator sets --
+CREATE TABLE validator_sets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_id INT NOT NULL,
+    start_height BIGINT NOT NULL,
+    validators_hash VARCHAR(256) NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT uq_validator_sets_chain_id_start_height UNIQUE (chain_id, start_height)
+);
+
+CREATE TABLE validator_set_members (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    validator_set_id INT NOT NULL,
+    address VARCHAR(256) NOT NULL,
+    pub_key_type VARCHAR(256) NOT NULL,
+    pub_key VARCHAR(256) NOT NULL,
+    voting_power BIGINT NOT NULL,
+    proposer_priority BIGINT NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT fk_validator_set_id FOREIGN KEY (validator_set_id) REFERENCES validator_sets(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_validator_set_members_validator_set_id ON validator_set_members(validator_set_id);
+
+-- unindexable ranges --
+CREATE TABLE unindexable_ranges (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_id INT NOT NULL,
+    start_height BIGINT NOT NULL,
+    end_height BIGINT NOT NULL,
+    reason VARCHAR(256) NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT uq_unindexable_ranges_chain_id_start_height UNIQUE (chain_id, start_height)
+);
+
+-- indexer cursors --
+CREATE TABLE indexer_cursors (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_id INT NOT NULL,
+    pipeline VARCHAR(64) NOT NULL,
+    height BIGINT NOT NULL,
+    inserted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP,
+    CONSTRAINT uq_indexer_cursors_chain_id_pipeline UNIQUE (chain_id, pipeline)
+);
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+
+use crate::errors::Error;
+
+/// AsyncStorage runs the blocking operations of a storage on the blocking thread pool of tokio,
+/// so that async tasks don't stall the runtime while waiting for the database.
+/// the operations are the ones of [`StorageReader`](crate::storage::StorageReader) and
+/// [`StorageWriter`](crate::storage::StorageWriter), called on the wrapped storage.
+pub struct AsyncStorage<S> {
+    storage: Arc<S>,
+    limiter: Arc<Semaphore>,
+}
+
+impl<S> Clone for AsyncStorage<S> {
+    fn clone(&self) -> Self {
+        Self {
+            storage: self.storage.clone(),
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+impl<S: Send + Sync + 'static> AsyncStorage<S> {
+    /// wraps the storage, at most `max_concurrency` operations run at once.
+    /// clones share the limit, it's usually the size of the connection pool.
+    pub fn new(storage: S, max_concurrency: usize) -> Self {
+        Self::from_shared(Arc::new(storage), max_concurrency)
+    }
+
+    /// same as [`AsyncStorage::new`], for a storage which is used elsewhere as well.
+    pub fn from_shared(storage: Arc<S>, max_concurrency: usize) -> Self {
+        Self {
+            storage,
+            limiter: Arc::new(Semaphore::new(max_concurrency.max(1))),
+        }
+    }
+
+    /// runs `f` on a blocking thread, waiting for a free slot first.
+    pub async fn run<R, E, F>(&self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&S) -> Result<R, E> + Send + 'static,
+        R: Send + 'static,
+        E: From<Error> + Send + 'static,
+    {
+        let _permit = self
+            .limiter
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| Error::TaskFailed(e.to_string()))?;
+
+        let storage = self.storage.clone();
+        tokio::task::spawn_blocking(move || f(&storage))
+            .await
+            .map_err(|e| Error::TaskFailed(e.to_string()))?
+    }
+}
@@ -1,10 +1,42 @@
 use serde::Deserialize;
 
+/// default size of the connection pool, the same as the default of r2d2.
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 10;
+
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct DBConfig {
+    /// `postgres` (default) or `sqlite`.
+    pub driver: Option<DBDriver>,
+    /// the connection fields are unused by sqlite.
+    #[serde(default)]
     pub host: String,
+    #[serde(default)]
     pub port: u32,
+    #[serde(default)]
     pub user: String,
+    #[serde(default)]
     pub password: String,
+    /// name of the database, or path of the database file for sqlite.
     pub database: String,
+    /// size of the connection pool.
+    pub max_connections: Option<u32>,
+}
+
+/// DBDriver is the kind of database the data is stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DBDriver {
+    #[default]
+    Postgres,
+    Sqlite,
+}
+
+impl DBConfig {
+    pub fn driver(&self) -> DBDriver {
+        self.driver.unwrap_or_default()
+    }
+
+    pub fn max_connections(&self) -> u32 {
+        self.max_connections.unwrap_or(DEFAULT_MAX_CONNECTIONS)
+    }
 }
@@ -1,13 +1,26 @@
+use std::ops::Deref;
+
+#[cfg(feature = "sqlite")]
+use diesel::{connection::SimpleConnection, r2d2::CustomizeConnection, SqliteConnection};
 use diesel::{r2d2::ConnectionManager, PgConnection};
 use r2d2::{Pool, PooledConnection};
 
-use crate::config::DBConfig;
+use crate::{config::DBConfig, errors::Error};
+
+/// milliseconds a sqlite connection waits for the lock of another writer before failing.
+#[cfg(feature = "sqlite")]
+const SQLITE_BUSY_TIMEOUT_MS: u32 = 5000;
+
+/// Database hands out connections of type `C`, postgres unless another one is given.
+pub trait Database<C = PgConnection> {
+    type Connection: Deref<Target = C>;
 
-pub trait Database {
     fn connect(&mut self) -> bool;
-    fn conn(&self) -> Option<PooledConnection<ConnectionManager<PgConnection>>>;
+    fn conn(&self) -> Result<Self::Connection, Error>;
 }
 
+/// BackendDB holds a connection pool, cloning it shares the pool.
+#[derive(Clone)]
 pub struct BackendDB {
     pub config: DBConfig,
     pub client: Option<Pool<ConnectionManager<PgConnection>>>,
@@ -23,6 +36,8 @@ impl BackendDB {
 }
 
 impl Database for BackendDB {
+    type Connection = PooledConnection<ConnectionManager<PgConnection>>;
+
     fn connect(&mut self) -> bool {
         let db_url = format!(
             "postgres://{}:{}@{}:{}/{}",
@@ -34,7 +49,9 @@ impl Database for BackendDB {
         );
 
         let manager = ConnectionManager::<PgConnection>::new(db_url.clone());
-        let pool = Pool::new(manager)
+        let pool = Pool::builder()
+            .max_size(self.config.max_connections())
+            .build(manager)
             .unwrap_or_else(|_| panic!("failed to conect to the database: {}", db_url));
 
         self.client = Some(pool);
@@ -42,7 +59,100 @@ impl Database for BackendDB {
         true
     }
 
-    fn conn(&self) -> Option<PooledConnection<ConnectionManager<PgConnection>>> {
-        self.client.as_ref().map(|p| p.get().unwrap())
+    fn conn(&self) -> Result<Self::Connection, Error> {
+        match self.client.as_ref() {
+            Some(pool) => pool.get().map_err(|e| e.into()),
+            None => Err(Error::ClientDoesntExists),
+        }
+    }
+}
+
+/// SqliteDB holds a connection pool to a sqlite database file, cloning it shares the pool.
+#[cfg(feature = "sqlite")]
+#[derive(Clone)]
+pub struct SqliteDB {
+    pub config: DBConfig,
+    pub client: Option<Pool<ConnectionManager<SqliteConnection>>>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteDB {
+    pub fn new(config: DBConfig) -> Self {
+        SqliteDB {
+            config,
+            client: None,
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Database<SqliteConnection> for SqliteDB {
+    type Connection = PooledConnection<ConnectionManager<SqliteConnection>>;
+
+    fn connect(&mut self) -> bool {
+        let manager = ConnectionManager::<SqliteConnection>::new(self.config.database.clone());
+        let pool = Pool::builder()
+            .max_size(self.config.max_connections())
+            .connection_customizer(Box::new(SqlitePragmas))
+            .build(manager)
+            .unwrap_or_else(|_| {
+                panic!(
+                    "failed to open the sqlite database: {}",
+                    self.config.database
+                )
+            });
+
+        self.client = Some(pool);
+
+        true
+    }
+
+    fn conn(&self) -> Result<Self::Connection, Error> {
+        match self.client.as_ref() {
+            Some(pool) => pool.get().map_err(|e| e.into()),
+            None => Err(Error::ClientDoesntExists),
+        }
+    }
+}
+
+/// SqlitePragmas sets up every new sqlite connection of the pool.
+/// readers don't block the writer in WAL mode, and writers wait for each other instead of failing.
+/// foreign keys are off by default in sqlite, members of a validator set are deleted by cascade.
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+struct SqlitePragmas;
+
+#[cfg(feature = "sqlite")]
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for SqlitePragmas {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.batch_execute(&format!(
+            "PRAGMA journal_mode = WAL; PRAGMA busy_timeout = {}; PRAGMA foreign_keys = ON;",
+            SQLITE_BUSY_TIMEOUT_MS
+        ))
+        .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// TransactionDB hands out the connection of an open transaction,
+/// so that every operation on top of it is a part of the transaction.
+pub struct TransactionDB<'a, C = PgConnection> {
+    conn: &'a C,
+}
+
+impl<'a, C> TransactionDB<'a, C> {
+    pub fn new(conn: &'a C) -> Self {
+        TransactionDB { conn }
+    }
+}
+
+impl<'a, C> Database<C> for TransactionDB<'a, C> {
+    type Connection = &'a C;
+
+    fn connect(&mut self) -> bool {
+        true
+    }
+
+    fn conn(&self) -> Result<Self::Connection, Error> {
+        Ok(self.conn)
     }
 }
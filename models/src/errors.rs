@@ -1,4 +1,4 @@
-use diesel::result::Error as DieselError;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +6,9 @@ pub enum Error {
     #[error("failed to establish connection pool")]
     ConnectionError(#[from] diesel::ConnectionError),
 
+    #[error("failed to get a connection from the pool")]
+    ConnectionPoolError(#[from] r2d2::Error),
+
     #[error("you forgot to connect to the database")]
     ClientDoesntExists,
 
@@ -14,6 +17,12 @@ pub enum Error {
 
     #[error("query error")]
     QueryError(DieselError),
+
+    #[error("storage task failed: {0}")]
+    TaskFailed(String),
+
+    #[error("transaction {0} is inserted more than once in a batch")]
+    DuplicateTransaction(String),
 }
 
 impl From<DieselError> for Error {
@@ -24,3 +33,27 @@ impl From<DieselError> for Error {
         }
     }
 }
+
+impl Error {
+    /// returns true if the failure is likely to be transient, e.g. the connection has been lost.
+    /// retrying the same query might succeed in that case.
+    ///
+    /// only connection failures, serialization failures and deadlocks are considered transient,
+    /// constraint violations and other query errors fail the same way on every attempt.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::ConnectionError(_) | Error::ConnectionPoolError(_) => true,
+            Error::QueryError(DieselError::DatabaseError(kind, info)) => match kind {
+                DatabaseErrorKind::UnableToSendCommand
+                | DatabaseErrorKind::SerializationFailure => true,
+                // diesel doesn't expose a dedicated kind for lock conflicts,
+                // postgres reports "deadlock detected" and sqlite "database is locked".
+                _ => {
+                    let message = info.message();
+                    message.contains("deadlock detected") || message.contains("database is locked")
+                }
+            },
+            _ => false,
+        }
+    }
+}
@@ -1,8 +1,12 @@
+pub mod async_storage;
 pub mod config;
 pub mod db;
 pub mod errors;
+pub mod memory_storage;
 pub mod models;
 pub mod schema;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_storage;
 pub mod storage;
 pub mod utils;
 
@@ -0,0 +1,943 @@
+use std::sync::{Mutex, MutexGuard};
+
+use crate::{
+    errors::Error,
+    models::{
+        account::{Account, NewAccount},
+        account_balance::{AccountBalance, NewAccountBalance},
+        block::{Block, BlockGap, NewBlock},
+        chain::{Chain, NewChain},
+        event::{Event, NewEvent},
+        indexer_cursor::{IndexerCursor, NewIndexerCursor},
+        message::{Message, NewMessage},
+        transaction::{NewTransaction, Transaction},
+        transaction_fee::{NewTransactionFee, TransactionFee},
+        transaction_signer::{NewTransactionSigner, TransactionSigner},
+        unindexable_range::{NewUnindexableRange, UnindexableRange},
+        validator_set::{NewValidatorSet, NewValidatorSetMember, ValidatorSet, ValidatorSetMember},
+    },
+    storage::{check_unique_transactions, Storage, StorageReader, StorageWriter},
+};
+
+/// MemoryStorage keeps every table in memory, nothing is persisted.
+/// It follows the unique keys of the database schema, so that it behaves like [`PersistenceStorage`]
+/// in tests and demos which don't have a database.
+///
+/// [`PersistenceStorage`]: crate::storage::PersistenceStorage
+#[derive(Default)]
+pub struct MemoryStorage {
+    tables: Mutex<Tables>,
+}
+
+#[derive(Default, Clone)]
+struct Tables {
+    last_id: i32,
+    chains: Vec<Chain>,
+    blocks: Vec<Block>,
+    transactions: Vec<Transaction>,
+    messages: Vec<Message>,
+    transaction_signers: Vec<TransactionSigner>,
+    transaction_fees: Vec<TransactionFee>,
+    events: Vec<Event>,
+    accounts: Vec<Account>,
+    account_balances: Vec<AccountBalance>,
+    validator_sets: Vec<ValidatorSet>,
+    validator_set_members: Vec<ValidatorSetMember>,
+    unindexable_ranges: Vec<UnindexableRange>,
+    indexer_cursors: Vec<IndexerCursor>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        // the tables are consistent after every operation, a panic in another thread doesn't matter.
+        self.tables
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Tables {
+    fn next_id(&mut self) -> i32 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn insert_block(&mut self, block: &NewBlock) -> usize {
+        if self
+            .blocks
+            .iter()
+            .any(|b| b.chain_id == block.chain_id && b.height == block.height)
+        {
+            return 0;
+        }
+
+        let id = self.next_id();
+        self.blocks.push(Block {
+            id,
+            chain_id: block.chain_id,
+            height: block.height,
+            block_hash: block.block_hash.clone(),
+            prev_hash: block.prev_hash.clone(),
+            proposer_address: block.proposer_address.clone(),
+            last_commit_hash: block.last_commit_hash.clone(),
+            data_hash: block.data_hash.clone(),
+            validators_hash: block.validators_hash.clone(),
+            next_validators_hash: block.next_validators_hash.clone(),
+            consensus_hash: block.consensus_hash.clone(),
+            app_hash: block.app_hash.clone(),
+            last_result_hash: block.last_result_hash.clone(),
+            evidence_hash: block.evidence_hash.clone(),
+            block_time: block.block_time,
+            inserted_at: block.inserted_at,
+            updated_at: None,
+        });
+        1
+    }
+
+    fn insert_event(&mut self, event: &NewEvent) -> usize {
+        if self.events.iter().any(|e| {
+            e.chain_id == event.chain_id
+                && e.block_height == event.block_height
+                && e.tx_type == event.tx_type
+                && e.tx_hash == event.tx_hash
+                && e.event_seq == event.event_seq
+                && e.attr_seq == event.attr_seq
+        }) {
+            return 0;
+        }
+
+        let id = self.next_id();
+        self.events.push(Event {
+            id,
+            chain_id: event.chain_id,
+            tx_type: event.tx_type,
+            tx_hash: event.tx_hash.clone(),
+            block_height: event.block_height,
+            event_seq: event.event_seq,
+            event_type: event.event_type.clone(),
+            event_key: event.event_key.clone(),
+            event_value: event.event_value.clone(),
+            indexed: event.indexed,
+            inserted_at: event.inserted_at,
+            updated_at: None,
+            attr_seq: event.attr_seq,
+        });
+        1
+    }
+
+    fn insert_transaction(&mut self, transaction: &NewTransaction) -> Transaction {
+        if let Some(stored) = self.transactions.iter_mut().find(|tx| {
+            tx.chain_id == transaction.chain_id
+                && tx.transaction_hash == transaction.transaction_hash
+        }) {
+            stored.updated_at = Some(transaction.inserted_at);
+            return stored.clone();
+        }
+
+        let id = self.next_id();
+        let stored = Transaction {
+            id,
+            chain_id: transaction.chain_id,
+            transaction_hash: transaction.transaction_hash.clone(),
+            height: transaction.height,
+            code: transaction.code,
+            code_space: transaction.code_space.clone(),
+            tx_data: transaction.tx_data.clone(),
+            raw_log: transaction.raw_log.clone(),
+            info: transaction.info.clone(),
+            memo: transaction.memo.clone(),
+            gas_wanted: transaction.gas_wanted,
+            gas_used: transaction.gas_used,
+            tx_timestamp: transaction.tx_timestamp.clone(),
+            inserted_at: transaction.inserted_at,
+            updated_at: None,
+            gas_limit: transaction.gas_limit,
+            fee_payer: transaction.fee_payer.clone(),
+            fee_granter: transaction.fee_granter.clone(),
+        };
+        self.transactions.push(stored.clone());
+        stored
+    }
+
+    fn insert_message(&mut self, message: &NewMessage) -> usize {
+        if self
+            .messages
+            .iter()
+            .any(|m| m.transaction_id == message.transaction_id && m.seq == message.seq)
+        {
+            return 0;
+        }
+
+        let id = self.next_id();
+        self.messages.push(Message {
+            id,
+            transaction_id: message.transaction_id,
+            seq: message.seq,
+            rawdata: message.rawdata.clone(),
+            inserted_at: message.inserted_at,
+            updated_at: None,
+        });
+        1
+    }
+
+    fn insert_transaction_signer(&mut self, signer: &NewTransactionSigner) -> usize {
+        if self
+            .transaction_signers
+            .iter()
+            .any(|s| s.transaction_id == signer.transaction_id && s.signer_seq == signer.signer_seq)
+        {
+            return 0;
+        }
+
+        let id = self.next_id();
+        self.transaction_signers.push(TransactionSigner {
+            id,
+            transaction_id: signer.transaction_id,
+            signer_seq: signer.signer_seq,
+            pub_key_type: signer.pub_key_type.clone(),
+            pub_key: signer.pub_key.clone(),
+            sequence: signer.sequence,
+            sign_mode: signer.sign_mode.clone(),
+            inserted_at: signer.inserted_at,
+            updated_at: None,
+        });
+        1
+    }
+
+    fn insert_transaction_fee(&mut self, fee: &NewTransactionFee) -> usize {
+        if self
+            .transaction_fees
+            .iter()
+            .any(|f| f.transaction_id == fee.transaction_id && f.seq == fee.seq)
+        {
+            return 0;
+        }
+
+        let id = self.next_id();
+        self.transaction_fees.push(TransactionFee {
+            id,
+            transaction_id: fee.transaction_id,
+            seq: fee.seq,
+            denom: fee.denom.clone(),
+            amount: fee.amount.clone(),
+            inserted_at: fee.inserted_at,
+            updated_at: None,
+        });
+        1
+    }
+
+    fn delete_blocks_in_range(&mut self, chain_id: i32, from_height: i64, to_height: i64) -> usize {
+        let in_range = |height: i64| from_height <= height && height <= to_height;
+
+        let tx_ids = self
+            .transactions
+            .iter()
+            .filter(|tx| tx.chain_id == chain_id && in_range(tx.height))
+            .map(|tx| tx.id)
+            .collect::<Vec<_>>();
+        self.messages
+            .retain(|m| !tx_ids.contains(&m.transaction_id));
+        self.transaction_signers
+            .retain(|s| !tx_ids.contains(&s.transaction_id));
+        self.transaction_fees
+            .retain(|f| !tx_ids.contains(&f.transaction_id));
+        self.events
+            .retain(|e| !(e.chain_id == chain_id && in_range(e.block_height)));
+        self.transactions
+            .retain(|tx| !(tx.chain_id == chain_id && in_range(tx.height)));
+
+        let set_ids = self
+            .validator_sets
+            .iter()
+            .filter(|set| set.chain_id == chain_id && in_range(set.start_height))
+            .map(|set| set.id)
+            .collect::<Vec<_>>();
+        self.validator_set_members
+            .retain(|m| !set_ids.contains(&m.validator_set_id));
+        self.validator_sets.retain(|set| !set_ids.contains(&set.id));
+
+        let before = self.blocks.len();
+        self.blocks
+            .retain(|b| !(b.chain_id == chain_id && in_range(b.height)));
+        before - self.blocks.len()
+    }
+
+    fn upsert_cursor(&mut self, cursor: &NewIndexerCursor, height: impl Fn(i64) -> i64) -> usize {
+        if let Some(stored) = self
+            .indexer_cursors
+            .iter_mut()
+            .find(|c| c.chain_id == cursor.chain_id && c.pipeline == cursor.pipeline)
+        {
+            stored.height = height(stored.height);
+            stored.updated_at = Some(cursor.inserted_at);
+            return 1;
+        }
+
+        let id = self.next_id();
+        self.indexer_cursors.push(IndexerCursor {
+            id,
+            chain_id: cursor.chain_id,
+            pipeline: cursor.pipeline.clone(),
+            height: cursor.height,
+            inserted_at: cursor.inserted_at,
+            updated_at: None,
+        });
+        1
+    }
+}
+
+impl StorageWriter for MemoryStorage {
+    fn insert_block(&self, block: &NewBlock) -> Result<usize, Error> {
+        Ok(self.tables().insert_block(block))
+    }
+
+    fn insert_blocks(&self, blocks: &[NewBlock]) -> Result<usize, Error> {
+        let mut tables = self.tables();
+        Ok(blocks.iter().map(|b| tables.insert_block(b)).sum())
+    }
+
+    fn latest_block_height(&self, chain_id: i32) -> Result<i64, Error> {
+        self.tables()
+            .blocks
+            .iter()
+            .filter(|b| b.chain_id == chain_id)
+            .map(|b| b.height)
+            .max()
+            .ok_or(Error::NotFound)
+    }
+
+    fn delete_blocks_from(&self, chain_id: i32, height: i64) -> Result<usize, Error> {
+        let mut tables = self.tables();
+        let deleted = tables.delete_blocks_in_range(chain_id, height, i64::MAX);
+        for cursor in tables.indexer_cursors.iter_mut() {
+            if cursor.chain_id == chain_id && cursor.height >= height {
+                cursor.height = height - 1;
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    fn delete_blocks_in_range(
+        &self,
+        chain_id: i32,
+        from_height: i64,
+        to_height: i64,
+    ) -> Result<usize, Error> {
+        Ok(self
+            .tables()
+            .delete_blocks_in_range(chain_id, from_height, to_height))
+    }
+
+    fn insert_chain(&self, chain: &NewChain) -> Result<usize, Error> {
+        let mut tables = self.tables();
+        if tables.chains.iter().any(|c| c.chain_id == chain.chain_id) {
+            return Ok(0);
+        }
+
+        let id = tables.next_id();
+        tables.chains.push(Chain {
+            id,
+            chain_id: chain.chain_id.clone(),
+            chain_name: chain.chain_name.clone(),
+            icon_url: None,
+            webisite: None,
+            inserted_at: chain.inserted_at,
+            updated_at: None,
+        });
+        Ok(1)
+    }
+
+    fn insert_event(&self, event: &NewEvent) -> Result<usize, Error> {
+        Ok(self.tables().insert_event(event))
+    }
+
+    fn insert_events(&self, events: &[NewEvent]) -> Result<usize, Error> {
+        let mut tables = self.tables();
+        Ok(events.iter().map(|e| tables.insert_event(e)).sum())
+    }
+
+    fn insert_transaction(&self, transaction: &NewTransaction) -> Result<Transaction, Error> {
+        Ok(self.tables().insert_transaction(transaction))
+    }
+
+    fn insert_transactions(
+        &self,
+        transactions: &[NewTransaction],
+    ) -> Result<Vec<Transaction>, Error> {
+        check_unique_transactions(transactions)?;
+        let mut tables = self.tables();
+        Ok(transactions
+            .iter()
+            .map(|tx| tables.insert_transaction(tx))
+            .collect())
+    }
+
+    fn insert_message(&self, message: &NewMessage) -> Result<usize, Error> {
+        Ok(self.tables().insert_message(message))
+    }
+
+    fn insert_messages(&self, messages: &[NewMessage]) -> Result<usize, Error> {
+        let mut tables = self.tables();
+        Ok(messages.iter().map(|m| tables.insert_message(m)).sum())
+    }
+
+    fn insert_transaction_signer(&self, signer: &NewTransactionSigner) -> Result<usize, Error> {
+        Ok(self.tables().insert_transaction_signer(signer))
+    }
+
+    fn insert_transaction_signers(&self, signers: &[NewTransactionSigner]) -> Result<usize, Error> {
+        let mut tables = self.tables();
+        Ok(signers
+            .iter()
+            .map(|s| tables.insert_transaction_signer(s))
+            .sum())
+    }
+
+    fn insert_transaction_fees(&self, fees: &[NewTransactionFee]) -> Result<usize, Error> {
+        let mut tables = self.tables();
+        Ok(fees.iter().map(|f| tables.insert_transaction_fee(f)).sum())
+    }
+
+    fn upsert_account(&self, account: &NewAccount) -> Result<Account, Error> {
+        let mut tables = self.tables();
+        if let Some(stored) = tables
+            .accounts
+            .iter_mut()
+            .find(|a| a.chain_id == account.chain_id && a.address == account.address)
+        {
+            stored.updated_at = Some(account.inserted_at);
+            return Ok(stored.clone());
+        }
+
+        let id = tables.next_id();
+        let stored = Account {
+            id,
+            chain_id: account.chain_id,
+            address: account.address.clone(),
+            inserted_at: account.inserted_at,
+            updated_at: None,
+        };
+        tables.accounts.push(stored.clone());
+        Ok(stored)
+    }
+
+    fn replace_account_balances(
+        &self,
+        account_id: i32,
+        balances: &[NewAccountBalance],
+    ) -> Result<usize, Error> {
+        let mut tables = self.tables();
+        tables.account_balances.retain(|b| {
+            b.account_id != account_id || balances.iter().any(|new| new.denom == b.denom)
+        });
+
+        for balance in balances {
+            match tables
+                .account_balances
+                .iter_mut()
+                .find(|b| b.account_id == account_id && b.denom == balance.denom)
+            {
+                Some(stored) => {
+                    stored.amount = balance.amount.clone();
+                    stored.updated_at = Some(balance.inserted_at);
+                }
+                None => {
+                    let id = tables.next_id();
+                    tables.account_balances.push(AccountBalance {
+                        id,
+                        account_id: balance.account_id,
+                        amount: balance.amount.clone(),
+                        denom: balance.denom.clone(),
+                        inserted_at: balance.inserted_at,
+                        updated_at: None,
+                    });
+                }
+            }
+        }
+
+        Ok(balances.len())
+    }
+
+    fn insert_validator_set(
+        &self,
+        validator_set: &NewValidatorSet,
+        members: &[NewValidatorSetMember],
+    ) -> Result<ValidatorSet, Error> {
+        let mut tables = self.tables();
+        let stored = match tables.validator_sets.iter_mut().find(|set| {
+            set.chain_id == validator_set.chain_id && set.start_height == validator_set.start_height
+        }) {
+            Some(stored) => {
+                stored.validators_hash = validator_set.validators_hash.clone();
+                stored.updated_at = Some(validator_set.inserted_at);
+                stored.clone()
+            }
+            None => {
+                let id = tables.next_id();
+                let stored = ValidatorSet {
+                    id,
+                    chain_id: validator_set.chain_id,
+                    start_height: validator_set.start_height,
+                    validators_hash: validator_set.validators_hash.clone(),
+                    inserted_at: validator_set.inserted_at,
+                    updated_at: None,
+                };
+                tables.validator_sets.push(stored.clone());
+                stored
+            }
+        };
+
+        tables
+            .validator_set_members
+            .retain(|m| m.validator_set_id != stored.id);
+        for member in members {
+            let id = tables.next_id();
+            tables.validator_set_members.push(ValidatorSetMember {
+                id,
+                validator_set_id: stored.id,
+                address: member.address.clone(),
+                pub_key_type: member.pub_key_type.clone(),
+                pub_key: member.pub_key.clone(),
+                voting_power: member.voting_power,
+                proposer_priority: member.proposer_priority,
+                inserted_at: member.inserted_at,
+                updated_at: None,
+            });
+        }
+
+        Ok(stored)
+    }
+
+    fn upsert_unindexable_range(
+        &self,
+        range: &NewUnindexableRange,
+    ) -> Result<UnindexableRange, Error> {
+        let mut tables = self.tables();
+        if let Some(stored) = tables
+            .unindexable_ranges
+            .iter_mut()
+            .find(|r| r.chain_id == range.chain_id && r.start_height == range.start_height)
+        {
+            stored.end_height = range.end_height;
+            stored.reason = range.reason.clone();
+            stored.updated_at = Some(range.inserted_at);
+            return Ok(stored.clone());
+        }
+
+        let id = tables.next_id();
+        let stored = UnindexableRange {
+            id,
+            chain_id: range.chain_id,
+            start_height: range.start_height,
+            end_height: range.end_height,
+            reason: range.reason.clone(),
+            inserted_at: range.inserted_at,
+            updated_at: None,
+        };
+        tables.unindexable_ranges.push(stored.clone());
+        Ok(stored)
+    }
+
+    fn advance_indexer_cursor(&self, cursor: &NewIndexerCursor) -> Result<usize, Error> {
+        Ok(self
+            .tables()
+            .upsert_cursor(cursor, |height| height.max(cursor.height)))
+    }
+
+    fn upsert_indexer_cursor(&self, cursor: &NewIndexerCursor) -> Result<usize, Error> {
+        Ok(self.tables().upsert_cursor(cursor, |_| cursor.height))
+    }
+
+    /// the tables are locked until `f` returns, `f` works on a copy of them which replaces the tables
+    /// only if it succeeds. concurrent operations wait for the transaction, so that they neither see
+    /// partial changes nor get lost when `f` fails.
+    fn within_transaction<R, F>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&dyn Storage) -> Result<R, Error>,
+    {
+        let mut tables = self.tables();
+        let transaction = MemoryStorage {
+            tables: Mutex::new(tables.clone()),
+        };
+        let result = f(&transaction)?;
+        *tables = std::mem::take(&mut *transaction.tables());
+
+        Ok(result)
+    }
+}
+
+impl StorageReader for MemoryStorage {
+    fn find_block_by_height(&self, chain_id: i32, height: i64) -> Result<Block, Error> {
+        self.tables()
+            .blocks
+            .iter()
+            .find(|b| b.chain_id == chain_id && b.height == height)
+            .cloned()
+            .ok_or(Error::NotFound)
+    }
+
+    fn list_blocks(&self, chain_id: i32, limit: i64, offset: i64) -> Result<Vec<Block>, Error> {
+        let mut blocks = self
+            .tables()
+            .blocks
+            .iter()
+            .filter(|b| b.chain_id == chain_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|b| std::cmp::Reverse(b.height));
+
+        Ok(blocks
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    fn find_latest_block(&self, chain_id: i32) -> Result<Block, Error> {
+        self.tables()
+            .blocks
+            .iter()
+            .filter(|b| b.chain_id == chain_id)
+            .max_by_key(|b| b.height)
+            .cloned()
+            .ok_or(Error::NotFound)
+    }
+
+    fn find_block_gaps(&self, chain_id: i32, from_height: i64) -> Result<Vec<BlockGap>, Error> {
+        let mut heights = self
+            .tables()
+            .blocks
+            .iter()
+            .filter(|b| b.chain_id == chain_id && b.height >= from_height)
+            .map(|b| b.height)
+            .collect::<Vec<_>>();
+        // the same sentinel as the query of the database.
+        heights.push(from_height - 1);
+        heights.sort_unstable();
+
+        Ok(heights
+            .windows(2)
+            .filter(|pair| pair[1] - pair[0] > 1)
+            .map(|pair| BlockGap {
+                start_height: pair[0] + 1,
+                end_height: pair[1] - 1,
+            })
+            .collect())
+    }
+
+    fn list_blocks_in_range(
+        &self,
+        chain_id: i32,
+        from_height: i64,
+        to_height: i64,
+        limit: i64,
+    ) -> Result<Vec<Block>, Error> {
+        let mut blocks = self
+            .tables()
+            .blocks
+            .iter()
+            .filter(|b| b.chain_id == chain_id && from_height <= b.height && b.height <= to_height)
+            .cloned()
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|b| b.height);
+        blocks.truncate(limit.max(0) as usize);
+
+        Ok(blocks)
+    }
+
+    fn find_by_chain_id(&self, chain_id: String) -> Result<Chain, Error> {
+        self.tables()
+            .chains
+            .iter()
+            .find(|c| c.chain_id == chain_id)
+            .cloned()
+            .ok_or(Error::NotFound)
+    }
+
+    fn all_chains(&self) -> Result<Vec<Chain>, Error> {
+        Ok(self.tables().chains.clone())
+    }
+
+    fn list_transactions(&self, chain_id: i32, height: i64) -> Result<Vec<Transaction>, Error> {
+        Ok(self
+            .tables()
+            .transactions
+            .iter()
+            .filter(|tx| tx.chain_id == chain_id && tx.height == height)
+            .cloned()
+            .collect())
+    }
+
+    fn find_transaction_by_hash(
+        &self,
+        chain_id: i32,
+        tx_hash: String,
+    ) -> Result<Transaction, Error> {
+        self.tables()
+            .transactions
+            .iter()
+            .find(|tx| tx.chain_id == chain_id && tx.transaction_hash == tx_hash)
+            .cloned()
+            .ok_or(Error::NotFound)
+    }
+
+    fn list_transactions_by_hash(&self, tx_hash: String) -> Result<Vec<Transaction>, Error> {
+        let mut transactions = self
+            .tables()
+            .transactions
+            .iter()
+            .filter(|tx| tx.transaction_hash == tx_hash)
+            .cloned()
+            .collect::<Vec<_>>();
+        transactions.sort_by_key(|tx| tx.chain_id);
+
+        Ok(transactions)
+    }
+
+    fn list_messages_by_tx(&self, tx_id: i32) -> Result<Vec<Message>, Error> {
+        Ok(self
+            .tables()
+            .messages
+            .iter()
+            .filter(|m| m.transaction_id == tx_id)
+            .cloned()
+            .collect())
+    }
+
+    fn list_signers_by_tx(&self, tx_id: i32) -> Result<Vec<TransactionSigner>, Error> {
+        let mut signers = self
+            .tables()
+            .transaction_signers
+            .iter()
+            .filter(|s| s.transaction_id == tx_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        signers.sort_by_key(|s| s.signer_seq);
+
+        Ok(signers)
+    }
+
+    fn list_fees_by_tx(&self, tx_id: i32) -> Result<Vec<TransactionFee>, Error> {
+        let mut fees = self
+            .tables()
+            .transaction_fees
+            .iter()
+            .filter(|f| f.transaction_id == tx_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        fees.sort_by_key(|f| f.seq);
+
+        Ok(fees)
+    }
+
+    fn list_events_by_tx(&self, chain_id: i32, tx_hash: String) -> Result<Vec<Event>, Error> {
+        Ok(self
+            .tables()
+            .events
+            .iter()
+            .filter(|e| e.chain_id == chain_id && e.tx_hash.as_ref() == Some(&tx_hash))
+            .cloned()
+            .collect())
+    }
+
+    fn find_account_by_address(&self, chain_id: i32, address: String) -> Result<Account, Error> {
+        self.tables()
+            .accounts
+            .iter()
+            .find(|a| a.chain_id == chain_id && a.address == address)
+            .cloned()
+            .ok_or(Error::NotFound)
+    }
+
+    fn list_account_balances(&self, account_id: i32) -> Result<Vec<AccountBalance>, Error> {
+        let mut balances = self
+            .tables()
+            .account_balances
+            .iter()
+            .filter(|b| b.account_id == account_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        balances.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+        Ok(balances)
+    }
+
+    fn find_validator_set_at_height(
+        &self,
+        chain_id: i32,
+        height: i64,
+    ) -> Result<ValidatorSet, Error> {
+        self.tables()
+            .validator_sets
+            .iter()
+            .filter(|set| set.chain_id == chain_id && set.start_height <= height)
+            .max_by_key(|set| set.start_height)
+            .cloned()
+            .ok_or(Error::NotFound)
+    }
+
+    fn list_validator_set_members(
+        &self,
+        validator_set_id: i32,
+    ) -> Result<Vec<ValidatorSetMember>, Error> {
+        let mut members = self
+            .tables()
+            .validator_set_members
+            .iter()
+            .filter(|m| m.validator_set_id == validator_set_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        members.sort_by(|a, b| {
+            b.voting_power
+                .cmp(&a.voting_power)
+                .then_with(|| a.address.cmp(&b.address))
+        });
+
+        Ok(members)
+    }
+
+    fn list_unindexable_ranges(&self, chain_id: i32) -> Result<Vec<UnindexableRange>, Error> {
+        let mut ranges = self
+            .tables()
+            .unindexable_ranges
+            .iter()
+            .filter(|r| r.chain_id == chain_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|r| r.start_height);
+
+        Ok(ranges)
+    }
+
+    fn find_indexer_cursor(&self, chain_id: i32, pipeline: &str) -> Result<IndexerCursor, Error> {
+        self.tables()
+            .indexer_cursors
+            .iter()
+            .find(|c| c.chain_id == chain_id && c.pipeline == pipeline)
+            .cloned()
+            .ok_or(Error::NotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+    use std::time::Duration;
+
+    use chrono::NaiveDateTime;
+
+    use super::*;
+
+    fn new_block(height: i64) -> NewBlock {
+        NewBlock {
+            chain_id: 1,
+            height,
+            block_hash: format!("HASH{}", height),
+            prev_hash: format!("HASH{}", height - 1),
+            proposer_address: String::new(),
+            last_commit_hash: String::new(),
+            data_hash: String::new(),
+            validators_hash: String::new(),
+            next_validators_hash: String::new(),
+            consensus_hash: String::new(),
+            app_hash: String::new(),
+            last_result_hash: String::new(),
+            evidence_hash: String::new(),
+            block_time: NaiveDateTime::from_timestamp(0, 0),
+            inserted_at: NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+
+    fn new_transaction(height: i64, hash: &str) -> NewTransaction {
+        NewTransaction {
+            chain_id: 1,
+            transaction_hash: hash.to_string(),
+            height,
+            code: 0,
+            code_space: String::new(),
+            tx_data: String::new(),
+            raw_log: String::new(),
+            info: String::new(),
+            memo: None,
+            gas_wanted: 0,
+            gas_used: 0,
+            tx_timestamp: String::new(),
+            gas_limit: 0,
+            fee_payer: None,
+            fee_granter: None,
+            inserted_at: NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+
+    #[test]
+    fn a_batch_carrying_a_hash_twice_is_refused() {
+        let storage = MemoryStorage::new();
+
+        let result =
+            storage.insert_transactions(&[new_transaction(1, "TX"), new_transaction(2, "TX")]);
+        assert!(matches!(result, Err(Error::DuplicateTransaction(hash)) if hash == "TX"));
+        assert!(storage
+            .find_transaction_by_hash(1, "TX".to_string())
+            .is_err());
+
+        // a hash stored by an earlier batch is returned as it is.
+        storage
+            .insert_transactions(&[new_transaction(1, "TX")])
+            .unwrap();
+        let stored = storage
+            .insert_transactions(&[new_transaction(2, "TX")])
+            .unwrap();
+        assert_eq!(stored[0].height, 1);
+    }
+
+    #[test]
+    fn failed_transaction_leaves_the_tables_untouched() {
+        let storage = MemoryStorage::new();
+        storage.insert_block(&new_block(1)).unwrap();
+
+        let result: Result<(), Error> = storage.within_transaction(|storage| {
+            storage.insert_block(&new_block(2))?;
+            Err(Error::NotFound)
+        });
+        assert!(result.is_err());
+        assert_eq!(storage.latest_block_height(1).unwrap(), 1);
+
+        storage
+            .within_transaction(|storage| storage.insert_block(&new_block(2)))
+            .unwrap();
+        assert_eq!(storage.latest_block_height(1).unwrap(), 2);
+    }
+
+    #[test]
+    fn concurrent_writes_wait_for_the_transaction() {
+        let storage = Arc::new(MemoryStorage::new());
+        let (started_tx, started_rx) = mpsc::channel();
+
+        let writer = {
+            let storage = storage.clone();
+            thread::spawn(move || {
+                started_rx.recv().unwrap();
+                storage.insert_block(&new_block(1)).unwrap();
+            })
+        };
+
+        let result: Result<(), Error> = storage.within_transaction(|storage| {
+            storage.insert_block(&new_block(2))?;
+            started_tx.send(()).unwrap();
+            // give the writer a chance to run, it must not be undone by the rollback.
+            thread::sleep(Duration::from_millis(50));
+            Err(Error::NotFound)
+        });
+        assert!(result.is_err());
+        writer.join().unwrap();
+
+        assert_eq!(storage.latest_block_height(1).unwrap(), 1);
+        assert!(storage.find_block_by_height(1, 2).is_err());
+    }
+}
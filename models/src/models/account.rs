@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::schema::accounts;
 
-#[derive(Debug, Queryable, Serialize, Deserialize)]
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
 pub struct Account {
     pub id: i32,
     pub chain_id: i32,
@@ -4,21 +4,21 @@ use serde::{Deserialize, Serialize};
 
 use crate::schema::account_balance;
 
-#[derive(Debug, Queryable, Serialize, Deserialize)]
-pub struct Account {
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
+pub struct AccountBalance {
     pub id: i32,
     pub account_id: i32,
-    pub amount: i64,
+    pub amount: String,
     pub denom: String,
     pub inserted_at: chrono::NaiveDateTime,
     pub updated_at: Option<chrono::NaiveDateTime>,
 }
 
-#[derive(Debug, Insertable, Serialize, Deserialize)]
+#[derive(Debug, Clone, Insertable, Serialize, Deserialize)]
 #[table_name = "account_balance"]
-pub struct NewAccount {
+pub struct NewAccountBalance {
     pub account_id: i32,
-    pub amount: i64,
+    pub amount: String,
     pub denom: String,
     pub inserted_at: chrono::NaiveDateTime,
 }
@@ -1,11 +1,13 @@
 use chrono::NaiveDateTime;
+use diesel::sql_types::BigInt;
 use diesel::Insertable;
 use diesel::Queryable;
+use diesel::QueryableByName;
 use serde::{Deserialize, Serialize};
 
 use crate::schema::blocks;
 
-#[derive(Debug, Queryable, Serialize, Deserialize)]
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
 pub struct Block {
     pub id: i32,
     pub chain_id: i32,
@@ -45,3 +47,12 @@ pub struct NewBlock {
     pub block_time: NaiveDateTime,
     pub inserted_at: NaiveDateTime,
 }
+
+/// BlockGap is an inclusive range of heights which are missing in the blocks table.
+#[derive(Debug, Clone, PartialEq, QueryableByName, Serialize, Deserialize)]
+pub struct BlockGap {
+    #[sql_type = "BigInt"]
+    pub start_height: i64,
+    #[sql_type = "BigInt"]
+    pub end_height: i64,
+}
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::schema::chains;
 
-#[derive(Debug, Queryable, Serialize)]
+#[derive(Debug, Clone, Queryable, Serialize)]
 pub struct Chain {
     pub id: i32,
     pub chain_id: String,
@@ -9,7 +9,7 @@ pub const TX_TYPE_TRANSACTION: i16 = 1;
 pub const TX_TYPE_BEGIN_BLOCK: i16 = 2;
 pub const TX_TYPE_END_BLOCK: i16 = 3;
 
-#[derive(Debug, Queryable, Serialize, Deserialize)]
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
 pub struct Event {
     pub id: i32,
     pub chain_id: i32,
@@ -23,6 +23,7 @@ pub struct Event {
     pub indexed: bool,
     pub inserted_at: NaiveDateTime,
     pub updated_at: Option<NaiveDateTime>,
+    pub attr_seq: i32,
 }
 
 #[derive(Debug, Insertable, Serialize, Deserialize)]
@@ -33,6 +34,7 @@ pub struct NewEvent {
     pub tx_hash: Option<String>,
     pub block_height: i64,
     pub event_seq: i32,
+    pub attr_seq: i32,
     pub event_type: String,
     pub event_key: String,
     pub event_value: String,
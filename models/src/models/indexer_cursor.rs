@@ -0,0 +1,30 @@
+use diesel::Insertable;
+use diesel::Queryable;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::indexer_cursors;
+
+/// pipeline which commits blocks, transactions and events.
+pub const PIPELINE_BLOCKS: &str = "blocks";
+/// pipeline which stores the validator set history.
+pub const PIPELINE_VALIDATORS: &str = "validators";
+
+/// IndexerCursor is the last height fully processed by a pipeline of the chain.
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
+pub struct IndexerCursor {
+    pub id: i32,
+    pub chain_id: i32,
+    pub pipeline: String,
+    pub height: i64,
+    pub inserted_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Insertable, Serialize, Deserialize)]
+#[table_name = "indexer_cursors"]
+pub struct NewIndexerCursor {
+    pub chain_id: i32,
+    pub pipeline: String,
+    pub height: i64,
+    pub inserted_at: chrono::NaiveDateTime,
+}
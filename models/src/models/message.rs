@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::schema::messages;
 
-#[derive(Debug, Queryable, Serialize, Deserialize)]
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
 pub struct Message {
     pub id: i32,
     pub transaction_id: i32,
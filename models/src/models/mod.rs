@@ -3,5 +3,10 @@ pub mod account_balance;
 pub mod block;
 pub mod chain;
 pub mod event;
+pub mod indexer_cursor;
 pub mod message;
 pub mod transaction;
+pub mod transaction_fee;
+pub mod transaction_signer;
+pub mod validator_set;
+pub mod unindexable_range;
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::schema::transactions;
 
-#[derive(Debug, Queryable, Serialize, Deserialize)]
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
 pub struct Transaction {
     pub id: i32,
     pub chain_id: i32,
@@ -22,6 +22,9 @@ pub struct Transaction {
     pub tx_timestamp: String,
     pub inserted_at: NaiveDateTime,
     pub updated_at: Option<NaiveDateTime>,
+    pub gas_limit: i64,
+    pub fee_payer: Option<String>,
+    pub fee_granter: Option<String>,
 }
 
 #[derive(Debug, Insertable, Serialize, Deserialize)]
@@ -39,5 +42,8 @@ pub struct NewTransaction {
     pub gas_wanted: i64,
     pub gas_used: i64,
     pub tx_timestamp: String,
+    pub gas_limit: i64,
+    pub fee_payer: Option<String>,
+    pub fee_granter: Option<String>,
     pub inserted_at: NaiveDateTime,
 }
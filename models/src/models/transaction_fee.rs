@@ -0,0 +1,26 @@
+use diesel::Insertable;
+use diesel::Queryable;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::transaction_fees;
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
+pub struct TransactionFee {
+    pub id: i32,
+    pub transaction_id: i32,
+    pub seq: i32,
+    pub denom: String,
+    pub amount: String,
+    pub inserted_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, Insertable, Serialize, Deserialize)]
+#[table_name = "transaction_fees"]
+pub struct NewTransactionFee {
+    pub transaction_id: i32,
+    pub seq: i32,
+    pub denom: String,
+    pub amount: String,
+    pub inserted_at: chrono::NaiveDateTime,
+}
@@ -0,0 +1,30 @@
+use diesel::Insertable;
+use diesel::Queryable;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::transaction_signers;
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
+pub struct TransactionSigner {
+    pub id: i32,
+    pub transaction_id: i32,
+    pub signer_seq: i32,
+    pub pub_key_type: Option<String>,
+    pub pub_key: Option<String>,
+    pub sequence: i64,
+    pub sign_mode: String,
+    pub inserted_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, Insertable, Serialize, Deserialize)]
+#[table_name = "transaction_signers"]
+pub struct NewTransactionSigner {
+    pub transaction_id: i32,
+    pub signer_seq: i32,
+    pub pub_key_type: Option<String>,
+    pub pub_key: Option<String>,
+    pub sequence: i64,
+    pub sign_mode: String,
+    pub inserted_at: chrono::NaiveDateTime,
+}
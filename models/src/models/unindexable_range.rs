@@ -0,0 +1,28 @@
+use diesel::Insertable;
+use diesel::Queryable;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::unindexable_ranges;
+
+/// UnindexableRange is a range of heights which the indexer cannot fetch,
+/// e.g. blocks pruned by the node before they were indexed.
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
+pub struct UnindexableRange {
+    pub id: i32,
+    pub chain_id: i32,
+    pub start_height: i64,
+    pub end_height: i64,
+    pub reason: String,
+    pub inserted_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Insertable, Serialize, Deserialize)]
+#[table_name = "unindexable_ranges"]
+pub struct NewUnindexableRange {
+    pub chain_id: i32,
+    pub start_height: i64,
+    pub end_height: i64,
+    pub reason: String,
+    pub inserted_at: chrono::NaiveDateTime,
+}
@@ -0,0 +1,51 @@
+use diesel::Insertable;
+use diesel::Queryable;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{validator_set_members, validator_sets};
+
+/// ValidatorSet is the validator set which becomes active at `start_height`.
+/// It stays active until the next stored set of the chain.
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
+pub struct ValidatorSet {
+    pub id: i32,
+    pub chain_id: i32,
+    pub start_height: i64,
+    pub validators_hash: String,
+    pub inserted_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Insertable, Serialize, Deserialize)]
+#[table_name = "validator_sets"]
+pub struct NewValidatorSet {
+    pub chain_id: i32,
+    pub start_height: i64,
+    pub validators_hash: String,
+    pub inserted_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
+pub struct ValidatorSetMember {
+    pub id: i32,
+    pub validator_set_id: i32,
+    pub address: String,
+    pub pub_key_type: String,
+    pub pub_key: String,
+    pub voting_power: i64,
+    pub proposer_priority: i64,
+    pub inserted_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Insertable, Serialize, Deserialize)]
+#[table_name = "validator_set_members"]
+pub struct NewValidatorSetMember {
+    pub validator_set_id: i32,
+    pub address: String,
+    pub pub_key_type: String,
+    pub pub_key: String,
+    pub voting_power: i64,
+    pub proposer_priority: i64,
+    pub inserted_at: chrono::NaiveDateTime,
+}
@@ -4,7 +4,7 @@ diesel::table! {
     account_balance (id) {
         id -> Int4,
         account_id -> Int4,
-        amount -> Int8,
+        amount -> Text,
         denom -> Varchar,
         inserted_at -> Timestamp,
         updated_at -> Nullable<Timestamp>,
@@ -69,6 +69,18 @@ diesel::table! {
         indexed -> Bool,
         inserted_at -> Timestamp,
         updated_at -> Nullable<Timestamp>,
+        attr_seq -> Int4,
+    }
+}
+
+diesel::table! {
+    indexer_cursors (id) {
+        id -> Int4,
+        chain_id -> Int4,
+        pipeline -> Varchar,
+        height -> Int8,
+        inserted_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
     }
 }
 
@@ -83,6 +95,32 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    transaction_fees (id) {
+        id -> Int4,
+        transaction_id -> Int4,
+        seq -> Int4,
+        denom -> Varchar,
+        amount -> Text,
+        inserted_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    transaction_signers (id) {
+        id -> Int4,
+        transaction_id -> Int4,
+        signer_seq -> Int4,
+        pub_key_type -> Nullable<Varchar>,
+        pub_key -> Nullable<Text>,
+        sequence -> Int8,
+        sign_mode -> Varchar,
+        inserted_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     transactions (id) {
         id -> Int4,
@@ -100,11 +138,54 @@ diesel::table! {
         tx_timestamp -> Varchar,
         inserted_at -> Timestamp,
         updated_at -> Nullable<Timestamp>,
+        gas_limit -> Int8,
+        fee_payer -> Nullable<Varchar>,
+        fee_granter -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    unindexable_ranges (id) {
+        id -> Int4,
+        chain_id -> Int4,
+        start_height -> Int8,
+        end_height -> Int8,
+        reason -> Varchar,
+        inserted_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    validator_set_members (id) {
+        id -> Int4,
+        validator_set_id -> Int4,
+        address -> Varchar,
+        pub_key_type -> Varchar,
+        pub_key -> Varchar,
+        voting_power -> Int8,
+        proposer_priority -> Int8,
+        inserted_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    validator_sets (id) {
+        id -> Int4,
+        chain_id -> Int4,
+        start_height -> Int8,
+        validators_hash -> Varchar,
+        inserted_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
     }
 }
 
 diesel::joinable!(account_balance -> accounts (account_id));
 diesel::joinable!(messages -> transactions (transaction_id));
+diesel::joinable!(transaction_fees -> transactions (transaction_id));
+diesel::joinable!(transaction_signers -> transactions (transaction_id));
+diesel::joinable!(validator_set_members -> validator_sets (validator_set_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     account_balance,
@@ -112,6 +193,12 @@ diesel::allow_tables_to_appear_in_same_query!(
     blocks,
     chains,
     events,
+    indexer_cursors,
     messages,
+    transaction_fees,
+    transaction_signers,
     transactions,
+    unindexable_ranges,
+    validator_set_members,
+    validator_sets,
 );
@@ -0,0 +1,1230 @@
+use diesel::prelude::*;
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::result::Error as DieselError;
+use diesel::sql_types::{BigInt, Integer, Text, Timestamp};
+use diesel::sqlite::Sqlite;
+use diesel::SqliteConnection;
+
+use crate::{
+    db::{Database, SqliteDB, TransactionDB},
+    errors::Error,
+    models::{
+        account::{Account, NewAccount},
+        account_balance::{AccountBalance, NewAccountBalance},
+        block::{Block, BlockGap, NewBlock},
+        chain::{Chain, NewChain},
+        event::{Event, NewEvent},
+        indexer_cursor::{IndexerCursor, NewIndexerCursor},
+        message::{Message, NewMessage},
+        transaction::{NewTransaction, Transaction},
+        transaction_fee::{NewTransactionFee, TransactionFee},
+        transaction_signer::{NewTransactionSigner, TransactionSigner},
+        unindexable_range::{NewUnindexableRange, UnindexableRange},
+        validator_set::{NewValidatorSet, NewValidatorSetMember, ValidatorSet, ValidatorSetMember},
+    },
+    schema::{
+        account_balance, accounts, blocks, chains, events, indexer_cursors, messages,
+        transaction_fees, transaction_signers, transactions, unindexable_ranges,
+        validator_set_members, validator_sets,
+    },
+    storage::{check_unique_transactions, Storage, StorageReader, StorageWriter},
+};
+
+/// SqliteStorage implements [`StorageWriter`] and [`StorageReader`] on top of a sqlite database,
+/// created with the migrations in `migrations_sqlite`.
+/// sqlite has no JSONB, `messages.rawdata` is stored as JSON text.
+pub struct SqliteStorage<T>
+where
+    T: Database<SqliteConnection>,
+{
+    db: T,
+}
+
+impl SqliteStorage<SqliteDB> {
+    /// creates a storage on top of the given database.
+    /// the connection pool is reused if the database has been connected already.
+    pub fn new(mut db: SqliteDB) -> Self {
+        let connected = db.client.is_some() || db.connect();
+        if !connected {
+            panic!("failed to open the sqlite database, please check configuration")
+        }
+
+        Self { db }
+    }
+}
+
+impl<T: Database<SqliteConnection>> SqliteStorage<T> {
+    pub fn get_conn(&self) -> Result<T::Connection, Error> {
+        self.db.conn()
+    }
+}
+
+/// OnConflictDoNothing skips the insert of a row which conflicts with a stored one on the `target` columns,
+/// diesel only builds the `ON CONFLICT` clause for postgres.
+/// unlike `INSERT OR IGNORE`, the other constraints like `NOT NULL` and foreign keys still fail the insert.
+struct OnConflictDoNothing<Q> {
+    insert: Q,
+    target: &'static str,
+}
+
+impl<Q> OnConflictDoNothing<Q> {
+    fn new(insert: Q, target: &'static str) -> Self {
+        OnConflictDoNothing { insert, target }
+    }
+}
+
+impl<Q: QueryFragment<Sqlite>> QueryFragment<Sqlite> for OnConflictDoNothing<Q> {
+    fn walk_ast(&self, mut out: AstPass<Sqlite>) -> QueryResult<()> {
+        self.insert.walk_ast(out.reborrow())?;
+        out.push_sql(" ON CONFLICT (");
+        out.push_sql(self.target);
+        out.push_sql(") DO NOTHING");
+        Ok(())
+    }
+}
+
+impl<Q> QueryId for OnConflictDoNothing<Q> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<Q> RunQueryDsl<SqliteConnection> for OnConflictDoNothing<Q> {}
+
+/// conflict targets, the columns of the unique constraints in `migrations_sqlite`.
+const BLOCKS_KEY: &str = "chain_id, height";
+const CHAINS_KEY: &str = "chain_id";
+const EVENTS_KEY: &str =
+    "chain_id, block_height, tx_type, COALESCE(tx_hash, ''), event_seq, attr_seq";
+const TRANSACTIONS_KEY: &str = "chain_id, transaction_hash";
+const TRANSACTION_SIGNERS_KEY: &str = "transaction_id, signer_seq";
+const TRANSACTION_FEES_KEY: &str = "transaction_id, seq";
+const ACCOUNTS_KEY: &str = "chain_id, address";
+const ACCOUNT_BALANCE_KEY: &str = "account_id, denom";
+const VALIDATOR_SETS_KEY: &str = "chain_id, start_height";
+const UNINDEXABLE_RANGES_KEY: &str = "chain_id, start_height";
+
+/// inserts the transaction if it isn't stored yet, and returns the stored one.
+/// diesel can't read back the inserted rows of sqlite, they're selected again.
+fn upsert_transaction(
+    conn: &SqliteConnection,
+    transaction: &NewTransaction,
+) -> Result<Transaction, Error> {
+    let key = transactions::chain_id
+        .eq(transaction.chain_id)
+        .and(transactions::transaction_hash.eq(&transaction.transaction_hash));
+
+    let inserted = OnConflictDoNothing::new(
+        diesel::insert_into(transactions::table).values(transaction),
+        TRANSACTIONS_KEY,
+    )
+    .execute(conn)?;
+    if inserted == 0 {
+        diesel::update(transactions::table.filter(key))
+            .set(transactions::updated_at.eq(Some(transaction.inserted_at)))
+            .execute(conn)?;
+    }
+
+    transactions::table
+        .filter(key)
+        .first(conn)
+        .map_err(|e| e.into())
+}
+
+/// inserts the message with its raw data as JSON text, a message stored already is skipped.
+fn insert_message_json(conn: &SqliteConnection, message: &NewMessage) -> Result<usize, Error> {
+    diesel::sql_query(
+        "INSERT INTO messages (transaction_id, seq, rawdata, inserted_at)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT (transaction_id, seq) DO NOTHING",
+    )
+    .bind::<Integer, _>(message.transaction_id)
+    .bind::<Integer, _>(message.seq)
+    .bind::<Text, _>(message.rawdata.to_string())
+    .bind::<Timestamp, _>(message.inserted_at)
+    .execute(conn)
+    .map_err(|e| e.into())
+}
+
+impl<T: Database<SqliteConnection>> StorageWriter for SqliteStorage<T> {
+    fn insert_block(&self, block: &NewBlock) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        OnConflictDoNothing::new(diesel::insert_into(blocks::table).values(block), BLOCKS_KEY)
+            .execute(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// inserts the blocks, heights stored already are left as they are.
+    /// sqlite runs a statement per row, they're grouped in a single transaction.
+    fn insert_blocks(&self, blocks: &[NewBlock]) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<usize, Error, _>(|| {
+            let mut inserted = 0;
+            for block in blocks {
+                inserted += OnConflictDoNothing::new(
+                    diesel::insert_into(blocks::table).values(block),
+                    BLOCKS_KEY,
+                )
+                .execute(&*conn)?;
+            }
+            Ok(inserted)
+        })
+    }
+
+    fn latest_block_height(&self, chain_id: i32) -> Result<i64, Error> {
+        let conn = self.get_conn()?;
+        blocks::table
+            .select(blocks::height)
+            .filter(blocks::chain_id.eq(chain_id))
+            .order(blocks::height.desc())
+            .limit(1)
+            .first(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// deletes blocks, transactions, messages, events and validator sets at or above the given height.
+    /// returns the number of deleted blocks.
+    /// deletes every block at or above the height, cursors of the chain are moved back below it.
+    fn delete_blocks_from(&self, chain_id: i32, height: i64) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<usize, Error, _>(|| {
+            let storage = SqliteStorage {
+                db: TransactionDB::new(&*conn),
+            };
+            let deleted = storage.delete_blocks_in_range(chain_id, height, i64::MAX)?;
+
+            diesel::update(
+                indexer_cursors::table.filter(
+                    indexer_cursors::chain_id
+                        .eq(chain_id)
+                        .and(indexer_cursors::height.ge(height)),
+                ),
+            )
+            .set(indexer_cursors::height.eq(height - 1))
+            .execute(&*conn)?;
+
+            Ok(deleted)
+        })
+    }
+
+    /// deletes blocks, transactions, messages, events and validator sets between the given heights (inclusive)
+    /// in a single transaction. returns the number of deleted blocks.
+    fn delete_blocks_in_range(
+        &self,
+        chain_id: i32,
+        from_height: i64,
+        to_height: i64,
+    ) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<usize, Error, _>(|| {
+            let tx_ids = transactions::table.select(transactions::id).filter(
+                transactions::chain_id
+                    .eq(chain_id)
+                    .and(transactions::height.between(from_height, to_height)),
+            );
+            diesel::delete(messages::table.filter(messages::transaction_id.eq_any(tx_ids)))
+                .execute(&*conn)?;
+            diesel::delete(
+                transaction_signers::table
+                    .filter(transaction_signers::transaction_id.eq_any(tx_ids)),
+            )
+            .execute(&*conn)?;
+            diesel::delete(
+                transaction_fees::table.filter(transaction_fees::transaction_id.eq_any(tx_ids)),
+            )
+            .execute(&*conn)?;
+
+            diesel::delete(
+                events::table.filter(
+                    events::chain_id
+                        .eq(chain_id)
+                        .and(events::block_height.between(from_height, to_height)),
+                ),
+            )
+            .execute(&*conn)?;
+
+            diesel::delete(
+                transactions::table.filter(
+                    transactions::chain_id
+                        .eq(chain_id)
+                        .and(transactions::height.between(from_height, to_height)),
+                ),
+            )
+            .execute(&*conn)?;
+
+            // members are removed by the cascading foreign key, foreign keys are enabled by `SqliteDB`.
+            diesel::delete(
+                validator_sets::table.filter(
+                    validator_sets::chain_id
+                        .eq(chain_id)
+                        .and(validator_sets::start_height.between(from_height, to_height)),
+                ),
+            )
+            .execute(&*conn)?;
+
+            diesel::delete(
+                blocks::table.filter(
+                    blocks::chain_id
+                        .eq(chain_id)
+                        .and(blocks::height.between(from_height, to_height)),
+                ),
+            )
+            .execute(&*conn)
+            .map_err(|e| e.into())
+        })
+    }
+
+    fn insert_chain(&self, chain: &NewChain) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        OnConflictDoNothing::new(diesel::insert_into(chains::table).values(chain), CHAINS_KEY)
+            .execute(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn insert_event(&self, event: &NewEvent) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        OnConflictDoNothing::new(diesel::insert_into(events::table).values(event), EVENTS_KEY)
+            .execute(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// inserts the events in a single transaction, attributes stored already are skipped.
+    fn insert_events(&self, events: &[NewEvent]) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<usize, Error, _>(|| {
+            let mut inserted = 0;
+            for event in events {
+                inserted += OnConflictDoNothing::new(
+                    diesel::insert_into(events::table).values(event),
+                    EVENTS_KEY,
+                )
+                .execute(&*conn)?;
+            }
+            Ok(inserted)
+        })
+    }
+
+    fn insert_transaction(&self, transaction: &NewTransaction) -> Result<Transaction, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<Transaction, Error, _>(|| upsert_transaction(&conn, transaction))
+    }
+
+    /// inserts the transactions in a single transaction, and returns the stored ones.
+    /// transactions stored already are returned as they are.
+    fn insert_transactions(
+        &self,
+        transactions: &[NewTransaction],
+    ) -> Result<Vec<Transaction>, Error> {
+        check_unique_transactions(transactions)?;
+        let conn = self.get_conn()?;
+        conn.transaction::<Vec<Transaction>, Error, _>(|| {
+            transactions
+                .iter()
+                .map(|transaction| upsert_transaction(&conn, transaction))
+                .collect()
+        })
+    }
+
+    fn insert_message(&self, message: &NewMessage) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        insert_message_json(&conn, message)
+    }
+
+    /// inserts the messages in a single transaction, messages stored already are skipped.
+    fn insert_messages(&self, messages: &[NewMessage]) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<usize, Error, _>(|| {
+            let mut inserted = 0;
+            for message in messages {
+                inserted += insert_message_json(&conn, message)?;
+            }
+            Ok(inserted)
+        })
+    }
+
+    fn insert_transaction_signer(&self, signer: &NewTransactionSigner) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        OnConflictDoNothing::new(
+            diesel::insert_into(transaction_signers::table).values(signer),
+            TRANSACTION_SIGNERS_KEY,
+        )
+        .execute(&*conn)
+        .map_err(|e| e.into())
+    }
+
+    /// inserts the signers in a single transaction, signers stored already are skipped.
+    fn insert_transaction_signers(&self, signers: &[NewTransactionSigner]) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<usize, Error, _>(|| {
+            let mut inserted = 0;
+            for signer in signers {
+                inserted += OnConflictDoNothing::new(
+                    diesel::insert_into(transaction_signers::table).values(signer),
+                    TRANSACTION_SIGNERS_KEY,
+                )
+                .execute(&*conn)?;
+            }
+            Ok(inserted)
+        })
+    }
+
+    /// inserts the fee coins in a single transaction, coins stored already are skipped.
+    fn insert_transaction_fees(&self, fees: &[NewTransactionFee]) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<usize, Error, _>(|| {
+            let mut inserted = 0;
+            for fee in fees {
+                inserted += OnConflictDoNothing::new(
+                    diesel::insert_into(transaction_fees::table).values(fee),
+                    TRANSACTION_FEES_KEY,
+                )
+                .execute(&*conn)?;
+            }
+            Ok(inserted)
+        })
+    }
+
+    /// inserts the account if it doesn't exist yet, and returns the stored account.
+    fn upsert_account(&self, account: &NewAccount) -> Result<Account, Error> {
+        let conn = self.get_conn()?;
+        let key = accounts::chain_id
+            .eq(account.chain_id)
+            .and(accounts::address.eq(&account.address));
+
+        conn.transaction::<Account, Error, _>(|| {
+            let inserted = OnConflictDoNothing::new(
+                diesel::insert_into(accounts::table).values(account),
+                ACCOUNTS_KEY,
+            )
+            .execute(&*conn)?;
+            if inserted == 0 {
+                diesel::update(accounts::table.filter(key))
+                    .set(accounts::updated_at.eq(Some(account.inserted_at)))
+                    .execute(&*conn)?;
+            }
+
+            accounts::table
+                .filter(key)
+                .first(&*conn)
+                .map_err(|e| e.into())
+        })
+    }
+
+    /// replaces the balances of the account with the given ones in a single transaction.
+    /// denoms which are not given any more are removed.
+    fn replace_account_balances(
+        &self,
+        account_id: i32,
+        balances: &[NewAccountBalance],
+    ) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<usize, Error, _>(|| {
+            let denoms = balances.iter().map(|b| b.denom.clone()).collect::<Vec<_>>();
+            diesel::delete(
+                account_balance::table.filter(
+                    account_balance::account_id
+                        .eq(account_id)
+                        .and(account_balance::denom.ne_all(denoms)),
+                ),
+            )
+            .execute(&*conn)?;
+
+            let mut updated = 0;
+            for balance in balances {
+                let inserted = OnConflictDoNothing::new(
+                    diesel::insert_into(account_balance::table).values(balance),
+                    ACCOUNT_BALANCE_KEY,
+                )
+                .execute(&*conn)?;
+                updated += match inserted {
+                    0 => diesel::update(
+                        account_balance::table.filter(
+                            account_balance::account_id
+                                .eq(balance.account_id)
+                                .and(account_balance::denom.eq(&balance.denom)),
+                        ),
+                    )
+                    .set((
+                        account_balance::amount.eq(&balance.amount),
+                        account_balance::updated_at.eq(Some(balance.inserted_at)),
+                    ))
+                    .execute(&*conn)?,
+                    inserted => inserted,
+                };
+            }
+
+            Ok(updated)
+        })
+    }
+
+    /// inserts the validator set and its members in a single transaction.
+    /// a set starting at the same height is replaced.
+    fn insert_validator_set(
+        &self,
+        validator_set: &NewValidatorSet,
+        members: &[NewValidatorSetMember],
+    ) -> Result<ValidatorSet, Error> {
+        let conn = self.get_conn()?;
+        let key = validator_sets::chain_id
+            .eq(validator_set.chain_id)
+            .and(validator_sets::start_height.eq(validator_set.start_height));
+
+        conn.transaction::<ValidatorSet, Error, _>(|| {
+            let inserted = OnConflictDoNothing::new(
+                diesel::insert_into(validator_sets::table).values(validator_set),
+                VALIDATOR_SETS_KEY,
+            )
+            .execute(&*conn)?;
+            if inserted == 0 {
+                diesel::update(validator_sets::table.filter(key))
+                    .set((
+                        validator_sets::validators_hash.eq(validator_set.validators_hash.clone()),
+                        validator_sets::updated_at.eq(Some(validator_set.inserted_at)),
+                    ))
+                    .execute(&*conn)?;
+            }
+            let new_set = validator_sets::table
+                .filter(key)
+                .first::<ValidatorSet>(&*conn)?;
+
+            // members of a replaced set are stored again.
+            diesel::delete(
+                validator_set_members::table
+                    .filter(validator_set_members::validator_set_id.eq(new_set.id)),
+            )
+            .execute(&*conn)?;
+
+            let members = members
+                .iter()
+                .cloned()
+                .map(|member| NewValidatorSetMember {
+                    validator_set_id: new_set.id,
+                    ..member
+                })
+                .collect::<Vec<_>>();
+            diesel::insert_into(validator_set_members::table)
+                .values(&members)
+                .execute(&*conn)?;
+
+            Ok(new_set)
+        })
+    }
+
+    /// stores the range, the end height and the reason are updated if it starts at a known height.
+    fn upsert_unindexable_range(
+        &self,
+        range: &NewUnindexableRange,
+    ) -> Result<UnindexableRange, Error> {
+        let conn = self.get_conn()?;
+        let key = unindexable_ranges::chain_id
+            .eq(range.chain_id)
+            .and(unindexable_ranges::start_height.eq(range.start_height));
+
+        conn.transaction::<UnindexableRange, Error, _>(|| {
+            let inserted = OnConflictDoNothing::new(
+                diesel::insert_into(unindexable_ranges::table).values(range),
+                UNINDEXABLE_RANGES_KEY,
+            )
+            .execute(&*conn)?;
+            if inserted == 0 {
+                diesel::update(unindexable_ranges::table.filter(key))
+                    .set((
+                        unindexable_ranges::end_height.eq(range.end_height),
+                        unindexable_ranges::reason.eq(range.reason.clone()),
+                        unindexable_ranges::updated_at.eq(Some(range.inserted_at)),
+                    ))
+                    .execute(&*conn)?;
+            }
+
+            unindexable_ranges::table
+                .filter(key)
+                .first(&*conn)
+                .map_err(|e| e.into())
+        })
+    }
+
+    /// stores the cursor, an existing one is only moved forward.
+    fn advance_indexer_cursor(&self, cursor: &NewIndexerCursor) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        diesel::sql_query(
+            "INSERT INTO indexer_cursors (chain_id, pipeline, height, inserted_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (chain_id, pipeline) DO UPDATE
+            SET height = MAX(indexer_cursors.height, excluded.height), updated_at = excluded.inserted_at",
+        )
+        .bind::<Integer, _>(cursor.chain_id)
+        .bind::<Text, _>(&cursor.pipeline)
+        .bind::<BigInt, _>(cursor.height)
+        .bind::<Timestamp, _>(cursor.inserted_at)
+        .execute(&*conn)
+        .map_err(|e| e.into())
+    }
+
+    /// stores the cursor as it is, it may move an existing one backward.
+    fn upsert_indexer_cursor(&self, cursor: &NewIndexerCursor) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        diesel::sql_query(
+            "INSERT INTO indexer_cursors (chain_id, pipeline, height, inserted_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (chain_id, pipeline) DO UPDATE
+            SET height = excluded.height, updated_at = excluded.inserted_at",
+        )
+        .bind::<Integer, _>(cursor.chain_id)
+        .bind::<Text, _>(&cursor.pipeline)
+        .bind::<BigInt, _>(cursor.height)
+        .bind::<Timestamp, _>(cursor.inserted_at)
+        .execute(&*conn)
+        .map_err(|e| e.into())
+    }
+
+    /// the write lock is taken upfront, so that the transaction never fails half-way on a busy database.
+    /// operations which run their own transaction are nested as savepoints.
+    fn within_transaction<R, F>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&dyn Storage) -> Result<R, Error>,
+    {
+        let conn = self.get_conn()?;
+        conn.immediate_transaction::<R, Error, _>(|| {
+            f(&SqliteStorage {
+                db: TransactionDB::new(&*conn),
+            })
+        })
+    }
+}
+
+impl<T: Database<SqliteConnection>> StorageReader for SqliteStorage<T> {
+    fn find_block_by_height(&self, chain_id: i32, height: i64) -> Result<Block, Error> {
+        let conn = self.get_conn()?;
+        blocks::table
+            .filter(blocks::chain_id.eq(chain_id).and(blocks::height.eq(height)))
+            .first(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn list_blocks(&self, chain_id: i32, limit: i64, offset: i64) -> Result<Vec<Block>, Error> {
+        let conn = self.get_conn()?;
+        blocks::table
+            .filter(blocks::chain_id.eq(chain_id))
+            .order(blocks::height.desc())
+            .limit(limit)
+            .offset(offset)
+            .load::<Block>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn find_latest_block(&self, chain_id: i32) -> Result<Block, Error> {
+        let conn = self.get_conn()?;
+        blocks::table
+            .filter(blocks::chain_id.eq(chain_id))
+            .order(blocks::height.desc())
+            .first(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// returns ranges of missing heights between `from_height` and the latest stored block.
+    fn find_block_gaps(&self, chain_id: i32, from_height: i64) -> Result<Vec<BlockGap>, Error> {
+        let conn = self.get_conn()?;
+        // `from_height - 1` is added as a sentinel so that a gap right after `from_height` is detected as well.
+        diesel::sql_query(
+            "SELECT height + 1 AS start_height, next_height - 1 AS end_height
+            FROM (
+                SELECT height, LEAD(height) OVER (ORDER BY height) AS next_height
+                FROM (
+                    SELECT height FROM blocks WHERE chain_id = ?1 AND height >= ?2
+                    UNION ALL
+                    SELECT ?2 - 1
+                ) AS heights
+            ) AS neighbours
+            WHERE next_height - height > 1
+            ORDER BY start_height",
+        )
+        .bind::<Integer, _>(chain_id)
+        .bind::<BigInt, _>(from_height)
+        .load::<BlockGap>(&*conn)
+        .map_err(|e| e.into())
+    }
+
+    /// returns the stored blocks between the given heights (inclusive) in ascending order.
+    fn list_blocks_in_range(
+        &self,
+        chain_id: i32,
+        from_height: i64,
+        to_height: i64,
+        limit: i64,
+    ) -> Result<Vec<Block>, Error> {
+        let conn = self.get_conn()?;
+        blocks::table
+            .filter(
+                blocks::chain_id
+                    .eq(chain_id)
+                    .and(blocks::height.between(from_height, to_height)),
+            )
+            .order(blocks::height.asc())
+            .limit(limit)
+            .load::<Block>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn find_by_chain_id(&self, chain_id: String) -> Result<Chain, Error> {
+        let conn = self.get_conn()?;
+        chains::table
+            .filter(chains::chain_id.eq(chain_id))
+            .first(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn all_chains(&self) -> Result<Vec<Chain>, Error> {
+        let conn = self.get_conn()?;
+        chains::table.load::<Chain>(&*conn).map_err(|e| e.into())
+    }
+
+    fn list_transactions(&self, chain_id: i32, height: i64) -> Result<Vec<Transaction>, Error> {
+        let conn = self.get_conn()?;
+        transactions::table
+            .filter(
+                transactions::height
+                    .eq(height)
+                    .and(transactions::chain_id.eq(chain_id)),
+            )
+            .load::<Transaction>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn find_transaction_by_hash(
+        &self,
+        chain_id: i32,
+        tx_hash: String,
+    ) -> Result<Transaction, Error> {
+        let conn = self.get_conn()?;
+        transactions::table
+            .filter(
+                transactions::chain_id
+                    .eq(chain_id)
+                    .and(transactions::transaction_hash.eq(tx_hash)),
+            )
+            .first(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// returns the transactions with the given hash in every chain, ordered by chain.
+    fn list_transactions_by_hash(&self, tx_hash: String) -> Result<Vec<Transaction>, Error> {
+        let conn = self.get_conn()?;
+        transactions::table
+            .filter(transactions::transaction_hash.eq(tx_hash))
+            .order(transactions::chain_id.asc())
+            .load::<Transaction>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// the raw data is read as JSON text and parsed.
+    fn list_messages_by_tx(&self, tx_id: i32) -> Result<Vec<Message>, Error> {
+        let conn = self.get_conn()?;
+        let rows = messages::table
+            .select((
+                messages::id,
+                messages::transaction_id,
+                messages::seq,
+                diesel::dsl::sql::<Text>("rawdata"),
+                messages::inserted_at,
+                messages::updated_at,
+            ))
+            .filter(messages::transaction_id.eq(tx_id))
+            .load::<(
+                i32,
+                i32,
+                i32,
+                String,
+                chrono::NaiveDateTime,
+                Option<chrono::NaiveDateTime>,
+            )>(&*conn)?;
+
+        rows.into_iter()
+            .map(
+                |(id, transaction_id, seq, rawdata, inserted_at, updated_at)| {
+                    let rawdata = serde_json::from_str(&rawdata)
+                        .map_err(|e| DieselError::DeserializationError(Box::new(e)))?;
+                    Ok(Message {
+                        id,
+                        transaction_id,
+                        seq,
+                        rawdata,
+                        inserted_at,
+                        updated_at,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    fn list_signers_by_tx(&self, tx_id: i32) -> Result<Vec<TransactionSigner>, Error> {
+        let conn = self.get_conn()?;
+        transaction_signers::table
+            .filter(transaction_signers::transaction_id.eq(tx_id))
+            .order(transaction_signers::signer_seq.asc())
+            .load::<TransactionSigner>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn list_fees_by_tx(&self, tx_id: i32) -> Result<Vec<TransactionFee>, Error> {
+        let conn = self.get_conn()?;
+        transaction_fees::table
+            .filter(transaction_fees::transaction_id.eq(tx_id))
+            .order(transaction_fees::seq.asc())
+            .load::<TransactionFee>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn list_events_by_tx(&self, chain_id: i32, tx_hash: String) -> Result<Vec<Event>, Error> {
+        let conn = self.get_conn()?;
+        events::table
+            .filter(
+                events::chain_id
+                    .eq(chain_id)
+                    .and(events::tx_hash.eq(tx_hash)),
+            )
+            .load::<Event>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn find_account_by_address(&self, chain_id: i32, address: String) -> Result<Account, Error> {
+        let conn = self.get_conn()?;
+        accounts::table
+            .filter(
+                accounts::chain_id
+                    .eq(chain_id)
+                    .and(accounts::address.eq(address)),
+            )
+            .first(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn list_account_balances(&self, account_id: i32) -> Result<Vec<AccountBalance>, Error> {
+        let conn = self.get_conn()?;
+        account_balance::table
+            .filter(account_balance::account_id.eq(account_id))
+            .order(account_balance::denom.asc())
+            .load::<AccountBalance>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// returns the validator set which is active at the given height,
+    /// that is the latest one starting at or below it.
+    fn find_validator_set_at_height(
+        &self,
+        chain_id: i32,
+        height: i64,
+    ) -> Result<ValidatorSet, Error> {
+        let conn = self.get_conn()?;
+        validator_sets::table
+            .filter(
+                validator_sets::chain_id
+                    .eq(chain_id)
+                    .and(validator_sets::start_height.le(height)),
+            )
+            .order(validator_sets::start_height.desc())
+            .first(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// returns the members of the validator set, sorted by voting power in descending order.
+    fn list_validator_set_members(
+        &self,
+        validator_set_id: i32,
+    ) -> Result<Vec<ValidatorSetMember>, Error> {
+        let conn = self.get_conn()?;
+        validator_set_members::table
+            .filter(validator_set_members::validator_set_id.eq(validator_set_id))
+            .order((
+                validator_set_members::voting_power.desc(),
+                validator_set_members::address.asc(),
+            ))
+            .load::<ValidatorSetMember>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// returns the unindexable ranges of the chain, ordered by start height.
+    fn list_unindexable_ranges(&self, chain_id: i32) -> Result<Vec<UnindexableRange>, Error> {
+        let conn = self.get_conn()?;
+        unindexable_ranges::table
+            .filter(unindexable_ranges::chain_id.eq(chain_id))
+            .order(unindexable_ranges::start_height.asc())
+            .load::<UnindexableRange>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn find_indexer_cursor(&self, chain_id: i32, pipeline: &str) -> Result<IndexerCursor, Error> {
+        let conn = self.get_conn()?;
+        indexer_cursors::table
+            .filter(
+                indexer_cursors::chain_id
+                    .eq(chain_id)
+                    .and(indexer_cursors::pipeline.eq(pipeline)),
+            )
+            .first(&*conn)
+            .map_err(|e| e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::NaiveDateTime;
+    use diesel::connection::SimpleConnection;
+
+    use super::*;
+    use crate::config::{DBConfig, DBDriver};
+
+    /// TempFile removes the database file and its WAL files once the test is over.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn remove(&self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.0.display(), suffix));
+            }
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            self.remove();
+        }
+    }
+
+    /// creates a storage on a fresh database file with the sqlite schema applied.
+    fn new_storage(name: &str) -> (SqliteStorage<SqliteDB>, TempFile) {
+        let path =
+            std::env::temp_dir().join(format!("cosmscan-{}-{}.sqlite", std::process::id(), name));
+        // a file left behind by an aborted run is started over.
+        let file = TempFile(path.clone());
+        file.remove();
+
+        let storage = SqliteStorage::new(SqliteDB::new(DBConfig {
+            driver: Some(DBDriver::Sqlite),
+            host: String::new(),
+            port: 0,
+            user: String::new(),
+            password: String::new(),
+            database: path.display().to_string(),
+            max_connections: Some(2),
+        }));
+        storage
+            .get_conn()
+            .unwrap()
+            .batch_execute(include_str!(
+                "../migrations_sqlite/2026-10-18-000001_initial_schema/up.sql"
+            ))
+            .unwrap();
+
+        (storage, file)
+    }
+
+    fn new_block(height: i64) -> NewBlock {
+        NewBlock {
+            chain_id: 1,
+            height,
+            block_hash: format!("HASH{}", height),
+            prev_hash: format!("HASH{}", height - 1),
+            proposer_address: String::new(),
+            last_commit_hash: String::new(),
+            data_hash: String::new(),
+            validators_hash: String::new(),
+            next_validators_hash: String::new(),
+            consensus_hash: String::new(),
+            app_hash: String::new(),
+            last_result_hash: String::new(),
+            evidence_hash: String::new(),
+            block_time: NaiveDateTime::from_timestamp(0, 0),
+            inserted_at: NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+
+    fn new_transaction(height: i64, hash: &str) -> NewTransaction {
+        NewTransaction {
+            chain_id: 1,
+            transaction_hash: hash.to_string(),
+            height,
+            code: 0,
+            code_space: String::new(),
+            tx_data: String::new(),
+            raw_log: String::new(),
+            info: String::new(),
+            memo: None,
+            gas_wanted: 0,
+            gas_used: 0,
+            tx_timestamp: String::new(),
+            gas_limit: 0,
+            fee_payer: None,
+            fee_granter: None,
+            inserted_at: NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+
+    fn new_event(height: i64, hash: &str) -> NewEvent {
+        NewEvent {
+            chain_id: 1,
+            tx_type: 0,
+            tx_hash: Some(hash.to_string()),
+            block_height: height,
+            event_seq: 0,
+            attr_seq: 0,
+            event_type: "transfer".to_string(),
+            event_key: "amount".to_string(),
+            event_value: "1uatom".to_string(),
+            indexed: true,
+            inserted_at: NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+
+    fn new_message(transaction_id: i32) -> NewMessage {
+        NewMessage {
+            transaction_id,
+            seq: 0,
+            rawdata: serde_json::json!({ "@type": "/cosmos.bank.v1beta1.MsgSend" }),
+            inserted_at: NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+
+    fn new_cursor(pipeline: &str, height: i64) -> NewIndexerCursor {
+        NewIndexerCursor {
+            chain_id: 1,
+            pipeline: pipeline.to_string(),
+            height,
+            inserted_at: NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+
+    fn new_balance(account_id: i32, denom: &str, amount: &str) -> NewAccountBalance {
+        NewAccountBalance {
+            account_id,
+            amount: amount.to_string(),
+            denom: denom.to_string(),
+            inserted_at: NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+
+    /// stores the blocks of the given heights, each with a transaction, a message and an event.
+    fn store_blocks(storage: &SqliteStorage<SqliteDB>, heights: &[i64]) {
+        let blocks = heights.iter().map(|h| new_block(*h)).collect::<Vec<_>>();
+        storage.insert_blocks(&blocks).unwrap();
+        for height in heights {
+            let hash = format!("TX{}", height);
+            let tx = storage
+                .insert_transaction(&new_transaction(*height, &hash))
+                .unwrap();
+            storage.insert_message(&new_message(tx.id)).unwrap();
+            storage.insert_event(&new_event(*height, &hash)).unwrap();
+        }
+    }
+
+    fn gaps(storage: &SqliteStorage<SqliteDB>, from_height: i64) -> Vec<(i64, i64)> {
+        storage
+            .find_block_gaps(1, from_height)
+            .unwrap()
+            .into_iter()
+            .map(|gap| (gap.start_height, gap.end_height))
+            .collect()
+    }
+
+    #[test]
+    fn a_recommitted_batch_keeps_the_stored_rows() {
+        let (storage, _file) = new_storage("recommit");
+
+        let blocks = [new_block(1), new_block(2)];
+        assert_eq!(storage.insert_blocks(&blocks).unwrap(), 2);
+        assert_eq!(storage.insert_blocks(&blocks).unwrap(), 0);
+
+        let txs = [new_transaction(1, "TX1"), new_transaction(2, "TX2")];
+        let first = storage.insert_transactions(&txs).unwrap();
+        let second = storage.insert_transactions(&txs).unwrap();
+        assert_eq!(
+            first.iter().map(|tx| tx.id).collect::<Vec<_>>(),
+            second.iter().map(|tx| tx.id).collect::<Vec<_>>()
+        );
+
+        let messages = [new_message(first[0].id)];
+        assert_eq!(storage.insert_messages(&messages).unwrap(), 1);
+        assert_eq!(storage.insert_messages(&messages).unwrap(), 0);
+
+        let events = [new_event(1, "TX1"), new_event(2, "TX2")];
+        assert_eq!(storage.insert_events(&events).unwrap(), 2);
+        assert_eq!(storage.insert_events(&events).unwrap(), 0);
+
+        assert_eq!(storage.latest_block_height(1).unwrap(), 2);
+        assert_eq!(storage.list_transactions(1, 1).unwrap().len(), 1);
+        assert_eq!(storage.list_messages_by_tx(first[0].id).unwrap().len(), 1);
+        assert_eq!(
+            storage
+                .list_events_by_tx(1, "TX1".to_string())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn finds_the_gaps_from_the_given_height() {
+        let (storage, _file) = new_storage("gaps");
+        assert!(gaps(&storage, 1).is_empty());
+
+        storage
+            .insert_blocks(&[3, 4, 7, 10].map(new_block))
+            .unwrap();
+
+        // the sentinel row reports the missing heights right after `from_height`.
+        assert_eq!(gaps(&storage, 1), vec![(1, 2), (5, 6), (8, 9)]);
+        assert_eq!(gaps(&storage, 3), vec![(5, 6), (8, 9)]);
+        assert_eq!(gaps(&storage, 6), vec![(6, 6), (8, 9)]);
+        assert!(gaps(&storage, 10).is_empty());
+    }
+
+    #[test]
+    fn deletes_a_range_with_its_rows() {
+        let (storage, _file) = new_storage("range");
+        store_blocks(&storage, &[1, 2, 3, 4]);
+        let deleted_tx = storage
+            .find_transaction_by_hash(1, "TX2".to_string())
+            .unwrap();
+
+        assert_eq!(storage.delete_blocks_in_range(1, 2, 3).unwrap(), 2);
+
+        assert_eq!(gaps(&storage, 1), vec![(2, 3)]);
+        assert!(storage
+            .find_transaction_by_hash(1, "TX2".to_string())
+            .is_err());
+        assert!(storage
+            .list_messages_by_tx(deleted_tx.id)
+            .unwrap()
+            .is_empty());
+        assert!(storage
+            .list_events_by_tx(1, "TX2".to_string())
+            .unwrap()
+            .is_empty());
+        assert!(storage
+            .find_transaction_by_hash(1, "TX4".to_string())
+            .is_ok());
+        assert_eq!(
+            storage
+                .list_events_by_tx(1, "TX4".to_string())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn deleting_from_a_height_moves_the_cursors_back() {
+        let (storage, _file) = new_storage("rollback");
+        store_blocks(&storage, &[1, 2, 3, 4, 5]);
+        storage
+            .advance_indexer_cursor(&new_cursor("tip", 5))
+            .unwrap();
+        storage
+            .advance_indexer_cursor(&new_cursor("backfill", 2))
+            .unwrap();
+
+        assert_eq!(storage.delete_blocks_from(1, 4).unwrap(), 2);
+
+        assert_eq!(storage.latest_block_height(1).unwrap(), 3);
+        assert!(storage
+            .find_transaction_by_hash(1, "TX4".to_string())
+            .is_err());
+        assert_eq!(storage.find_indexer_cursor(1, "tip").unwrap().height, 3);
+        assert_eq!(
+            storage.find_indexer_cursor(1, "backfill").unwrap().height,
+            2
+        );
+
+        // a rolled back cursor moves forward again.
+        storage
+            .advance_indexer_cursor(&new_cursor("tip", 4))
+            .unwrap();
+        assert_eq!(storage.find_indexer_cursor(1, "tip").unwrap().height, 4);
+    }
+
+    #[test]
+    fn deleting_a_validator_set_deletes_its_members() {
+        let (storage, _file) = new_storage("validators");
+        store_blocks(&storage, &[1, 2, 3]);
+
+        let validator_set = NewValidatorSet {
+            chain_id: 1,
+            start_height: 2,
+            validators_hash: "VALS".to_string(),
+            inserted_at: NaiveDateTime::from_timestamp(0, 0),
+        };
+        let members = ["VAL1", "VAL2"]
+            .iter()
+            .map(|address| NewValidatorSetMember {
+                validator_set_id: 0,
+                address: address.to_string(),
+                pub_key_type: "ed25519".to_string(),
+                pub_key: String::new(),
+                voting_power: 10,
+                proposer_priority: 0,
+                inserted_at: NaiveDateTime::from_timestamp(0, 0),
+            })
+            .collect::<Vec<_>>();
+
+        let stored = storage
+            .insert_validator_set(&validator_set, &members)
+            .unwrap();
+        let replaced = storage
+            .insert_validator_set(&validator_set, &members[..1])
+            .unwrap();
+        assert_eq!(stored.id, replaced.id);
+        assert_eq!(
+            storage.list_validator_set_members(stored.id).unwrap().len(),
+            1
+        );
+
+        storage.delete_blocks_from(1, 2).unwrap();
+
+        assert!(storage.find_validator_set_at_height(1, 3).is_err());
+        let remaining: i64 = validator_set_members::table
+            .count()
+            .get_result(&*storage.get_conn().unwrap())
+            .unwrap();
+        assert_eq!(remaining, 0);
+    }
+
+    #[test]
+    fn replaces_the_balances_of_an_account() {
+        let (storage, _file) = new_storage("balances");
+        let account = storage
+            .upsert_account(&NewAccount {
+                chain_id: 1,
+                address: "cosmos1address".to_string(),
+                inserted_at: NaiveDateTime::from_timestamp(0, 0),
+            })
+            .unwrap();
+
+        storage
+            .replace_account_balances(
+                account.id,
+                &[
+                    new_balance(account.id, "uatom", "10"),
+                    new_balance(account.id, "uosmo", "5"),
+                ],
+            )
+            .unwrap();
+        storage
+            .replace_account_balances(account.id, &[new_balance(account.id, "uatom", "20")])
+            .unwrap();
+
+        let balances = storage
+            .list_account_balances(account.id)
+            .unwrap()
+            .into_iter()
+            .map(|b| (b.denom, b.amount))
+            .collect::<Vec<_>>();
+        assert_eq!(balances, vec![("uatom".to_string(), "20".to_string())]);
+
+        storage.replace_account_balances(account.id, &[]).unwrap();
+        assert!(storage
+            .list_account_balances(account.id)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,14 +1,25 @@
 use crate::{
-    db::{BackendDB, Database},
+    db::{BackendDB, Database, TransactionDB},
     errors::Error,
     models::{
+        account::{Account, NewAccount},
+        account_balance::{AccountBalance, NewAccountBalance},
         block::NewBlock,
         chain::{Chain, NewChain},
         event::NewEvent,
+        indexer_cursor::{IndexerCursor, NewIndexerCursor},
         message::{Message, NewMessage},
         transaction::NewTransaction,
+        transaction_fee::{NewTransactionFee, TransactionFee},
+        transaction_signer::{NewTransactionSigner, TransactionSigner},
+        unindexable_range::{NewUnindexableRange, UnindexableRange},
+        validator_set::{NewValidatorSet, NewValidatorSetMember, ValidatorSet, ValidatorSetMember},
+    },
+    schema::{
+        account_balance, accounts, blocks, chains, events, indexer_cursors, messages,
+        transaction_fees, transaction_signers, transactions, unindexable_ranges,
+        validator_set_members, validator_sets,
     },
-    schema::{blocks, chains, events, messages, transactions},
 };
 
 use crate::schema::blocks::dsl::blocks as all_blocks;
@@ -17,13 +28,28 @@ use crate::schema::events::dsl::events as all_events;
 use crate::schema::messages::dsl::messages as all_messages;
 use crate::schema::transactions::dsl::transactions as all_transactions;
 
-use crate::models::block::Block;
+use crate::models::block::{Block, BlockGap};
 use crate::models::event::Event;
 use crate::models::transaction::Transaction;
-use diesel::{prelude::*, r2d2::ConnectionManager};
-use r2d2::PooledConnection;
-
-type Connection = PooledConnection<ConnectionManager<PgConnection>>;
+use diesel::pg::upsert::excluded;
+use diesel::prelude::*;
+use std::collections::HashSet;
+
+/// maximum number of rows in a single multi-row insert.
+/// it keeps the bind parameters of the widest table below the limit of postgres (65535).
+const MAX_ROWS_PER_INSERT: usize = 2000;
+
+/// refuses a batch which carries the same transaction hash of a chain more than once.
+/// postgres can't upsert a row twice in a single statement, every storage refuses it the same way.
+pub(crate) fn check_unique_transactions(transactions: &[NewTransaction]) -> Result<(), Error> {
+    let mut keys = HashSet::with_capacity(transactions.len());
+    for tx in transactions {
+        if !keys.insert((tx.chain_id, tx.transaction_hash.as_str())) {
+            return Err(Error::DuplicateTransaction(tx.transaction_hash.clone()));
+        }
+    }
+    Ok(())
+}
 
 /// StorageReader defines a set of methods for reading the database
 pub trait StorageReader {
@@ -31,6 +57,14 @@ pub trait StorageReader {
     fn find_block_by_height(&self, chain_id: i32, height: i64) -> Result<Block, Error>;
     fn list_blocks(&self, chain_id: i32, limit: i64, offset: i64) -> Result<Vec<Block>, Error>;
     fn find_latest_block(&self, chain_id: i32) -> Result<Block, Error>;
+    fn find_block_gaps(&self, chain_id: i32, from_height: i64) -> Result<Vec<BlockGap>, Error>;
+    fn list_blocks_in_range(
+        &self,
+        chain_id: i32,
+        from_height: i64,
+        to_height: i64,
+        limit: i64,
+    ) -> Result<Vec<Block>, Error>;
 
     // chain operations
     fn find_by_chain_id(&self, chain_id: String) -> Result<Chain, Error>;
@@ -42,34 +76,124 @@ pub trait StorageReader {
         chain_id: i32,
         block_height: i64,
     ) -> Result<Vec<Transaction>, Error>;
-    fn find_transaction_by_hash(&self, tx_hash: String) -> Result<Transaction, Error>;
+    fn find_transaction_by_hash(
+        &self,
+        chain_id: i32,
+        tx_hash: String,
+    ) -> Result<Transaction, Error>;
+    fn list_transactions_by_hash(&self, tx_hash: String) -> Result<Vec<Transaction>, Error>;
 
     // message operations
     fn list_messages_by_tx(&self, tx_id: i32) -> Result<Vec<Message>, Error>;
 
+    // transaction signer operations
+    fn list_signers_by_tx(&self, tx_id: i32) -> Result<Vec<TransactionSigner>, Error>;
+
+    // transaction fee operations
+    fn list_fees_by_tx(&self, tx_id: i32) -> Result<Vec<TransactionFee>, Error>;
+
     // event operations
-    fn list_events_by_tx(&self, tx_hash: String) -> Result<Vec<Event>, Error>;
+    fn list_events_by_tx(&self, chain_id: i32, tx_hash: String) -> Result<Vec<Event>, Error>;
+
+    // account operations
+    fn find_account_by_address(&self, chain_id: i32, address: String) -> Result<Account, Error>;
+    fn list_account_balances(&self, account_id: i32) -> Result<Vec<AccountBalance>, Error>;
+
+    // validator set operations
+    fn find_validator_set_at_height(
+        &self,
+        chain_id: i32,
+        height: i64,
+    ) -> Result<ValidatorSet, Error>;
+    fn list_validator_set_members(
+        &self,
+        validator_set_id: i32,
+    ) -> Result<Vec<ValidatorSetMember>, Error>;
+
+    // unindexable range operations
+    fn list_unindexable_ranges(&self, chain_id: i32) -> Result<Vec<UnindexableRange>, Error>;
+
+    // indexer cursor operations
+    fn find_indexer_cursor(&self, chain_id: i32, pipeline: &str) -> Result<IndexerCursor, Error>;
 }
 
 /// StorageWriter defines a set of method for writing/updating the database.
 pub trait StorageWriter {
     // block operations
     fn insert_block(&self, block: &NewBlock) -> Result<usize, Error>;
+    fn insert_blocks(&self, blocks: &[NewBlock]) -> Result<usize, Error>;
     fn latest_block_height(&self, chain_id: i32) -> Result<i64, Error>;
+    fn delete_blocks_from(&self, chain_id: i32, height: i64) -> Result<usize, Error>;
+    fn delete_blocks_in_range(
+        &self,
+        chain_id: i32,
+        from_height: i64,
+        to_height: i64,
+    ) -> Result<usize, Error>;
 
     // chain operations
     fn insert_chain(&self, chain: &NewChain) -> Result<usize, Error>;
 
     // event operations
     fn insert_event(&self, event: &NewEvent) -> Result<usize, Error>;
+    fn insert_events(&self, events: &[NewEvent]) -> Result<usize, Error>;
 
     // transaction operations
     fn insert_transaction(&self, transaction: &NewTransaction) -> Result<Transaction, Error>;
+    /// a hash must not be given more than once, see [`Error::DuplicateTransaction`].
+    fn insert_transactions(
+        &self,
+        transactions: &[NewTransaction],
+    ) -> Result<Vec<Transaction>, Error>;
 
     // message operations
     fn insert_message(&self, message: &NewMessage) -> Result<usize, Error>;
+    fn insert_messages(&self, messages: &[NewMessage]) -> Result<usize, Error>;
+
+    // transaction signer operations
+    fn insert_transaction_signer(&self, signer: &NewTransactionSigner) -> Result<usize, Error>;
+    fn insert_transaction_signers(&self, signers: &[NewTransactionSigner]) -> Result<usize, Error>;
+
+    // transaction fee operations
+    fn insert_transaction_fees(&self, fees: &[NewTransactionFee]) -> Result<usize, Error>;
+
+    // account operations
+    fn upsert_account(&self, account: &NewAccount) -> Result<Account, Error>;
+    fn replace_account_balances(
+        &self,
+        account_id: i32,
+        balances: &[NewAccountBalance],
+    ) -> Result<usize, Error>;
+
+    // validator set operations
+    fn insert_validator_set(
+        &self,
+        validator_set: &NewValidatorSet,
+        members: &[NewValidatorSetMember],
+    ) -> Result<ValidatorSet, Error>;
+
+    // unindexable range operations
+    fn upsert_unindexable_range(
+        &self,
+        range: &NewUnindexableRange,
+    ) -> Result<UnindexableRange, Error>;
+
+    // indexer cursor operations
+    fn advance_indexer_cursor(&self, cursor: &NewIndexerCursor) -> Result<usize, Error>;
+    fn upsert_indexer_cursor(&self, cursor: &NewIndexerCursor) -> Result<usize, Error>;
+
+    /// runs `f` in a single transaction, every operation on the given storage is a part of it.
+    fn within_transaction<R, F>(&self, f: F) -> Result<R, Error>
+    where
+        Self: Sized,
+        F: FnOnce(&dyn Storage) -> Result<R, Error>;
 }
 
+/// Storage is implemented by every storage which can both read and write.
+pub trait Storage: StorageReader + StorageWriter {}
+
+impl<T: StorageReader + StorageWriter> Storage for T {}
+
 /// PersistenceStorage should implements both [`StorageWriter`] and [`StorageReader`]
 pub struct PersistenceStorage<T>
 where
@@ -79,42 +203,49 @@ where
 }
 
 impl PersistenceStorage<BackendDB> {
+    /// creates a storage on top of the given database.
+    /// the connection pool is reused if the database has been connected already.
     pub fn new(mut db: BackendDB) -> Self {
-        let connected = db.connect();
+        let connected = db.client.is_some() || db.connect();
         if !connected {
             panic!("failed to connect to the database, please check configuration")
         }
 
         Self { db }
     }
+}
 
-    pub fn get_conn(&self) -> Result<Connection, Error> {
-        match self.db.conn() {
-            Some(conn) => Ok(conn),
-            None => Err(Error::ClientDoesntExists),
-        }
-    }
-
-    pub fn within_transaction<F>(&self, f: F) -> Result<bool, Error>
-    where
-        F: FnOnce() -> Result<bool, Error>,
-    {
-        let conn = self.get_conn()?;
-        conn.build_transaction()
-            .repeatable_read()
-            .run::<bool, Error, _>(|| f())
+impl<T: Database> PersistenceStorage<T> {
+    pub fn get_conn(&self) -> Result<T::Connection, Error> {
+        self.db.conn()
     }
 }
 
-impl StorageWriter for PersistenceStorage<BackendDB> {
+impl<T: Database> StorageWriter for PersistenceStorage<T> {
     fn insert_block(&self, block: &NewBlock) -> Result<usize, Error> {
         let conn = self.get_conn()?;
         diesel::insert_into(blocks::table)
             .values(block)
-            .execute(&conn)
+            .on_conflict((blocks::chain_id, blocks::height))
+            .do_nothing()
+            .execute(&*conn)
             .map_err(|e| e.into())
     }
 
+    /// inserts the blocks with multi-row statements, heights stored already are left as they are.
+    fn insert_blocks(&self, blocks: &[NewBlock]) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        let mut inserted = 0;
+        for chunk in blocks.chunks(MAX_ROWS_PER_INSERT) {
+            inserted += diesel::insert_into(blocks::table)
+                .values(chunk)
+                .on_conflict((blocks::chain_id, blocks::height))
+                .do_nothing()
+                .execute(&*conn)?;
+        }
+        Ok(inserted)
+    }
+
     fn latest_block_height(&self, chain_id: i32) -> Result<i64, Error> {
         let conn = self.get_conn()?;
         blocks::table
@@ -122,15 +253,109 @@ impl StorageWriter for PersistenceStorage<BackendDB> {
             .filter(blocks::chain_id.eq(chain_id))
             .order(blocks::height.desc())
             .limit(1)
-            .first(&conn)
+            .first(&*conn)
             .map_err(|e| e.into())
     }
 
+    /// deletes blocks, transactions, messages, events and validator sets at or above the given height.
+    /// returns the number of deleted blocks.
+    /// deletes every block at or above the height, cursors of the chain are moved back below it.
+    fn delete_blocks_from(&self, chain_id: i32, height: i64) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<usize, Error, _>(|| {
+            let storage = PersistenceStorage {
+                db: TransactionDB::new(&*conn),
+            };
+            let deleted = storage.delete_blocks_in_range(chain_id, height, i64::MAX)?;
+
+            diesel::update(
+                indexer_cursors::table.filter(
+                    indexer_cursors::chain_id
+                        .eq(chain_id)
+                        .and(indexer_cursors::height.ge(height)),
+                ),
+            )
+            .set(indexer_cursors::height.eq(height - 1))
+            .execute(&*conn)?;
+
+            Ok(deleted)
+        })
+    }
+
+    /// deletes blocks, transactions, messages, events and validator sets between the given heights (inclusive)
+    /// in a single transaction. returns the number of deleted blocks.
+    fn delete_blocks_in_range(
+        &self,
+        chain_id: i32,
+        from_height: i64,
+        to_height: i64,
+    ) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<usize, Error, _>(|| {
+            let tx_ids = transactions::table.select(transactions::id).filter(
+                transactions::chain_id
+                    .eq(chain_id)
+                    .and(transactions::height.between(from_height, to_height)),
+            );
+            diesel::delete(messages::table.filter(messages::transaction_id.eq_any(tx_ids)))
+                .execute(&*conn)?;
+            diesel::delete(
+                transaction_signers::table
+                    .filter(transaction_signers::transaction_id.eq_any(tx_ids)),
+            )
+            .execute(&*conn)?;
+            diesel::delete(
+                transaction_fees::table.filter(transaction_fees::transaction_id.eq_any(tx_ids)),
+            )
+            .execute(&*conn)?;
+
+            diesel::delete(
+                events::table.filter(
+                    events::chain_id
+                        .eq(chain_id)
+                        .and(events::block_height.between(from_height, to_height)),
+                ),
+            )
+            .execute(&*conn)?;
+
+            diesel::delete(
+                transactions::table.filter(
+                    transactions::chain_id
+                        .eq(chain_id)
+                        .and(transactions::height.between(from_height, to_height)),
+                ),
+            )
+            .execute(&*conn)?;
+
+            // members are removed by the cascading foreign key.
+            diesel::delete(
+                validator_sets::table.filter(
+                    validator_sets::chain_id
+                        .eq(chain_id)
+                        .and(validator_sets::start_height.between(from_height, to_height)),
+                ),
+            )
+            .execute(&*conn)?;
+
+            diesel::delete(
+                blocks::table.filter(
+                    blocks::chain_id
+                        .eq(chain_id)
+                        .and(blocks::height.between(from_height, to_height)),
+                ),
+            )
+            .execute(&*conn)
+            .map_err(|e| e.into())
+        })
+    }
+
     fn insert_chain(&self, chain: &NewChain) -> Result<usize, Error> {
         let conn = self.get_conn()?;
         diesel::insert_into(chains::table)
             .values(chain)
-            .execute(&conn)
+            .on_conflict(chains::chain_id)
+            .do_nothing()
+            .execute(&*conn)
             .map_err(|e| e.into())
     }
 
@@ -138,33 +363,295 @@ impl StorageWriter for PersistenceStorage<BackendDB> {
         let conn = self.get_conn()?;
         diesel::insert_into(events::table)
             .values(event)
-            .execute(&conn)
+            .on_conflict_do_nothing()
+            .execute(&*conn)
             .map_err(|e| e.into())
     }
 
+    /// inserts the events with multi-row statements, attributes stored already are skipped.
+    fn insert_events(&self, events: &[NewEvent]) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        let mut inserted = 0;
+        for chunk in events.chunks(MAX_ROWS_PER_INSERT) {
+            inserted += diesel::insert_into(events::table)
+                .values(chunk)
+                .on_conflict_do_nothing()
+                .execute(&*conn)?;
+        }
+        Ok(inserted)
+    }
+
     fn insert_transaction(&self, transaction: &NewTransaction) -> Result<Transaction, Error> {
         let conn = self.get_conn()?;
         diesel::insert_into(transactions::table)
             .values(transaction)
-            .get_result::<Transaction>(&conn)
+            .on_conflict((transactions::chain_id, transactions::transaction_hash))
+            .do_update()
+            .set(transactions::updated_at.eq(Some(transaction.inserted_at)))
+            .get_result::<Transaction>(&*conn)
             .map_err(|e| e.into())
     }
 
+    /// inserts the transactions with multi-row statements, and returns the stored ones.
+    /// transactions stored already are returned as they are.
+    fn insert_transactions(
+        &self,
+        transactions: &[NewTransaction],
+    ) -> Result<Vec<Transaction>, Error> {
+        check_unique_transactions(transactions)?;
+        let conn = self.get_conn()?;
+        let mut inserted = Vec::with_capacity(transactions.len());
+        for chunk in transactions.chunks(MAX_ROWS_PER_INSERT) {
+            inserted.extend(
+                diesel::insert_into(transactions::table)
+                    .values(chunk)
+                    .on_conflict((transactions::chain_id, transactions::transaction_hash))
+                    .do_update()
+                    .set(
+                        transactions::updated_at.eq(excluded(transactions::inserted_at).nullable()),
+                    )
+                    .get_results::<Transaction>(&*conn)?,
+            );
+        }
+        Ok(inserted)
+    }
+
     fn insert_message(&self, message: &NewMessage) -> Result<usize, Error> {
         let conn = self.get_conn()?;
         diesel::insert_into(messages::table)
             .values(message)
-            .execute(&conn)
+            .on_conflict((messages::transaction_id, messages::seq))
+            .do_nothing()
+            .execute(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// inserts the messages with multi-row statements, messages stored already are skipped.
+    fn insert_messages(&self, messages: &[NewMessage]) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        let mut inserted = 0;
+        for chunk in messages.chunks(MAX_ROWS_PER_INSERT) {
+            inserted += diesel::insert_into(messages::table)
+                .values(chunk)
+                .on_conflict((messages::transaction_id, messages::seq))
+                .do_nothing()
+                .execute(&*conn)?;
+        }
+        Ok(inserted)
+    }
+
+    fn insert_transaction_signer(&self, signer: &NewTransactionSigner) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        diesel::insert_into(transaction_signers::table)
+            .values(signer)
+            .on_conflict((
+                transaction_signers::transaction_id,
+                transaction_signers::signer_seq,
+            ))
+            .do_nothing()
+            .execute(&*conn)
             .map_err(|e| e.into())
     }
+
+    /// inserts the signers with multi-row statements, signers stored already are skipped.
+    fn insert_transaction_signers(&self, signers: &[NewTransactionSigner]) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        let mut inserted = 0;
+        for chunk in signers.chunks(MAX_ROWS_PER_INSERT) {
+            inserted += diesel::insert_into(transaction_signers::table)
+                .values(chunk)
+                .on_conflict((
+                    transaction_signers::transaction_id,
+                    transaction_signers::signer_seq,
+                ))
+                .do_nothing()
+                .execute(&*conn)?;
+        }
+        Ok(inserted)
+    }
+
+    /// inserts the fee coins with multi-row statements, coins stored already are skipped.
+    fn insert_transaction_fees(&self, fees: &[NewTransactionFee]) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        let mut inserted = 0;
+        for chunk in fees.chunks(MAX_ROWS_PER_INSERT) {
+            inserted += diesel::insert_into(transaction_fees::table)
+                .values(chunk)
+                .on_conflict((transaction_fees::transaction_id, transaction_fees::seq))
+                .do_nothing()
+                .execute(&*conn)?;
+        }
+        Ok(inserted)
+    }
+
+    /// inserts the account if it doesn't exist yet, and returns the stored account.
+    fn upsert_account(&self, account: &NewAccount) -> Result<Account, Error> {
+        let conn = self.get_conn()?;
+        diesel::insert_into(accounts::table)
+            .values(account)
+            .on_conflict((accounts::chain_id, accounts::address))
+            .do_update()
+            .set(accounts::updated_at.eq(Some(account.inserted_at)))
+            .get_result::<Account>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// replaces the balances of the account with the given ones in a single transaction.
+    /// denoms which are not given any more are removed.
+    fn replace_account_balances(
+        &self,
+        account_id: i32,
+        balances: &[NewAccountBalance],
+    ) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<usize, Error, _>(|| {
+            let denoms = balances.iter().map(|b| b.denom.clone()).collect::<Vec<_>>();
+            diesel::delete(
+                account_balance::table.filter(
+                    account_balance::account_id
+                        .eq(account_id)
+                        .and(account_balance::denom.ne_all(denoms)),
+                ),
+            )
+            .execute(&*conn)?;
+
+            let mut updated = 0;
+            for balance in balances {
+                updated += diesel::insert_into(account_balance::table)
+                    .values(balance)
+                    .on_conflict((account_balance::account_id, account_balance::denom))
+                    .do_update()
+                    .set((
+                        account_balance::amount.eq(&balance.amount),
+                        account_balance::updated_at.eq(Some(balance.inserted_at)),
+                    ))
+                    .execute(&*conn)?;
+            }
+
+            Ok(updated)
+        })
+    }
+
+    /// inserts the validator set and its members in a single transaction.
+    /// a set starting at the same height is replaced.
+    fn insert_validator_set(
+        &self,
+        validator_set: &NewValidatorSet,
+        members: &[NewValidatorSetMember],
+    ) -> Result<ValidatorSet, Error> {
+        let conn = self.get_conn()?;
+        conn.transaction::<ValidatorSet, Error, _>(|| {
+            let new_set = diesel::insert_into(validator_sets::table)
+                .values(validator_set)
+                .on_conflict((validator_sets::chain_id, validator_sets::start_height))
+                .do_update()
+                .set((
+                    validator_sets::validators_hash.eq(validator_set.validators_hash.clone()),
+                    validator_sets::updated_at.eq(Some(validator_set.inserted_at)),
+                ))
+                .get_result::<ValidatorSet>(&*conn)?;
+
+            // members of a replaced set are stored again.
+            diesel::delete(
+                validator_set_members::table
+                    .filter(validator_set_members::validator_set_id.eq(new_set.id)),
+            )
+            .execute(&*conn)?;
+
+            let members = members
+                .iter()
+                .cloned()
+                .map(|member| NewValidatorSetMember {
+                    validator_set_id: new_set.id,
+                    ..member
+                })
+                .collect::<Vec<_>>();
+            if !members.is_empty() {
+                diesel::insert_into(validator_set_members::table)
+                    .values(&members)
+                    .execute(&*conn)?;
+            }
+
+            Ok(new_set)
+        })
+    }
+
+    /// stores the range, the end height and the reason are updated if it starts at a known height.
+    fn upsert_unindexable_range(
+        &self,
+        range: &NewUnindexableRange,
+    ) -> Result<UnindexableRange, Error> {
+        let conn = self.get_conn()?;
+        diesel::insert_into(unindexable_ranges::table)
+            .values(range)
+            .on_conflict((
+                unindexable_ranges::chain_id,
+                unindexable_ranges::start_height,
+            ))
+            .do_update()
+            .set((
+                unindexable_ranges::end_height.eq(range.end_height),
+                unindexable_ranges::reason.eq(range.reason.clone()),
+                unindexable_ranges::updated_at.eq(Some(range.inserted_at)),
+            ))
+            .get_result::<UnindexableRange>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// stores the cursor, an existing one is only moved forward.
+    fn advance_indexer_cursor(&self, cursor: &NewIndexerCursor) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        diesel::insert_into(indexer_cursors::table)
+            .values(cursor)
+            .on_conflict((indexer_cursors::chain_id, indexer_cursors::pipeline))
+            .do_update()
+            .set((
+                indexer_cursors::height.eq(diesel::dsl::sql::<diesel::sql_types::BigInt>(
+                    "GREATEST(indexer_cursors.height, excluded.height)",
+                )),
+                indexer_cursors::updated_at.eq(Some(cursor.inserted_at)),
+            ))
+            .execute(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// stores the cursor as it is, it may move an existing one backward.
+    fn upsert_indexer_cursor(&self, cursor: &NewIndexerCursor) -> Result<usize, Error> {
+        let conn = self.get_conn()?;
+        diesel::insert_into(indexer_cursors::table)
+            .values(cursor)
+            .on_conflict((indexer_cursors::chain_id, indexer_cursors::pipeline))
+            .do_update()
+            .set((
+                indexer_cursors::height.eq(cursor.height),
+                indexer_cursors::updated_at.eq(Some(cursor.inserted_at)),
+            ))
+            .execute(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// operations which run their own transaction are nested as savepoints.
+    fn within_transaction<R, F>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&dyn Storage) -> Result<R, Error>,
+    {
+        let conn = self.get_conn()?;
+        conn.build_transaction()
+            .repeatable_read()
+            .run::<R, Error, _>(|| {
+                f(&PersistenceStorage {
+                    db: TransactionDB::new(&*conn),
+                })
+            })
+    }
 }
 
-impl StorageReader for PersistenceStorage<BackendDB> {
+impl<T: Database> StorageReader for PersistenceStorage<T> {
     fn find_block_by_height(&self, chain_id: i32, height: i64) -> Result<Block, Error> {
         let conn = self.get_conn()?;
         all_blocks
             .filter(blocks::chain_id.eq(chain_id).and(blocks::height.eq(height)))
-            .first(&conn)
+            .first(&*conn)
             .map_err(|e| e.into())
     }
 
@@ -175,7 +662,7 @@ impl StorageReader for PersistenceStorage<BackendDB> {
             .order(blocks::height.desc())
             .limit(limit)
             .offset(offset)
-            .load::<Block>(&conn)
+            .load::<Block>(&*conn)
             .map_err(|e| e.into())
     }
 
@@ -184,7 +671,51 @@ impl StorageReader for PersistenceStorage<BackendDB> {
         all_blocks
             .filter(blocks::chain_id.eq(chain_id))
             .order(blocks::height.desc())
-            .first(&conn)
+            .first(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// returns ranges of missing heights between `from_height` and the latest stored block.
+    fn find_block_gaps(&self, chain_id: i32, from_height: i64) -> Result<Vec<BlockGap>, Error> {
+        let conn = self.get_conn()?;
+        // `from_height - 1` is added as a sentinel so that a gap right after `from_height` is detected as well.
+        diesel::sql_query(
+            "SELECT height + 1 AS start_height, next_height - 1 AS end_height
+            FROM (
+                SELECT height, LEAD(height) OVER (ORDER BY height) AS next_height
+                FROM (
+                    SELECT height FROM blocks WHERE chain_id = $1 AND height >= $2
+                    UNION ALL
+                    SELECT $2 - 1
+                ) AS heights
+            ) AS neighbours
+            WHERE next_height - height > 1
+            ORDER BY start_height",
+        )
+        .bind::<diesel::sql_types::Integer, _>(chain_id)
+        .bind::<diesel::sql_types::BigInt, _>(from_height)
+        .load::<BlockGap>(&*conn)
+        .map_err(|e| e.into())
+    }
+
+    /// returns the stored blocks between the given heights (inclusive) in ascending order.
+    fn list_blocks_in_range(
+        &self,
+        chain_id: i32,
+        from_height: i64,
+        to_height: i64,
+        limit: i64,
+    ) -> Result<Vec<Block>, Error> {
+        let conn = self.get_conn()?;
+        all_blocks
+            .filter(
+                blocks::chain_id
+                    .eq(chain_id)
+                    .and(blocks::height.between(from_height, to_height)),
+            )
+            .order(blocks::height.asc())
+            .limit(limit)
+            .load::<Block>(&*conn)
             .map_err(|e| e.into())
     }
 
@@ -192,13 +723,13 @@ impl StorageReader for PersistenceStorage<BackendDB> {
         let conn = self.get_conn()?;
         all_chains
             .filter(chains::chain_id.eq(chain_id))
-            .first(&conn)
+            .first(&*conn)
             .map_err(|e| e.into())
     }
 
     fn all_chains(&self) -> Result<Vec<Chain>, Error> {
         let conn = self.get_conn()?;
-        all_chains.load::<Chain>(&conn).map_err(|e| e.into())
+        all_chains.load::<Chain>(&*conn).map_err(|e| e.into())
     }
 
     fn list_transactions(&self, chain_id: i32, height: i64) -> Result<Vec<Transaction>, Error> {
@@ -208,15 +739,33 @@ impl StorageReader for PersistenceStorage<BackendDB> {
             .and(transactions::chain_id.eq(chain_id));
         all_transactions
             .filter(condition)
-            .load::<Transaction>(&conn)
+            .load::<Transaction>(&*conn)
             .map_err(|e| e.into())
     }
 
-    fn find_transaction_by_hash(&self, tx_hash: String) -> Result<Transaction, Error> {
+    fn find_transaction_by_hash(
+        &self,
+        chain_id: i32,
+        tx_hash: String,
+    ) -> Result<Transaction, Error> {
+        let conn = self.get_conn()?;
+        all_transactions
+            .filter(
+                transactions::chain_id
+                    .eq(chain_id)
+                    .and(transactions::transaction_hash.eq(tx_hash)),
+            )
+            .first(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// returns the transactions with the given hash in every chain, ordered by chain.
+    fn list_transactions_by_hash(&self, tx_hash: String) -> Result<Vec<Transaction>, Error> {
         let conn = self.get_conn()?;
         all_transactions
             .filter(transactions::transaction_hash.eq(tx_hash))
-            .first(&conn)
+            .order(transactions::chain_id.asc())
+            .load::<Transaction>(&*conn)
             .map_err(|e| e.into())
     }
 
@@ -224,15 +773,115 @@ impl StorageReader for PersistenceStorage<BackendDB> {
         let conn = self.get_conn()?;
         all_messages
             .filter(messages::transaction_id.eq(tx_id))
-            .load::<Message>(&conn)
+            .load::<Message>(&*conn)
             .map_err(|e| e.into())
     }
 
-    fn list_events_by_tx(&self, tx_hash: String) -> Result<Vec<Event>, Error> {
+    fn list_signers_by_tx(&self, tx_id: i32) -> Result<Vec<TransactionSigner>, Error> {
+        let conn = self.get_conn()?;
+        transaction_signers::table
+            .filter(transaction_signers::transaction_id.eq(tx_id))
+            .order(transaction_signers::signer_seq.asc())
+            .load::<TransactionSigner>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn list_fees_by_tx(&self, tx_id: i32) -> Result<Vec<TransactionFee>, Error> {
+        let conn = self.get_conn()?;
+        transaction_fees::table
+            .filter(transaction_fees::transaction_id.eq(tx_id))
+            .order(transaction_fees::seq.asc())
+            .load::<TransactionFee>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn list_events_by_tx(&self, chain_id: i32, tx_hash: String) -> Result<Vec<Event>, Error> {
         let conn = self.get_conn()?;
         all_events
-            .filter(events::tx_hash.eq(tx_hash))
-            .load::<Event>(&conn)
+            .filter(
+                events::chain_id
+                    .eq(chain_id)
+                    .and(events::tx_hash.eq(tx_hash)),
+            )
+            .load::<Event>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn find_account_by_address(&self, chain_id: i32, address: String) -> Result<Account, Error> {
+        let conn = self.get_conn()?;
+        accounts::table
+            .filter(
+                accounts::chain_id
+                    .eq(chain_id)
+                    .and(accounts::address.eq(address)),
+            )
+            .first(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn list_account_balances(&self, account_id: i32) -> Result<Vec<AccountBalance>, Error> {
+        let conn = self.get_conn()?;
+        account_balance::table
+            .filter(account_balance::account_id.eq(account_id))
+            .order(account_balance::denom.asc())
+            .load::<AccountBalance>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// returns the validator set which is active at the given height,
+    /// that is the latest one starting at or below it.
+    fn find_validator_set_at_height(
+        &self,
+        chain_id: i32,
+        height: i64,
+    ) -> Result<ValidatorSet, Error> {
+        let conn = self.get_conn()?;
+        validator_sets::table
+            .filter(
+                validator_sets::chain_id
+                    .eq(chain_id)
+                    .and(validator_sets::start_height.le(height)),
+            )
+            .order(validator_sets::start_height.desc())
+            .first(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// returns the members of the validator set, sorted by voting power in descending order.
+    fn list_validator_set_members(
+        &self,
+        validator_set_id: i32,
+    ) -> Result<Vec<ValidatorSetMember>, Error> {
+        let conn = self.get_conn()?;
+        validator_set_members::table
+            .filter(validator_set_members::validator_set_id.eq(validator_set_id))
+            .order((
+                validator_set_members::voting_power.desc(),
+                validator_set_members::address.asc(),
+            ))
+            .load::<ValidatorSetMember>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    /// returns the unindexable ranges of the chain, ordered by start height.
+    fn list_unindexable_ranges(&self, chain_id: i32) -> Result<Vec<UnindexableRange>, Error> {
+        let conn = self.get_conn()?;
+        unindexable_ranges::table
+            .filter(unindexable_ranges::chain_id.eq(chain_id))
+            .order(unindexable_ranges::start_height.asc())
+            .load::<UnindexableRange>(&*conn)
+            .map_err(|e| e.into())
+    }
+
+    fn find_indexer_cursor(&self, chain_id: i32, pipeline: &str) -> Result<IndexerCursor, Error> {
+        let conn = self.get_conn()?;
+        indexer_cursors::table
+            .filter(
+                indexer_cursors::chain_id
+                    .eq(chain_id)
+                    .and(indexer_cursors::pipeline.eq(pipeline)),
+            )
+            .first(&*conn)
             .map_err(|e| e.into())
     }
 }
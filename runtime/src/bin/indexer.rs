@@ -1,5 +1,6 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use cosmscan_indexer::{config::Config, indexer::Indexer};
+use cosmscan_models::{config::DBDriver, storage::Storage};
 
 use log::{error, info};
 
@@ -8,6 +9,38 @@ use log::{error, info};
 struct Cli {
     #[clap(short, long, value_parser)]
     filename: String,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Deletes the indexed blocks in the given range and indexes them again.
+    Reindex {
+        /// required if several chains are configured.
+        #[clap(long, value_parser)]
+        chain_id: Option<String>,
+
+        #[clap(long, value_parser)]
+        from: i64,
+
+        #[clap(long, value_parser)]
+        to: i64,
+    },
+
+    /// Moves the cursor of a derived pipeline, e.g. validators, it syncs again from the next height.
+    ResetCursor {
+        /// required if several chains are configured.
+        #[clap(long, value_parser)]
+        chain_id: Option<String>,
+
+        #[clap(long, value_parser)]
+        pipeline: String,
+
+        #[clap(long, value_parser)]
+        height: i64,
+    },
 }
 
 #[tokio::main]
@@ -20,15 +53,85 @@ async fn main() {
     let config = Config::from_file(cli.filename.clone())
         .unwrap_or_else(|_| panic!("wrong config file location: {}", cli.filename));
 
+    // commands on a single chain don't connect to the nodes of the other chains.
+    let config = match &cli.command {
+        Some(Command::Reindex { chain_id, .. }) | Some(Command::ResetCursor { chain_id, .. }) => {
+            config
+                .select_chain(chain_id.as_deref())
+                .unwrap_or_else(|e| panic!("invalid chain: {}", e))
+        }
+        None => config,
+    };
+
+    match config.db.driver() {
+        DBDriver::Postgres => run(Indexer::new(config).await.unwrap(), cli.command).await,
+        #[cfg(feature = "sqlite")]
+        DBDriver::Sqlite => run(Indexer::new_sqlite(config).await.unwrap(), cli.command).await,
+        #[cfg(not(feature = "sqlite"))]
+        DBDriver::Sqlite => panic!("sqlite support isn't built in, build with `--features sqlite`"),
+    }
+}
+
+async fn run<S: Storage + Send + Sync + 'static>(indexer: Indexer<S>, command: Option<Command>) {
+    match command {
+        Some(Command::Reindex { chain_id, from, to }) => {
+            match indexer.reindex(chain_id, from, to).await {
+                Ok(_) => info!("reindex finished | from: {}, to: {}", from, to),
+                Err(e) => {
+                    error!("failed to reindex blocks: {:?}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(Command::ResetCursor {
+            chain_id,
+            pipeline,
+            height,
+        }) => {
+            if let Err(e) = indexer.reset_cursor(chain_id, &pipeline, height).await {
+                error!("failed to reset the cursor: {:?}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        None => {}
+    }
+
+    // stop gracefully on SIGINT/SIGTERM, the fetched blocks are committed before exiting.
+    let shutdown = indexer.shutdown_token();
+    tokio::spawn(async move {
+        wait_for_signal().await;
+        info!("received a shutdown signal, committing the fetched blocks");
+        shutdown.cancel();
+    });
+
     // start a indexer
-    let indexer = Indexer::new(config).await.unwrap();
     match indexer.start().await {
         Ok(_) => {
+            // closes the connection pool.
+            drop(indexer);
             info!("indexer finished");
         }
         Err(e) => {
             error!("unexpected error during fetching blockchain: {:?}", e);
-            panic!("teardown the indexer");
+            std::process::exit(1);
         }
     }
 }
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = signal(SignalKind::terminate()).expect("failed to listen to SIGTERM");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = sigterm.recv() => {},
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -1,5 +1,6 @@
 use api_server::{server::ApiServer, Config};
 use clap::Parser;
+use cosmscan_models::{config::DBDriver, storage::Storage};
 
 #[derive(Parser)]
 #[clap(author, version, about)]
@@ -18,7 +19,16 @@ async fn main() {
     let config = Config::from_file(cli.filename.clone())
         .unwrap_or_else(|_| panic!("wrong config file location: {}", cli.filename));
 
-    let server = ApiServer::new(config);
+    match config.db.driver() {
+        DBDriver::Postgres => run(ApiServer::new(config)).await,
+        #[cfg(feature = "sqlite")]
+        DBDriver::Sqlite => run(ApiServer::new_sqlite(config)).await,
+        #[cfg(not(feature = "sqlite"))]
+        DBDriver::Sqlite => panic!("sqlite support isn't built in, build with `--features sqlite`"),
+    }
+}
+
+async fn run<S: Storage + Send + Sync + 'static>(server: ApiServer<S>) {
     match server.run().await {
         Ok(_) => log::info!("server has been stopped"),
         Err(e) => log::error!("server stopped unexpectedly {}", e),